@@ -0,0 +1,92 @@
+//! Backgrounds composited behind the avatar in static fallback mode.
+
+use crate::error::{CliError, Result};
+use crate::validation::{is_image_reference, is_video_reference};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A background image or video for the static fallback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Background {
+    /// Still image, looped for the audio duration.
+    Image(PathBuf),
+    /// Video, restarted from the beginning until the audio ends.
+    Video(PathBuf),
+}
+
+impl Background {
+    /// Validates `path` as a background (PNG, JPEG, WebP, BMP, or MP4).
+    pub fn from_path(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(CliError::Video(format!(
+                "Background not found: {}",
+                path.display()
+            )));
+        }
+        if is_image_reference(path) {
+            Ok(Self::Image(path.to_path_buf()))
+        } else if is_video_reference(path) {
+            Ok(Self::Video(path.to_path_buf()))
+        } else {
+            Err(CliError::Video(format!(
+                "Unsupported background format: {}. Supported formats: PNG, JPEG, WebP, BMP, MP4",
+                path.display()
+            )))
+        }
+    }
+
+    /// FFmpeg input arguments that loop the background indefinitely.
+    pub fn input_args(&self) -> Vec<String> {
+        let (flag, value, path) = match self {
+            Self::Image(path) => ("-loop", "1", path),
+            Self::Video(path) => ("-stream_loop", "-1", path),
+        };
+        vec![
+            flag.to_string(),
+            value.to_string(),
+            "-i".to_string(),
+            path.to_string_lossy().into_owned(),
+        ]
+    }
+}
+
+impl FromStr for Background {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_path(Path::new(s))
+    }
+}
+
+/// Filter graph centring input 1 (the avatar) over input 0 (the background).
+///
+/// `format=auto` keeps the avatar's alpha channel when blending.
+pub const OVERLAY_FILTER: &str = "[0:v][1:v]overlay=(W-w)/2:(H-h)/2:format=auto[v]";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_background_format_validated() {
+        let dir = tempdir().unwrap();
+        let image = dir.path().join("bg.jpg");
+        let video = dir.path().join("bg.mp4");
+        let text = dir.path().join("bg.txt");
+        for path in [&image, &video, &text] {
+            std::fs::write(path, b"x").unwrap();
+        }
+
+        assert_eq!(
+            Background::from_path(&image).unwrap(),
+            Background::Image(image.clone())
+        );
+        assert_eq!(
+            Background::from_path(&video).unwrap().input_args()[..2],
+            ["-stream_loop", "-1"]
+        );
+        assert!(Background::from_path(&text).is_err());
+        assert!(Background::from_path(&dir.path().join("missing.png")).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+//! Video encoders and their availability in the installed FFmpeg.
+//!
+//! FFmpeg builds often lack libx265, libvpx, or the NVENC encoders, which
+//! otherwise surfaces as "Unknown encoder" halfway through a render.
+
+use super::container::Container;
+use crate::error::{CliError, Result};
+use crate::ffmpeg::FfmpegConfig;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Pixel formats accepted by `--pix-fmt`.
+pub const SUPPORTED_PIX_FMTS: &[&str] = &[
+    "yuv420p",
+    "yuvj420p",
+    "yuv422p",
+    "yuv444p",
+    "yuv420p10le",
+    "nv12",
+];
+
+/// A video codec selectable with `--codec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VideoCodec {
+    /// H.264 via libx264.
+    H264,
+    /// H.265/HEVC via libx265.
+    H265,
+    /// VP9 via libvpx.
+    Vp9,
+    /// VP8 via libvpx, for FFmpeg builds without VP9.
+    Vp8,
+    /// H.264 on an NVIDIA GPU.
+    H264Nvenc,
+    /// H.265/HEVC on an NVIDIA GPU.
+    HevcNvenc,
+}
+
+impl VideoCodec {
+    /// Name of the FFmpeg encoder implementing this codec.
+    pub fn encoder(self) -> &'static str {
+        match self {
+            Self::H264 => "libx264",
+            Self::H265 => "libx265",
+            Self::Vp9 => "libvpx-vp9",
+            Self::Vp8 => "libvpx",
+            Self::H264Nvenc => "h264_nvenc",
+            Self::HevcNvenc => "hevc_nvenc",
+        }
+    }
+
+    /// Encoder and quality arguments for this codec.
+    pub fn video_args(self) -> Vec<String> {
+        let quality: &[&str] = match self {
+            Self::H264 => &["-preset", "medium", "-crf", "23"],
+            Self::H265 => &["-preset", "medium", "-crf", "28"],
+            Self::Vp9 => &["-crf", "32", "-b:v", "0"],
+            Self::Vp8 => &["-crf", "10", "-b:v", "1M"],
+            Self::H264Nvenc => &["-preset", "p5", "-cq", "23"],
+            Self::HevcNvenc => &["-preset", "p5", "-cq", "28"],
+        };
+        let mut args = vec!["-c:v".to_string(), self.encoder().to_string()];
+        args.extend(quality.iter().map(|a| a.to_string()));
+        args
+    }
+
+    /// Codecs tried in order, with `--codec-fallback`, when this one's
+    /// encoder is missing.
+    pub fn fallbacks(self) -> &'static [VideoCodec] {
+        match self {
+            Self::H264 => &[],
+            Self::H265 => &[Self::H264],
+            Self::Vp9 => &[Self::Vp8, Self::H264],
+            Self::Vp8 => &[Self::H264],
+            Self::H264Nvenc => &[Self::H264],
+            Self::HevcNvenc => &[Self::H265, Self::H264],
+        }
+    }
+}
+
+impl fmt::Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => write!(f, "{self:?}"),
+        }
+    }
+}
+
+/// An output pixel format such as `yuv420p` (the widely playable default).
+///
+/// Some embedded players only decode full-range `yuvj420p`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelFormat(String);
+
+impl Default for PixelFormat {
+    fn default() -> Self {
+        Self("yuv420p".to_string())
+    }
+}
+
+impl PixelFormat {
+    /// FFmpeg name of the format.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PixelFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if SUPPORTED_PIX_FMTS.contains(&s) {
+            Ok(Self(s.to_string()))
+        } else {
+            Err(CliError::Video(format!(
+                "Unsupported pixel format '{s}'. Supported: {}",
+                SUPPORTED_PIX_FMTS.join(", ")
+            )))
+        }
+    }
+}
+
+/// Encoder names listed in `ffmpeg -encoders` output.
+pub fn parse_encoders(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns each supported codec and whether the FFmpeg build provides it.
+pub fn available_codecs(ffmpeg: &FfmpegConfig) -> Result<CodecReport> {
+    let output = ffmpeg
+        .ffmpeg()
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .map_err(|e| CliError::Video(format!("Failed to run ffmpeg: {e}")))?;
+    if !output.status.success() {
+        return Err(CliError::Video("ffmpeg -encoders failed".to_string()));
+    }
+    Ok(CodecReport::from_encoders(&parse_encoders(
+        &String::from_utf8_lossy(&output.stdout),
+    )))
+}
+
+/// Fails unless `codec` fits the output container and FFmpeg can encode it.
+pub fn check_codec(ffmpeg: &FfmpegConfig, codec: VideoCodec, output: Option<&Path>) -> Result<()> {
+    let container = output.map_or(Container::Mp4, Container::for_output);
+    if !container.supports(codec) {
+        return Err(CliError::Video(format!(
+            "--codec {codec} cannot be written to a {container:?} file"
+        )));
+    }
+    let report = available_codecs(ffmpeg)?;
+    if report.is_available(codec) {
+        return Ok(());
+    }
+    let available: Vec<_> = report.available().map(|c| c.to_string()).collect();
+    Err(CliError::Video(format!(
+        "--codec {codec} needs the {} encoder, which this FFmpeg build lacks. Available: {} \
+         (see --list-codecs)",
+        codec.encoder(),
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        }
+    )))
+}
+
+/// Checks `codec` against every output and returns the codec to encode with.
+///
+/// With `fallback`, a missing encoder is replaced by the first codec in its
+/// [`VideoCodec::fallbacks`] cascade that FFmpeg provides and every output
+/// container can carry; without it, a missing encoder is an error.
+pub fn resolve_codec(
+    ffmpeg: &FfmpegConfig,
+    codec: VideoCodec,
+    outputs: &[&Path],
+    fallback: bool,
+) -> Result<VideoCodec> {
+    if !fallback {
+        if outputs.is_empty() {
+            check_codec(ffmpeg, codec, None)?;
+        }
+        for output in outputs {
+            check_codec(ffmpeg, codec, Some(output))?;
+        }
+        return Ok(codec);
+    }
+    let mut containers: Vec<Container> = outputs.iter().map(|o| Container::for_output(o)).collect();
+    if containers.is_empty() {
+        containers.push(Container::Mp4);
+    }
+    let chosen = available_codecs(ffmpeg)?
+        .best_available(codec, &containers)
+        .ok_or_else(|| {
+            CliError::Video(format!(
+                "--codec {codec} and its fallbacks are unavailable in this FFmpeg build \
+                 (see --list-codecs)"
+            ))
+        })?;
+    if chosen != codec {
+        tracing::warn!(
+            "FFmpeg lacks the {} encoder; falling back from {codec} to {chosen}",
+            codec.encoder()
+        );
+    }
+    Ok(chosen)
+}
+
+/// Availability of each supported codec, as shown by `--list-codecs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecReport {
+    pub codecs: Vec<(VideoCodec, bool)>,
+}
+
+impl CodecReport {
+    /// Builds the report from the encoder names FFmpeg lists.
+    pub fn from_encoders(encoders: &[String]) -> Self {
+        let codecs = VideoCodec::value_variants()
+            .iter()
+            .map(|&codec| (codec, encoders.iter().any(|e| e == codec.encoder())))
+            .collect();
+        Self { codecs }
+    }
+
+    /// Returns true if FFmpeg can encode `codec`.
+    pub fn is_available(&self, codec: VideoCodec) -> bool {
+        self.codecs.iter().any(|&(c, ok)| c == codec && ok)
+    }
+
+    /// `codec`, or the first of its fallbacks, that FFmpeg provides and all
+    /// `containers` can carry.
+    pub fn best_available(
+        &self,
+        codec: VideoCodec,
+        containers: &[Container],
+    ) -> Option<VideoCodec> {
+        std::iter::once(codec)
+            .chain(codec.fallbacks().iter().copied())
+            .find(|&c| self.is_available(c) && containers.iter().all(|k| k.supports(c)))
+    }
+
+    /// Codecs the FFmpeg build provides.
+    pub fn available(&self) -> impl Iterator<Item = VideoCodec> + '_ {
+        self.codecs.iter().filter(|(_, ok)| *ok).map(|&(c, _)| c)
+    }
+}
+
+impl fmt::Display for CodecReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Codecs in this FFmpeg build:")?;
+        for (codec, available) in &self.codecs {
+            let status = if *available { "available" } else { "missing" };
+            writeln!(
+                f,
+                "  {:<12} {:<12} {status}",
+                codec.to_string(),
+                codec.encoder()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_ENCODERS: &str = "\
+Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ .F.... = Frame-level multithreading
+ ------
+ V....D a64multi             Multicolor charset for Commodore 64 (codec a64_multi)
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V....D h264_nvenc           NVIDIA NVENC H.264 encoder (codec h264)
+ V....D libvpx-vp9           libvpx VP9 (codec vp9)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+
+    #[test]
+    fn test_parse_encoders_skips_legend() {
+        let encoders = parse_encoders(SAMPLE_ENCODERS);
+        assert_eq!(
+            encoders,
+            ["a64multi", "libx264", "h264_nvenc", "libvpx-vp9", "aac"]
+        );
+    }
+
+    #[test]
+    fn test_report_marks_missing_codecs() {
+        let report = CodecReport::from_encoders(&parse_encoders(SAMPLE_ENCODERS));
+        let available: Vec<_> = report.available().collect();
+        assert_eq!(
+            available,
+            [VideoCodec::H264, VideoCodec::Vp9, VideoCodec::H264Nvenc]
+        );
+        assert!(!report.is_available(VideoCodec::H265));
+
+        let text = report.to_string();
+        assert!(text.contains("h264-nvenc"));
+        assert!(
+            text.lines()
+                .any(|l| l.contains("libx265") && l.ends_with("missing"))
+        );
+    }
+
+    #[test]
+    fn test_missing_libx265_falls_back_to_h264() {
+        let report = CodecReport::from_encoders(&parse_encoders(SAMPLE_ENCODERS));
+
+        assert_eq!(
+            report.best_available(VideoCodec::H265, &[Container::Mp4]),
+            Some(VideoCodec::H264)
+        );
+        assert_eq!(
+            report.best_available(VideoCodec::HevcNvenc, &[Container::Mkv]),
+            Some(VideoCodec::H264)
+        );
+    }
+
+    #[test]
+    fn test_vp9_cascade_respects_container() {
+        let vp8_only = CodecReport::from_encoders(&["libvpx".to_string(), "libx264".to_string()]);
+        assert_eq!(
+            vp8_only.best_available(VideoCodec::Vp9, &[Container::Webm]),
+            Some(VideoCodec::Vp8)
+        );
+
+        let h264_only = CodecReport::from_encoders(&["libx264".to_string()]);
+        assert_eq!(
+            h264_only.best_available(VideoCodec::Vp9, &[Container::Mp4]),
+            Some(VideoCodec::H264)
+        );
+        assert_eq!(
+            h264_only.best_available(VideoCodec::Vp9, &[Container::Webm]),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Output containers and the codecs each one can carry.
+
+use super::codec::{PixelFormat, VideoCodec};
+use crate::error::{CliError, Result};
+use std::path::Path;
+
+/// Output file extensions accepted by [`Container::from_path`].
+pub const SUPPORTED_CONTAINERS: &[&str] = &["mp4", "mov", "mkv", "webm", "gif"];
+
+/// A video or animation container the assembler can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Mp4,
+    Mov,
+    Mkv,
+    /// WebM only carries VP8/VP9/AV1 video and Vorbis/Opus audio.
+    Webm,
+    /// Animated GIF, which has no audio track.
+    Gif,
+}
+
+impl Container {
+    /// Looks up a container by file extension (case-insensitive).
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "mp4" => Some(Self::Mp4),
+            "mov" => Some(Self::Mov),
+            "mkv" => Some(Self::Mkv),
+            "webm" => Some(Self::Webm),
+            "gif" => Some(Self::Gif),
+            _ => None,
+        }
+    }
+
+    /// Detects the container from the output path's extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        Self::from_extension(ext).ok_or_else(|| {
+            CliError::InvalidOutputPath(format!(
+                "{} has no supported video container extension. Supported: {}",
+                path.display(),
+                SUPPORTED_CONTAINERS.join(", ")
+            ))
+        })
+    }
+
+    /// Container for an output path, falling back to MP4.
+    ///
+    /// Named pipes have no extension to go by and are always written as MP4.
+    pub fn for_output(path: &Path) -> Self {
+        Self::from_path(path).unwrap_or(Self::Mp4)
+    }
+
+    /// File extension (and format name) for this container.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mov => "mov",
+            Self::Mkv => "mkv",
+            Self::Webm => "webm",
+            Self::Gif => "gif",
+        }
+    }
+
+    /// Returns true if this container can carry `codec`.
+    pub fn supports(self, codec: VideoCodec) -> bool {
+        match self {
+            Self::Webm => matches!(codec, VideoCodec::Vp9 | VideoCodec::Vp8),
+            Self::Gif => false,
+            Self::Mp4 | Self::Mov | Self::Mkv => true,
+        }
+    }
+
+    /// Returns true if this container can carry `audio_codec` (an ffprobe
+    /// codec name) without re-encoding it.
+    pub fn accepts_audio(self, audio_codec: &str) -> bool {
+        let pcm = audio_codec.starts_with("pcm_");
+        match self {
+            Self::Mp4 => matches!(audio_codec, "aac" | "mp3" | "alac"),
+            Self::Mov => matches!(audio_codec, "aac" | "mp3" | "alac") || pcm,
+            Self::Mkv => matches!(audio_codec, "aac" | "mp3" | "opus" | "vorbis" | "flac") || pcm,
+            Self::Webm => matches!(audio_codec, "opus" | "vorbis"),
+            Self::Gif => false,
+        }
+    }
+
+    /// Video and audio codec arguments compatible with this container.
+    pub fn codec_args(self) -> Vec<String> {
+        self.codec_args_with(None, None, &PixelFormat::default())
+    }
+
+    /// Codec arguments using `codec` instead of the container's default.
+    ///
+    /// The audio is stream-copied when `source_audio` (the input's codec
+    /// name) fits the container, and re-encoded otherwise.
+    pub fn codec_args_with(
+        self,
+        codec: Option<VideoCodec>,
+        source_audio: Option<&str>,
+        pix_fmt: &PixelFormat,
+    ) -> Vec<String> {
+        let (default, audio) = match self {
+            Self::Mp4 | Self::Mov | Self::Mkv => (VideoCodec::H264, "aac"),
+            Self::Webm => (VideoCodec::Vp9, "libopus"),
+            Self::Gif => return vec!["-an".to_string()],
+        };
+        let mut args = codec.unwrap_or(default).video_args();
+        let audio_args: &[&str] = match source_audio {
+            Some(source) if self.accepts_audio(source) => &["-c:a", "copy"],
+            _ => &["-c:a", audio, "-b:a", "128k"],
+        };
+        args.extend(audio_args.iter().map(|a| a.to_string()));
+        args.extend(["-pix_fmt".to_string(), pix_fmt.to_string()]);
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_containers_accepted() {
+        for ext in SUPPORTED_CONTAINERS {
+            let path = format!("out.{ext}");
+            assert!(Container::from_path(Path::new(&path)).is_ok(), "{ext}");
+        }
+        assert_eq!(
+            Container::from_path(Path::new("OUT.MKV")).unwrap(),
+            Container::Mkv
+        );
+    }
+
+    #[test]
+    fn test_unsupported_container_rejected() {
+        for path in ["out.ogg", "out.wav", "out"] {
+            let err = Container::from_path(Path::new(path)).unwrap_err();
+            assert!(matches!(err, CliError::InvalidOutputPath(_)));
+            assert!(err.to_string().contains("mp4, mov, mkv, webm, gif"));
+        }
+    }
+
+    #[test]
+    fn test_codecs_match_container() {
+        assert!(Container::Mp4.codec_args().contains(&"libx264".to_string()));
+        assert!(
+            Container::Webm
+                .codec_args()
+                .contains(&"libopus".to_string())
+        );
+        assert_eq!(Container::Gif.codec_args(), ["-an"]);
+    }
+
+    #[test]
+    fn test_codec_override() {
+        let args =
+            Container::Mkv.codec_args_with(Some(VideoCodec::H265), None, &PixelFormat::default());
+        assert_eq!(args[..2], ["-c:v", "libx265"]);
+        assert!(args.contains(&"aac".to_string()));
+        assert!(!Container::Webm.supports(VideoCodec::H264));
+        assert!(Container::Webm.supports(VideoCodec::Vp9));
+    }
+
+    #[test]
+    fn test_audio_copied_only_when_container_accepts_it() {
+        let copy = Container::Mp4.codec_args_with(None, Some("aac"), &PixelFormat::default());
+        assert!(copy.windows(2).any(|w| w == ["-c:a", "copy"]));
+        assert!(!copy.contains(&"-b:a".to_string()));
+
+        let encode =
+            Container::Mp4.codec_args_with(None, Some("pcm_s16le"), &PixelFormat::default());
+        assert!(encode.windows(2).any(|w| w == ["-c:a", "aac"]));
+        assert!(Container::Mkv.accepts_audio("pcm_s16le"));
+        assert!(!Container::Webm.accepts_audio("aac"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Flattening of transparent server frames (`--frame-background`).
+//!
+//! Servers doing layered compositing may return RGBA frames with a
+//! transparent mouth region. None of the output pixel formats carry alpha,
+//! and FFmpeg would otherwise drop it and show whatever color sits under
+//! the transparent pixels, so such frames are composited over a solid
+//! color or an image before staging.
+
+use crate::error::{CliError, Result};
+use image::{ImageFormat, Rgb, RgbImage, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// What transparent frame pixels are composited over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameBackground {
+    /// A solid `#RRGGBB` color.
+    Color([u8; 3]),
+    /// An image, stretched to the frame size.
+    Image(PathBuf),
+}
+
+impl Default for FrameBackground {
+    fn default() -> Self {
+        Self::Color([0, 0, 0])
+    }
+}
+
+/// Parses `#RRGGBB` (or `RRGGBB`) as a color, anything else as an image path.
+impl FromStr for FrameBackground {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+            return Ok(Self::Color([channel(0), channel(2), channel(4)]));
+        }
+        let path = Path::new(s);
+        if !path.exists() {
+            return Err(CliError::Video(format!(
+                "Frame background '{s}' is neither a #RRGGBB color nor an existing image"
+            )));
+        }
+        Ok(Self::Image(path.to_path_buf()))
+    }
+}
+
+/// A loaded background, ready to composite frames onto.
+#[derive(Debug, Clone)]
+pub(super) enum Fill {
+    Color(Rgb<u8>),
+    Image(RgbImage),
+}
+
+impl FrameBackground {
+    /// Loads the background image, if any.
+    pub(super) fn load(&self) -> Result<Fill> {
+        match self {
+            Self::Color(rgb) => Ok(Fill::Color(Rgb(*rgb))),
+            Self::Image(path) => image::open(path)
+                .map(|img| Fill::Image(img.to_rgb8()))
+                .map_err(|e| {
+                    CliError::Video(format!(
+                        "Failed to load frame background {}: {e}",
+                        path.display()
+                    ))
+                }),
+        }
+    }
+}
+
+/// Returns true if the PNG header declares an alpha channel.
+///
+/// Checks the IHDR color type (grayscale+alpha or RGBA) without decoding.
+fn png_has_alpha(png: &[u8]) -> bool {
+    png.starts_with(b"\x89PNG\r\n\x1a\n") && matches!(png.get(25), Some(4 | 6))
+}
+
+/// Composites a PNG frame with alpha over `fill`, returning an opaque PNG.
+///
+/// Frames without an alpha channel are returned as `None`, untouched.
+pub(super) fn flatten_frame(index: usize, png: &[u8], fill: &Fill) -> Result<Option<Vec<u8>>> {
+    if !png_has_alpha(png) {
+        return Ok(None);
+    }
+    let invalid =
+        |e: image::ImageError| CliError::Video(format!("Failed to flatten frame {index}: {e}"));
+    let frame: RgbaImage = image::load_from_memory_with_format(png, ImageFormat::Png)
+        .map_err(invalid)?
+        .to_rgba8();
+    let (width, height) = frame.dimensions();
+    let background = match fill {
+        Fill::Color(rgb) => RgbImage::from_pixel(width, height, *rgb),
+        Fill::Image(img) if img.dimensions() == (width, height) => img.clone(),
+        Fill::Image(img) => {
+            image::imageops::resize(img, width, height, image::imageops::FilterType::Triangle)
+        }
+    };
+    let flat = RgbImage::from_fn(width, height, |x, y| {
+        let [r, g, b, a] = frame.get_pixel(x, y).0;
+        let under = background.get_pixel(x, y).0;
+        let alpha = u16::from(a);
+        let blend = |top: u8, bottom: u8| {
+            ((u16::from(top) * alpha + u16::from(bottom) * (255 - alpha) + 127) / 255) as u8
+        };
+        Rgb([blend(r, under[0]), blend(g, under[1]), blend(b, under[2])])
+    });
+    let mut out = Vec::new();
+    flat.write_to(&mut std::io::Cursor::new(&mut out), ImageFormat::Png)
+        .map_err(invalid)?;
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn png(img: &RgbaImage) -> Vec<u8> {
+        let mut out = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_rgba_frame_flattened_over_color() {
+        let mut frame = RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 255]));
+        frame.put_pixel(1, 0, Rgba([255, 0, 0, 0]));
+        let fill = "#00ff00"
+            .parse::<FrameBackground>()
+            .unwrap()
+            .load()
+            .unwrap();
+
+        let flat = flatten_frame(0, &png(&frame), &fill).unwrap().unwrap();
+        let flat = image::load_from_memory(&flat).unwrap();
+
+        assert_eq!(flat.color(), image::ColorType::Rgb8);
+        let flat = flat.to_rgb8();
+        assert_eq!(flat.get_pixel(0, 0), &Rgb([255, 0, 0]));
+        assert_eq!(flat.get_pixel(1, 0), &Rgb([0, 255, 0]));
+    }
+
+    #[test]
+    fn test_half_alpha_blends_over_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let bg_path = dir.path().join("bg.png");
+        RgbImage::from_pixel(4, 4, Rgb([0, 0, 200]))
+            .save(&bg_path)
+            .unwrap();
+        let fill = FrameBackground::from_str(bg_path.to_str().unwrap())
+            .unwrap()
+            .load()
+            .unwrap();
+        let frame = RgbaImage::from_pixel(2, 2, Rgba([200, 0, 0, 128]));
+
+        let flat = flatten_frame(0, &png(&frame), &fill).unwrap().unwrap();
+        let pixel = *image::load_from_memory(&flat)
+            .unwrap()
+            .to_rgb8()
+            .get_pixel(1, 1);
+
+        assert_eq!(pixel, Rgb([100, 0, 100]));
+    }
+
+    #[test]
+    fn test_opaque_frames_untouched() {
+        let mut rgb = Vec::new();
+        RgbImage::new(2, 2)
+            .write_to(&mut std::io::Cursor::new(&mut rgb), ImageFormat::Png)
+            .unwrap();
+        let fill = FrameBackground::default().load().unwrap();
+
+        assert!(flatten_frame(0, &rgb, &fill).unwrap().is_none());
+        assert!("not-a-color-or-file".parse::<FrameBackground>().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Filename templates for staged frames.
+
+use crate::error::{CliError, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Default zero-padding width for frame indices.
+const DEFAULT_WIDTH: usize = 5;
+
+/// A frame filename template such as `render.{index:4}.png`.
+///
+/// The template must contain exactly one `{index}` placeholder, optionally
+/// with a padding width (`{index:4}`). Indices start at `start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramePattern {
+    prefix: String,
+    suffix: String,
+    width: usize,
+    start: u32,
+}
+
+impl Default for FramePattern {
+    fn default() -> Self {
+        Self {
+            prefix: "frame_".to_string(),
+            suffix: ".png".to_string(),
+            width: DEFAULT_WIDTH,
+            start: 0,
+        }
+    }
+}
+
+impl FramePattern {
+    /// Sets the number of the first frame (e.g. 1 for one-based naming).
+    pub fn with_start(mut self, start: u32) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Number of the first frame.
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    /// Filename for the zero-based frame `index`.
+    pub fn filename(&self, index: usize) -> String {
+        let number = index + self.start as usize;
+        format!(
+            "{}{number:0width$}{}",
+            self.prefix,
+            self.suffix,
+            width = self.width
+        )
+    }
+
+    /// Equivalent FFmpeg image2 input pattern (e.g. `render.%04d.png`).
+    pub fn ffmpeg_pattern(&self) -> String {
+        format!(
+            "{}%0{}d{}",
+            self.prefix.replace('%', "%%"),
+            self.width,
+            self.suffix.replace('%', "%%")
+        )
+    }
+}
+
+impl fmt::Display for FramePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{{index:{}}}{}", self.prefix, self.width, self.suffix)
+    }
+}
+
+impl FromStr for FramePattern {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| CliError::InvalidFramePattern(format!("'{s}': {reason}"));
+
+        if s.matches("{index").count() != 1 {
+            return Err(invalid("must contain exactly one {index} placeholder"));
+        }
+
+        let open = s.find("{index").unwrap();
+        let close = s[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| invalid("unterminated placeholder"))?;
+
+        let spec = &s[open + "{index".len()..close];
+        let width = match spec.strip_prefix(':') {
+            Some(w) => w.parse().map_err(|_| invalid("padding must be a number"))?,
+            None if spec.is_empty() => DEFAULT_WIDTH,
+            None => return Err(invalid("expected {index} or {index:WIDTH}")),
+        };
+
+        let prefix = &s[..open];
+        let suffix = &s[close + 1..];
+        if prefix.contains('/') || suffix.contains('/') {
+            return Err(invalid("must be a file name, not a path"));
+        }
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            width,
+            start: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_pattern_matches_legacy_naming() {
+        let pattern = FramePattern::default();
+        assert_eq!(pattern.filename(0), "frame_00000.png");
+        assert_eq!(pattern.ffmpeg_pattern(), "frame_%05d.png");
+    }
+
+    #[test]
+    fn test_custom_pattern_with_one_based_offset() {
+        let pattern: FramePattern = "render.{index:4}.png".parse().unwrap();
+        let pattern = pattern.with_start(1);
+
+        assert_eq!(pattern.filename(0), "render.0001.png");
+        assert_eq!(pattern.filename(41), "render.0042.png");
+        assert_eq!(pattern.ffmpeg_pattern(), "render.%04d.png");
+    }
+
+    #[test]
+    fn test_pattern_display_round_trips() {
+        let pattern: FramePattern = "render.{index:4}.png".parse().unwrap();
+        assert_eq!(pattern.to_string(), "render.{index:4}.png");
+    }
+
+    #[test]
+    fn test_pattern_without_width_uses_default_padding() {
+        let pattern: FramePattern = "{index}.png".parse().unwrap();
+        assert_eq!(pattern.filename(7), "00007.png");
+    }
+
+    #[test]
+    fn test_invalid_patterns_rejected() {
+        assert!("frame.png".parse::<FramePattern>().is_err());
+        assert!("{index}_{index}.png".parse::<FramePattern>().is_err());
+        assert!("{index:x}.png".parse::<FramePattern>().is_err());
+        assert!("{index.png".parse::<FramePattern>().is_err());
+        assert!("dir/{index}.png".parse::<FramePattern>().is_err());
+    }
+}
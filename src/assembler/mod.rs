@@ -1,25 +1,257 @@
 //! Video assembly from frames and audio.
 
+pub mod background;
+pub mod codec;
+pub mod container;
+pub mod flatten;
+pub mod frame_pattern;
+pub mod output;
+pub mod pipe;
+pub mod server_video;
+pub mod sink;
+pub mod sync;
+mod work_dir;
+
+use crate::cli::Args;
+use crate::debug_bundle::SharedBundle;
 use crate::error::{CliError, Result};
+use crate::ffmpeg::FfmpegConfig;
 use crate::loader::{AudioData, ImageData};
-use base64::Engine;
-use std::path::Path;
-use std::process::Command;
+use crate::progress::{self, ProgressSink};
+pub use background::Background;
+pub use codec::{
+    CodecReport, PixelFormat, VideoCodec, available_codecs, check_codec, resolve_codec,
+};
+pub use container::Container;
+pub use flatten::FrameBackground;
+pub use frame_pattern::FramePattern;
+use indicatif::ProgressBar;
+pub use output::OutputTarget;
+pub use pipe::PipeSink;
+pub use server_video::write_server_video;
+pub use sink::{FfmpegSink, FrameSink, infer_into, write_frames};
+use std::path::{Path, PathBuf};
+pub use sync::SyncLength;
+use work_dir::WorkDir;
 
 /// Assembles frames into a video with audio.
 ///
 /// Uses FFmpeg command line for encoding.
 pub struct VideoAssembler {
     fps: u32,
-    temp_dir: tempfile::TempDir,
+    work_dir: WorkDir,
+    frames_dir: Option<PathBuf>,
+    frame_pattern: FramePattern,
+    sync_length: Option<(SyncLength, f32)>,
+    hold_last: Option<f64>,
+    background: Option<Background>,
+    video_codec: Option<VideoCodec>,
+    source_audio_codec: Option<String>,
+    pix_fmt: PixelFormat,
+    audio_language: Option<String>,
+    strip_metadata: bool,
+    progress_bar: bool,
+    frame_fill: flatten::Fill,
+    ffmpeg: FfmpegConfig,
+    debug_bundle: Option<SharedBundle>,
 }
 
 impl VideoAssembler {
     /// Creates a new video assembler.
     pub fn new(fps: u32) -> Result<Self> {
-        let temp_dir = tempfile::tempdir()
-            .map_err(|e| CliError::Video(format!("Failed to create temp dir: {e}")))?;
-        Ok(Self { fps, temp_dir })
+        Ok(Self::with_work_dir(fps, WorkDir::temp()?))
+    }
+
+    /// Creates an assembler staging files in `name` under the system temp
+    /// dir, reused across runs and never cleaned up (`--temp-name`).
+    pub fn named(fps: u32, name: &str) -> Result<Self> {
+        Ok(Self::with_work_dir(fps, WorkDir::named(name)?))
+    }
+
+    fn with_work_dir(fps: u32, work_dir: WorkDir) -> Self {
+        Self {
+            fps,
+            work_dir,
+            frames_dir: None,
+            frame_pattern: FramePattern::default(),
+            sync_length: None,
+            hold_last: None,
+            background: None,
+            video_codec: None,
+            source_audio_codec: None,
+            pix_fmt: PixelFormat::default(),
+            audio_language: None,
+            strip_metadata: false,
+            progress_bar: false,
+            frame_fill: flatten::Fill::Color(image::Rgb([0, 0, 0])),
+            ffmpeg: FfmpegConfig::default(),
+            debug_bundle: None,
+        }
+    }
+
+    /// Creates an assembler from the frame staging and muxing arguments.
+    ///
+    /// `audio_secs` is the audio length used by `--sync-length`.
+    pub fn from_args(
+        args: &Args,
+        fps: u32,
+        audio_secs: f32,
+        bundle: Option<&SharedBundle>,
+    ) -> Result<Self> {
+        let assembler = match &args.temp_name {
+            Some(name) => Self::named(fps, name)?,
+            None => Self::new(fps)?,
+        };
+        let mut assembler = assembler
+            .with_frame_pattern(args.frame_pattern.clone().with_start(args.frame_start))
+            .with_pix_fmt(args.pix_fmt.clone())
+            .with_ffmpeg(args.ffmpeg.clone())
+            .with_progress_bar(!args.quiet);
+        if let Some(codec) = args.codec {
+            assembler = assembler.with_video_codec(codec);
+        }
+        if let Some(mode) = args.sync_length {
+            assembler = assembler.with_sync_length(mode, audio_secs);
+        }
+        if let Some(secs) = args.hold_last {
+            assembler = assembler.with_hold_last(secs)?;
+        }
+        if let Some(background) = &args.background {
+            assembler = assembler.with_background(background.clone());
+        }
+        if let Some(background) = &args.frame_background {
+            assembler = assembler.with_frame_background(background)?;
+        }
+        if let Some(language) = &args.audio_language {
+            assembler = assembler.with_audio_language(language);
+        }
+        if args.strip_audio_metadata {
+            assembler = assembler.with_stripped_metadata();
+        }
+        if let Some(dir) = &args.keep_frames {
+            assembler = assembler.with_frames_dir(dir.clone())?;
+        }
+        if let Some(bundle) = bundle {
+            assembler = assembler.with_debug_bundle(bundle.clone());
+        }
+        Ok(assembler)
+    }
+
+    /// Stages frames in `dir` (kept after the run) instead of a temp dir.
+    pub fn with_frames_dir(mut self, dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            CliError::Video(format!(
+                "Failed to create frames dir {}: {e}",
+                dir.display()
+            ))
+        })?;
+        self.frames_dir = Some(dir);
+        Ok(self)
+    }
+
+    /// Sets the filename template for staged frames.
+    pub fn with_frame_pattern(mut self, pattern: FramePattern) -> Self {
+        self.frame_pattern = pattern;
+        self
+    }
+
+    /// Reconciles the video length with `audio_secs` of audio when muxing.
+    ///
+    /// Without this, the output ends with the shorter stream.
+    pub fn with_sync_length(mut self, mode: SyncLength, audio_secs: f32) -> Self {
+        self.sync_length = Some((mode, audio_secs));
+        self
+    }
+
+    /// Holds the last frame for `secs` after the video ends, padding the
+    /// audio with silence to match.
+    pub fn with_hold_last(mut self, secs: f64) -> Result<Self> {
+        sync::check_hold(secs)?;
+        self.hold_last = Some(secs);
+        Ok(self)
+    }
+
+    /// Composites the static fallback avatar over `background`.
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Encodes with `codec` instead of the container's default.
+    pub fn with_video_codec(mut self, codec: VideoCodec) -> Self {
+        self.video_codec = Some(codec);
+        self
+    }
+
+    /// Encodes with `pix_fmt` instead of `yuv420p`.
+    pub fn with_pix_fmt(mut self, pix_fmt: PixelFormat) -> Self {
+        self.pix_fmt = pix_fmt;
+        self
+    }
+
+    /// Composites transparent frames over `background` instead of black.
+    pub fn with_frame_background(mut self, background: &FrameBackground) -> Result<Self> {
+        self.frame_fill = background.load()?;
+        Ok(self)
+    }
+
+    /// Tags the output's audio stream with `language` (e.g. `en`).
+    pub fn with_audio_language(mut self, language: &str) -> Self {
+        self.audio_language = Some(language.to_string());
+        self
+    }
+
+    /// Drops metadata (artist, encoder, ...) carried by the inputs, so none
+    /// of it reaches the output. Tags set explicitly, such as the audio
+    /// language, are still written.
+    pub fn with_stripped_metadata(mut self) -> Self {
+        self.strip_metadata = true;
+        self
+    }
+
+    /// Shows a frame progress bar while frames are written.
+    pub fn with_progress_bar(mut self, enabled: bool) -> Self {
+        self.progress_bar = enabled;
+        self
+    }
+
+    /// A bar counting `total` frames, hidden unless enabled by
+    /// [`Self::with_progress_bar`].
+    pub fn frame_bar(&self, total: usize) -> ProgressBar {
+        progress::frame_bar(total, self.progress_bar)
+    }
+
+    /// Stream-copies the audio instead of re-encoding it when its codec
+    /// already fits the output container.
+    ///
+    /// The codec of `audio_path` is probed with ffprobe; if probing fails the
+    /// audio is re-encoded as usual.
+    pub fn with_audio_passthrough(mut self, audio_path: &Path) -> Self {
+        match crate::probe::audio_codec(&self.ffmpeg, audio_path) {
+            Ok(codec) => {
+                tracing::debug!("Source audio codec: {codec}");
+                self.source_audio_codec = Some(codec);
+            }
+            Err(e) => tracing::warn!("Could not probe audio codec, re-encoding: {e}"),
+        }
+        self
+    }
+
+    /// Runs the given FFmpeg binary instead of the one on `PATH`.
+    pub fn with_ffmpeg(mut self, ffmpeg: FfmpegConfig) -> Self {
+        self.ffmpeg = ffmpeg;
+        self
+    }
+
+    /// Directory where frames are staged.
+    pub fn frames_dir(&self) -> &Path {
+        self.frames_dir.as_deref().unwrap_or(self.work_dir.path())
+    }
+
+    /// Records FFmpeg invocations into the given debug bundle.
+    pub fn with_debug_bundle(mut self, bundle: SharedBundle) -> Self {
+        self.debug_bundle = Some(bundle);
+        self
     }
 
     /// Assembles a video from base64-encoded PNG frames and audio.
@@ -30,19 +262,30 @@ impl VideoAssembler {
         output_path: &Path,
     ) -> Result<()> {
         tracing::info!("Assembling {} frames into video", frames.len());
+        let bar = self.frame_bar(frames.len());
+        let sink = ProgressSink::new(self.sink(audio_path, output_path), |_| bar.inc(1));
+        let result = write_frames(frames, sink);
+        bar.finish_and_clear();
+        result
+    }
 
-        // Write frames to temp directory
+    /// Decodes base64 frames and writes them to the frames directory.
+    pub fn stage_frames(&self, frames: &[String]) -> Result<()> {
         for (i, frame_b64) in frames.iter().enumerate() {
-            let frame_path = self.temp_dir.path().join(format!("frame_{i:05}.png"));
-            let frame_bytes = base64::engine::general_purpose::STANDARD
-                .decode(frame_b64)
-                .map_err(|e| CliError::Video(format!("Failed to decode frame {i}: {e}")))?;
-            std::fs::write(&frame_path, frame_bytes)
-                .map_err(|e| CliError::Video(format!("Failed to write frame {i}: {e}")))?;
+            self.write_staged_frame(i, &sink::decode_frame(i, frame_b64)?)?;
         }
+        Ok(())
+    }
 
-        // Run FFmpeg to combine frames and audio
-        self.run_ffmpeg_frames(audio_path, output_path)
+    /// Writes the PNG bytes of frame `index` to the frames directory.
+    fn write_staged_frame(&self, index: usize, png: &[u8]) -> Result<()> {
+        let frame_path = self.frames_dir().join(self.frame_pattern.filename(index));
+        let flat = flatten::flatten_frame(index, png, &self.frame_fill)?;
+        let png = flat.as_deref().unwrap_or(png);
+        tracing::trace_span!("write_frame").in_scope(|| {
+            std::fs::write(&frame_path, png)
+                .map_err(|e| CliError::Video(format!("Failed to write frame {index}: {e}")))
+        })
     }
 
     /// Creates a video from a static image and audio (passthrough mode).
@@ -66,41 +309,14 @@ impl VideoAssembler {
         self.run_ffmpeg_static(image_path, audio_path, audio.duration_secs, output_path)
     }
 
-    fn run_ffmpeg_frames(&self, audio_path: &Path, output_path: &Path) -> Result<()> {
-        let frame_pattern = self.temp_dir.path().join("frame_%05d.png");
-
-        let status = Command::new("ffmpeg")
-            .args([
-                "-y", // Overwrite output
-                "-framerate",
-                &self.fps.to_string(),
-                "-i",
-                frame_pattern.to_str().unwrap(),
-                "-i",
-                audio_path.to_str().unwrap(),
-                "-c:v",
-                "libx264",
-                "-preset",
-                "medium",
-                "-crf",
-                "23",
-                "-c:a",
-                "aac",
-                "-b:a",
-                "128k",
-                "-pix_fmt",
-                "yuv420p",
-                "-shortest",
-                output_path.to_str().unwrap(),
-            ])
-            .output()
-            .map_err(|e| CliError::Video(format!("Failed to run ffmpeg: {e}")))?;
-
-        if !status.status.success() {
-            let stderr = String::from_utf8_lossy(&status.stderr);
-            return Err(CliError::Video(format!("FFmpeg failed: {stderr}")));
-        }
-
+    fn run_ffmpeg_frames(
+        &self,
+        frame_count: usize,
+        audio_path: &Path,
+        output_path: &Path,
+    ) -> Result<()> {
+        let args = self.frames_args(frame_count, audio_path, output_path)?;
+        self.run_ffmpeg(&args)?;
         tracing::info!("Video created: {}", output_path.display());
         Ok(())
     }
@@ -112,51 +328,146 @@ impl VideoAssembler {
         duration: f32,
         output_path: &Path,
     ) -> Result<()> {
-        let status = Command::new("ffmpeg")
-            .args([
-                "-y", // Overwrite output
-                "-loop",
-                "1",
-                "-i",
-                image_path.to_str().unwrap(),
-                "-i",
-                audio_path.to_str().unwrap(),
-                "-c:v",
-                "libx264",
-                "-preset",
-                "medium",
-                "-crf",
-                "23",
-                "-c:a",
-                "aac",
-                "-b:a",
-                "128k",
-                "-pix_fmt",
-                "yuv420p",
-                "-t",
-                &format!("{:.2}", duration),
-                "-shortest",
-                output_path.to_str().unwrap(),
-            ])
+        let args = self.static_args(image_path, audio_path, duration, output_path);
+        self.run_ffmpeg(&args)?;
+        tracing::info!("Static video created: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Builds FFmpeg arguments for encoding staged frames with audio.
+    fn frames_args(
+        &self,
+        frame_count: usize,
+        audio_path: &Path,
+        output_path: &Path,
+    ) -> Result<Vec<String>> {
+        let frame_pattern = self.frames_dir().join(self.frame_pattern.ffmpeg_pattern());
+        let mut args = strings(&["-y", "-framerate"]);
+        args.push(self.fps.to_string());
+        if self.frame_pattern.start() != 0 {
+            args.extend([
+                "-start_number".to_string(),
+                self.frame_pattern.start().to_string(),
+            ]);
+        }
+        args.extend(["-i".to_string(), path_arg(&frame_pattern)]);
+        args.extend(["-i".to_string(), path_arg(audio_path)]);
+        let video_secs = frame_count as f64 / f64::from(self.fps);
+        let sync_args = match (self.sync_length, self.hold_last) {
+            (Some((mode, audio_secs)), _) => mode.ffmpeg_args(video_secs, f64::from(audio_secs))?,
+            (None, Some(hold)) => sync::hold_args(video_secs, hold)?,
+            (None, None) => vec!["-shortest".to_string()],
+        };
+        // Audio filters (padding) need decoded audio, so they rule out copying
+        let source_audio = self
+            .source_audio_codec
+            .as_deref()
+            .filter(|_| !sync_args.iter().any(|a| a == "-af"));
+        args.extend(Container::for_output(output_path).codec_args_with(
+            self.video_codec,
+            source_audio,
+            &self.pix_fmt,
+        ));
+        args.extend(sync_args);
+        args.extend(self.metadata_args());
+        args.extend(OutputTarget::detect(output_path).ffmpeg_args(output_path));
+        Ok(args)
+    }
+
+    /// Builds FFmpeg arguments for a looped static image with audio.
+    fn static_args(
+        &self,
+        image_path: &Path,
+        audio_path: &Path,
+        duration: f32,
+        output_path: &Path,
+    ) -> Vec<String> {
+        let mut args = vec!["-y".to_string()];
+        if let Some(background) = &self.background {
+            args.extend(background.input_args());
+        }
+        args.extend(strings(&["-loop", "1", "-i"]));
+        args.push(path_arg(image_path));
+        args.extend(["-i".to_string(), path_arg(audio_path)]);
+        if self.background.is_some() {
+            args.extend(strings(&["-filter_complex", background::OVERLAY_FILTER]));
+            args.extend(strings(&["-map", "[v]", "-map", "2:a"]));
+        }
+        args.extend(Container::for_output(output_path).codec_args_with(
+            self.video_codec,
+            self.source_audio_codec.as_deref(),
+            &self.pix_fmt,
+        ));
+        args.extend(["-t".to_string(), format!("{duration:.2}")]);
+        args.push("-shortest".to_string());
+        args.extend(self.metadata_args());
+        args.extend(OutputTarget::detect(output_path).ffmpeg_args(output_path));
+        args
+    }
+
+    /// Metadata for `--strip-audio-metadata` and `--audio-language`.
+    ///
+    /// Source metadata is dropped first so explicit tags still apply.
+    fn metadata_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.strip_metadata {
+            args.extend(strings(&["-map_metadata", "-1"]));
+        }
+        if let Some(language) = &self.audio_language {
+            args.extend([
+                "-metadata:s:a:0".to_string(),
+                format!("language={language}"),
+            ]);
+        }
+        args
+    }
+
+    /// Runs FFmpeg with the given arguments, recording it in the debug bundle.
+    fn run_ffmpeg(&self, args: &[String]) -> Result<()> {
+        let _span = tracing::info_span!("ffmpeg").entered();
+        tracing::debug!("ffmpeg {}", args.join(" "));
+        let output = self
+            .ffmpeg
+            .ffmpeg()
+            .args(args)
             .output()
             .map_err(|e| CliError::Video(format!("Failed to run ffmpeg: {e}")))?;
 
-        if !status.status.success() {
-            let stderr = String::from_utf8_lossy(&status.stderr);
-            return Err(CliError::Video(format!("FFmpeg failed: {stderr}")));
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        if let Some(bundle) = &self.debug_bundle {
+            let mut command = vec![path_arg(self.ffmpeg.ffmpeg_path())];
+            command.extend_from_slice(args);
+            bundle
+                .lock()
+                .unwrap()
+                .record_ffmpeg(command, stderr.clone(), output.status.success());
         }
 
-        tracing::info!("Static video created: {}", output_path.display());
+        if !output.status.success() {
+            return Err(CliError::Video(format!("FFmpeg failed: {stderr}")));
+        }
         Ok(())
     }
 }
 
-/// Checks if FFmpeg is available on the system.
-pub fn check_ffmpeg() -> Result<()> {
-    let output = Command::new("ffmpeg")
-        .arg("-version")
-        .output()
-        .map_err(|_| CliError::Video("FFmpeg not found. Please install FFmpeg.".to_string()))?;
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+fn path_arg(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Checks if the configured FFmpeg binary can be run.
+///
+/// Returns the first line of `ffmpeg -version`.
+pub fn check_ffmpeg(ffmpeg: &FfmpegConfig) -> Result<String> {
+    let output = ffmpeg.ffmpeg().arg("-version").output().map_err(|_| {
+        CliError::Video(format!(
+            "FFmpeg not found at {}. Please install FFmpeg or set --ffmpeg-path.",
+            ffmpeg.ffmpeg_path().display()
+        ))
+    })?;
 
     if !output.status.success() {
         return Err(CliError::Video("FFmpeg check failed".to_string()));
@@ -166,5 +477,8 @@ pub fn check_ffmpeg() -> Result<()> {
     let first_line = version.lines().next().unwrap_or("unknown");
     tracing::debug!("FFmpeg: {first_line}");
 
-    Ok(())
+    Ok(first_line.to_string())
 }
+
+#[cfg(test)]
+mod tests;
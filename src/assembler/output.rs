@@ -0,0 +1,95 @@
+//! Output destination handling.
+//!
+//! Regular files are written by FFmpeg as seekable MP4. A named pipe (FIFO)
+//! can't be seeked, so a live consumer gets fragmented MP4 instead.
+
+use std::path::Path;
+
+/// Where the assembled video is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputTarget {
+    /// A regular file.
+    File,
+    /// An existing named pipe read by a live consumer.
+    Fifo,
+}
+
+impl OutputTarget {
+    /// Detects the target kind from the file type at `path`.
+    pub fn detect(path: &Path) -> Self {
+        if is_fifo(path) {
+            Self::Fifo
+        } else {
+            Self::File
+        }
+    }
+
+    /// Returns true if the output can be stat'ed for its size.
+    pub fn has_size(self) -> bool {
+        self == Self::File
+    }
+
+    /// FFmpeg arguments that write to `path` in the right container mode.
+    pub fn ffmpeg_args(self, path: &Path) -> Vec<String> {
+        let mut args = Vec::new();
+        if self == Self::Fifo {
+            args.extend(
+                [
+                    "-movflags",
+                    "frag_keyframe+empty_moov+default_base_moof",
+                    "-f",
+                    "mp4",
+                ]
+                .map(String::from),
+            );
+        }
+        args.push(path.to_string_lossy().into_owned());
+        args
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regular_output_is_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.mp4");
+        assert_eq!(OutputTarget::detect(&path), OutputTarget::File);
+
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(OutputTarget::detect(&path), OutputTarget::File);
+        assert_eq!(OutputTarget::File.ffmpeg_args(&path).len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_output_selects_streaming() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("live.mp4");
+        let status = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let target = OutputTarget::detect(&path);
+        assert_eq!(target, OutputTarget::Fifo);
+        assert!(!target.has_size());
+        let args = target.ffmpeg_args(&path);
+        assert!(args[1].contains("frag_keyframe"));
+        assert_eq!(args[2..4], ["-f", "mp4"]);
+    }
+}
@@ -0,0 +1,193 @@
+//! Encoding frames as they arrive (`--pipeline`).
+//!
+//! [`FfmpegSink`](super::FfmpegSink) stages every frame on disk and runs
+//! FFmpeg once the last one is in. [`PipeSink`] instead starts FFmpeg up
+//! front reading PNGs from its stdin (`image2pipe`), so a streamed render is
+//! encoded while the server is still producing frames.
+
+use super::sink::FrameSink;
+use super::{Container, OutputTarget, VideoAssembler, flatten, path_arg, strings};
+use crate::debug_bundle::SharedBundle;
+use crate::error::{CliError, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Stdio};
+
+/// Feeds frames to a running FFmpeg through its stdin.
+pub struct PipeSink {
+    child: Child,
+    stdin: ChildStdin,
+    command: Vec<String>,
+    log_path: PathBuf,
+    fill: flatten::Fill,
+    output_path: PathBuf,
+    debug_bundle: Option<SharedBundle>,
+}
+
+impl VideoAssembler {
+    /// Starts FFmpeg encoding PNG frames written to the returned sink, muxed
+    /// with `audio_path` into `output_path`.
+    ///
+    /// The frame count isn't known up front, so the video simply ends with
+    /// the shorter stream (`--sync-length` and `--hold-last` need staging).
+    pub fn pipe_sink(&self, audio_path: &Path, output_path: &Path) -> Result<PipeSink> {
+        let args = self.pipe_args(audio_path, output_path);
+        tracing::debug!("ffmpeg {}", args.join(" "));
+        // FFmpeg's log goes to a file: an unread stderr pipe could fill up
+        // and stall the encoder while frames are still being written
+        let log_path = self.work_dir.path().join("ffmpeg-pipe.log");
+        let log = File::create(&log_path)?;
+        let mut child = self
+            .ffmpeg
+            .ffmpeg()
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(log)
+            .spawn()
+            .map_err(|e| CliError::Video(format!("Failed to run ffmpeg: {e}")))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let mut command = vec![path_arg(self.ffmpeg.ffmpeg_path())];
+        command.extend(args);
+        Ok(PipeSink {
+            child,
+            stdin,
+            command,
+            log_path,
+            fill: self.frame_fill.clone(),
+            output_path: output_path.to_path_buf(),
+            debug_bundle: self.debug_bundle.clone(),
+        })
+    }
+
+    /// Builds FFmpeg arguments for encoding PNGs read from stdin with audio.
+    fn pipe_args(&self, audio_path: &Path, output_path: &Path) -> Vec<String> {
+        let mut args = strings(&["-y", "-f", "image2pipe", "-c:v", "png", "-framerate"]);
+        args.push(self.fps.to_string());
+        args.extend(strings(&["-i", "-"]));
+        args.extend(["-i".to_string(), path_arg(audio_path)]);
+        args.extend(Container::for_output(output_path).codec_args_with(
+            self.video_codec,
+            self.source_audio_codec.as_deref(),
+            &self.pix_fmt,
+        ));
+        args.push("-shortest".to_string());
+        args.extend(self.metadata_args());
+        args.extend(OutputTarget::detect(output_path).ffmpeg_args(output_path));
+        args
+    }
+}
+
+impl PipeSink {
+    /// Stops FFmpeg without finishing the video, e.g. after inference failed.
+    pub fn abort(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl FrameSink for PipeSink {
+    fn write_frame(&mut self, index: usize, png: &[u8]) -> Result<()> {
+        let flat = flatten::flatten_frame(index, png, &self.fill)?;
+        let png = flat.as_deref().unwrap_or(png);
+        self.stdin
+            .write_all(png)
+            .map_err(|e| CliError::Video(format!("Failed to pipe frame {index} to ffmpeg: {e}")))
+    }
+
+    fn finish(self) -> Result<()> {
+        let Self {
+            mut child,
+            stdin,
+            command,
+            log_path,
+            output_path,
+            debug_bundle,
+            ..
+        } = self;
+        // Closing stdin tells FFmpeg the last frame has arrived
+        drop(stdin);
+        let status = child
+            .wait()
+            .map_err(|e| CliError::Video(format!("Failed to wait for ffmpeg: {e}")))?;
+        let stderr = std::fs::read_to_string(&log_path).unwrap_or_default();
+        if let Some(bundle) = &debug_bundle {
+            bundle
+                .lock()
+                .unwrap()
+                .record_ffmpeg(command, stderr.clone(), status.success());
+        }
+        if !status.success() {
+            return Err(CliError::Video(format!("FFmpeg failed: {stderr}")));
+        }
+        tracing::info!("Video created: {}", output_path.display());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ffmpeg::FfmpegConfig;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    /// Waits until `path` holds `len` bytes, for up to two seconds.
+    fn wait_for_len(path: &Path, len: u64) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if std::fs::metadata(path).is_ok_and(|m| m.len() == len) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn test_frames_reach_encoder_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let stub = dir.path().join("ffmpeg-stub");
+        // Copies stdin to the output path (the last argument) as it arrives
+        std::fs::write(&stub, "#!/bin/sh\nfor last; do :; done\ncat > \"$last\"\n").unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let assembler = VideoAssembler::new(25)
+            .unwrap()
+            .with_ffmpeg(FfmpegConfig::from_path(&stub).unwrap());
+        let output = dir.path().join("out.mp4");
+        let frames = [b"first frame".to_vec(), b"second frame".to_vec()];
+
+        let mut sink = assembler
+            .pipe_sink(&dir.path().join("audio.wav"), &output)
+            .unwrap();
+        sink.write_frame(0, &frames[0]).unwrap();
+        // The encoder has the first frame before the second is even written
+        assert!(wait_for_len(&output, frames[0].len() as u64));
+        sink.write_frame(1, &frames[1]).unwrap();
+        sink.finish().unwrap();
+
+        assert_eq!(std::fs::read(&output).unwrap(), frames.concat());
+    }
+
+    #[test]
+    fn test_pipe_args_read_stdin() {
+        let assembler = VideoAssembler::new(30).unwrap();
+        let args = assembler.pipe_args(Path::new("a.wav"), Path::new("out.mp4"));
+        assert_eq!(
+            args[1..9],
+            [
+                "-f",
+                "image2pipe",
+                "-c:v",
+                "png",
+                "-framerate",
+                "30",
+                "-i",
+                "-"
+            ]
+        );
+        assert!(args.contains(&"-shortest".to_string()));
+        assert_eq!(args.last().unwrap(), "out.mp4");
+    }
+}
@@ -0,0 +1,80 @@
+//! Videos assembled by the server (`--server-assemble`).
+//!
+//! When the server returns a finished video instead of frames, it is
+//! decoded and written as-is, so no local FFmpeg encode is needed.
+
+use crate::error::{CliError, Result, decode_base64};
+use std::path::Path;
+
+/// Decodes a base64 server video and writes it to every output.
+///
+/// Returns the decoded size in bytes.
+pub fn write_server_video(video_b64: &str, outputs: &[&Path]) -> Result<usize> {
+    let bytes = decode_base64(video_b64, || "server video".to_string())?;
+    if bytes.is_empty() {
+        return Err(CliError::Video(
+            "Server returned an empty video".to_string(),
+        ));
+    }
+    for output in outputs {
+        std::fs::write(output, &bytes)?;
+    }
+    Ok(bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::test_support::{MockResponse, MockServer, test_audio, test_image};
+    use base64::Engine;
+
+    #[tokio::test]
+    async fn test_video_out_written_directly_to_output() {
+        let video = b"\x00\x00\x00\x18ftypmp42 server-made";
+        let encoded = base64::engine::general_purpose::STANDARD.encode(video);
+        let server = MockServer::with_infer(move |_| {
+            MockResponse::json(serde_json::json!({
+                "status": "success",
+                "total_frames": 0,
+                "frames": [],
+                "video_out": encoded,
+            }))
+        })
+        .await;
+        let options = InferenceOptions {
+            output: Some("mp4".to_string()),
+            ..InferenceOptions::new(25)
+        };
+
+        let response = MuseTalkClient::new(server.url())
+            .with_retry_on_empty(true)
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+            )
+            .await
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.mp4");
+        let size = write_server_video(&response.video_out.unwrap(), &[&output]).unwrap();
+
+        assert_eq!(size, video.len());
+        assert_eq!(std::fs::read(&output).unwrap(), video);
+        let requests = server.requests_to("/infer");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].json()["output"], "mp4");
+    }
+
+    #[test]
+    fn test_invalid_video_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.mp4");
+        assert!(matches!(
+            write_server_video("not base64!", &[&output]),
+            Err(CliError::Base64Decode { .. })
+        ));
+        assert!(!output.exists());
+    }
+}
@@ -0,0 +1,223 @@
+//! Pluggable consumers for generated frames.
+//!
+//! The FFmpeg assembler is one [`FrameSink`]; library users can supply their
+//! own to push frames elsewhere without going through FFmpeg.
+
+use super::VideoAssembler;
+use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+use crate::error::{CliError, Result, decode_base64};
+use crate::loader::AudioData;
+use crate::throttle::ThrottledLog;
+use std::path::Path;
+
+/// A consumer of decoded PNG frames, written in order from index 0.
+pub trait FrameSink {
+    /// Accepts the PNG bytes of frame `index`.
+    fn write_frame(&mut self, index: usize, png: &[u8]) -> Result<()>;
+
+    /// Called once after the last frame.
+    fn finish(self) -> Result<()>
+    where
+        Self: Sized;
+}
+
+/// Stages frames for a [`VideoAssembler`] and encodes them with FFmpeg on finish.
+///
+/// Frames are staged once; each output is then encoded from the same frames.
+pub struct FfmpegSink<'a> {
+    assembler: &'a VideoAssembler,
+    audio_path: &'a Path,
+    output_paths: Vec<&'a Path>,
+    frame_count: usize,
+}
+
+impl VideoAssembler {
+    /// Returns a sink that muxes its frames with `audio_path` into `output_path`.
+    pub fn sink<'a>(&'a self, audio_path: &'a Path, output_path: &'a Path) -> FfmpegSink<'a> {
+        FfmpegSink {
+            assembler: self,
+            audio_path,
+            output_paths: vec![output_path],
+            frame_count: 0,
+        }
+    }
+
+    /// Removes frames a previous run left after the last of `frame_count`.
+    ///
+    /// FFmpeg reads numbered frames until the first gap, so leftovers in a
+    /// reused `--temp-name` or `--keep-frames` directory would otherwise be
+    /// encoded after this run's frames.
+    fn remove_stale_frames(&self, frame_count: usize) -> Result<()> {
+        for index in frame_count.. {
+            let path = self.frames_dir().join(self.frame_pattern.filename(index));
+            match std::fs::remove_file(&path) {
+                Ok(()) => tracing::debug!("Removed stale frame {}", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => break,
+                Err(e) => {
+                    return Err(CliError::Video(format!(
+                        "Failed to remove stale frame {}: {e}",
+                        path.display()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> FfmpegSink<'a> {
+    /// Also encodes the staged frames into `output_path`.
+    pub fn with_output(mut self, output_path: &'a Path) -> Self {
+        self.output_paths.push(output_path);
+        self
+    }
+}
+
+impl FrameSink for FfmpegSink<'_> {
+    fn write_frame(&mut self, index: usize, png: &[u8]) -> Result<()> {
+        self.assembler.write_staged_frame(index, png)?;
+        self.frame_count = self.frame_count.max(index + 1);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        self.assembler.remove_stale_frames(self.frame_count)?;
+        for output_path in self.output_paths {
+            self.assembler
+                .run_ffmpeg_frames(self.frame_count, self.audio_path, output_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes base64 frames into `sink` in order, then finishes it.
+pub fn write_frames<S: FrameSink>(frames: &[String], mut sink: S) -> Result<()> {
+    let mut log = ThrottledLog::new("Wrote", frames.len());
+    for (i, frame_b64) in frames.iter().enumerate() {
+        let png = decode_frame(i, frame_b64)?;
+        sink.write_frame(i, &png)?;
+        log.tick();
+    }
+    log.finish();
+    sink.finish()
+}
+
+/// Runs inference and streams the returned frames into `sink`.
+///
+/// Returns the number of frames written.
+pub async fn infer_into<S: FrameSink>(
+    client: &MuseTalkClient,
+    reference: ReferenceInput<'_>,
+    audio: &AudioData,
+    options: &InferenceOptions,
+    sink: S,
+) -> Result<usize> {
+    let response = client.infer(reference, audio, options).await?;
+    let frames: Vec<String> = response.frames.into_iter().map(|f| f.data).collect();
+    write_frames(&frames, sink)?;
+    Ok(frames.len())
+}
+
+/// Decodes one base64 frame.
+pub(super) fn decode_frame(index: usize, frame_b64: &str) -> Result<Vec<u8>> {
+    tracing::trace_span!("decode_frame")
+        .in_scope(|| decode_base64(frame_b64, || format!("frame {index}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockServer, frames_response, test_audio, test_image};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Collected {
+        frames: Vec<(usize, Vec<u8>)>,
+        finished: bool,
+    }
+
+    /// Collects frames in memory, recording whether it was finished.
+    #[derive(Default, Clone)]
+    struct MemorySink(Arc<Mutex<Collected>>);
+
+    impl FrameSink for MemorySink {
+        fn write_frame(&mut self, index: usize, png: &[u8]) -> Result<()> {
+            self.0.lock().unwrap().frames.push((index, png.to_vec()));
+            Ok(())
+        }
+
+        fn finish(self) -> Result<()> {
+            self.0.lock().unwrap().finished = true;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_infer_into_memory_sink() {
+        let server = MockServer::with_infer(|_| frames_response(4)).await;
+        let client = MuseTalkClient::new(server.url());
+        let sink = MemorySink::default();
+
+        let count = infer_into(
+            &client,
+            ReferenceInput::Image(&test_image()),
+            &test_audio(),
+            &InferenceOptions::new(25),
+            sink.clone(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(count, 4);
+        let collected = sink.0.lock().unwrap();
+        let indices: Vec<_> = collected.frames.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, [0, 1, 2, 3]);
+        assert!(
+            collected
+                .frames
+                .iter()
+                .all(|(_, png)| png.starts_with(b"\x89PNG"))
+        );
+        assert!(collected.finished);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_one_frame_set_encoded_into_each_output() {
+        use crate::ffmpeg::FfmpegConfig;
+        use crate::test_support::tiny_png_base64;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let stub = dir.path().join("ffmpeg-stub");
+        // Writes the output path (the last argument) so each run is observable
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\nfor last; do :; done\necho ok > \"$last\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let assembler = VideoAssembler::new(25)
+            .unwrap()
+            .with_ffmpeg(FfmpegConfig::from_path(&stub).unwrap());
+        let audio = dir.path().join("audio.wav");
+        let mp4 = dir.path().join("out.mp4");
+        let webm = dir.path().join("out.webm");
+        let frames = vec![tiny_png_base64(); 3];
+
+        write_frames(&frames, assembler.sink(&audio, &mp4).with_output(&webm)).unwrap();
+
+        assert!(mp4.exists());
+        assert!(webm.exists());
+        assert!(assembler.frames_dir().join("frame_00002.png").exists());
+    }
+
+    #[test]
+    fn test_invalid_frame_stops_before_finish() {
+        let sink = MemorySink::default();
+        let frames = vec!["%%%".to_string()];
+        assert!(write_frames(&frames, sink.clone()).is_err());
+        assert!(!sink.0.lock().unwrap().finished);
+    }
+}
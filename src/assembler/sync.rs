@@ -0,0 +1,164 @@
+//! Reconciling the rendered video length with the audio length.
+//!
+//! The server may return slightly more or fewer frames than the audio
+//! covers. These modes make the muxed output match the audio exactly.
+
+use crate::error::{CliError, Result};
+
+/// Smallest video speed factor accepted by [`SyncLength::Stretch`].
+pub const MIN_STRETCH: f64 = 0.8;
+
+/// Largest video speed factor accepted by [`SyncLength::Stretch`].
+pub const MAX_STRETCH: f64 = 1.25;
+
+/// Longest final-frame hold accepted by [`hold_args`], in seconds.
+pub const MAX_HOLD_SECS: f64 = 30.0;
+
+/// How to reconcile video and audio lengths when muxing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SyncLength {
+    /// Cut the output at the audio duration.
+    Trim,
+    /// Freeze the last frame or append silence, whichever stream is shorter.
+    Pad,
+    /// Retime the video so it spans the audio duration.
+    Stretch,
+}
+
+impl SyncLength {
+    /// FFmpeg output arguments aligning a `video_secs` stream to `audio_secs`.
+    pub fn ffmpeg_args(self, video_secs: f64, audio_secs: f64) -> Result<Vec<String>> {
+        let args = match self {
+            Self::Trim => vec!["-t".to_string(), format!("{audio_secs:.3}")],
+            Self::Pad => pad_args(video_secs, audio_secs),
+            Self::Stretch => {
+                let factor = stretch_factor(video_secs, audio_secs)?;
+                vec!["-vf".to_string(), format!("setpts={factor:.6}*PTS")]
+            }
+        };
+        Ok(args)
+    }
+}
+
+/// Pads the shorter stream up to the longer one.
+fn pad_args(video_secs: f64, audio_secs: f64) -> Vec<String> {
+    let gap = (audio_secs - video_secs).abs();
+    let mut args = if video_secs < audio_secs {
+        vec![
+            "-vf".to_string(),
+            format!("tpad=stop_mode=clone:stop_duration={gap:.3}"),
+        ]
+    } else {
+        vec!["-af".to_string(), format!("apad=pad_dur={gap:.3}")]
+    };
+    args.extend([
+        "-t".to_string(),
+        format!("{:.3}", video_secs.max(audio_secs)),
+    ]);
+    args
+}
+
+/// FFmpeg output arguments holding the last frame for `hold_secs` past the
+/// end of a `video_secs` video, with the audio padded by matching silence.
+pub fn hold_args(video_secs: f64, hold_secs: f64) -> Result<Vec<String>> {
+    check_hold(hold_secs)?;
+    Ok(vec![
+        "-vf".to_string(),
+        format!("tpad=stop_mode=clone:stop_duration={hold_secs:.3}"),
+        "-af".to_string(),
+        "apad".to_string(),
+        "-t".to_string(),
+        format!("{:.3}", video_secs + hold_secs),
+    ])
+}
+
+/// Rejects negative, non-finite, or implausibly long holds.
+pub fn check_hold(hold_secs: f64) -> Result<()> {
+    if !(0.0..=MAX_HOLD_SECS).contains(&hold_secs) {
+        return Err(CliError::Video(format!(
+            "--hold-last must be between 0 and {MAX_HOLD_SECS} seconds, got {hold_secs}"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the PTS multiplier mapping the video duration onto the audio.
+///
+/// Rejects factors outside [`MIN_STRETCH`]..=[`MAX_STRETCH`], which would
+/// visibly warp the motion.
+fn stretch_factor(video_secs: f64, audio_secs: f64) -> Result<f64> {
+    if video_secs <= 0.0 {
+        return Err(CliError::Video("Cannot stretch an empty video".to_string()));
+    }
+    let factor = audio_secs / video_secs;
+    if !(MIN_STRETCH..=MAX_STRETCH).contains(&factor) {
+        return Err(CliError::Video(format!(
+            "Stretch factor {factor:.3} is outside {MIN_STRETCH}-{MAX_STRETCH}; \
+             video is {video_secs:.2}s but audio is {audio_secs:.2}s"
+        )));
+    }
+    Ok(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_args() {
+        let args = SyncLength::Trim.ffmpeg_args(4.2, 4.0).unwrap();
+        assert_eq!(args, ["-t", "4.000"]);
+    }
+
+    #[test]
+    fn test_pad_args_freeze_short_video() {
+        let args = SyncLength::Pad.ffmpeg_args(3.5, 4.0).unwrap();
+        assert_eq!(
+            args,
+            [
+                "-vf",
+                "tpad=stop_mode=clone:stop_duration=0.500",
+                "-t",
+                "4.000"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pad_args_silence_short_audio() {
+        let args = SyncLength::Pad.ffmpeg_args(4.0, 3.75).unwrap();
+        assert_eq!(args, ["-af", "apad=pad_dur=0.250", "-t", "4.000"]);
+    }
+
+    #[test]
+    fn test_stretch_args() {
+        let args = SyncLength::Stretch.ffmpeg_args(4.0, 4.4).unwrap();
+        assert_eq!(args, ["-vf", "setpts=1.100000*PTS"]);
+    }
+
+    #[test]
+    fn test_hold_args_extend_last_frame_and_audio() {
+        let args = hold_args(4.0, 1.5).unwrap();
+        assert_eq!(
+            args,
+            [
+                "-vf",
+                "tpad=stop_mode=clone:stop_duration=1.500",
+                "-af",
+                "apad",
+                "-t",
+                "5.500"
+            ]
+        );
+        assert!(hold_args(4.0, -1.0).is_err());
+        assert!(hold_args(4.0, f64::NAN).is_err());
+        assert!(hold_args(4.0, MAX_HOLD_SECS + 1.0).is_err());
+    }
+
+    #[test]
+    fn test_stretch_rejects_extreme_factors() {
+        assert!(SyncLength::Stretch.ffmpeg_args(2.0, 4.0).is_err());
+        assert!(SyncLength::Stretch.ffmpeg_args(4.0, 2.0).is_err());
+        assert!(SyncLength::Stretch.ffmpeg_args(0.0, 2.0).is_err());
+    }
+}
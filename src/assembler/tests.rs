@@ -0,0 +1,322 @@
+//! FFmpeg argument building tests.
+
+use super::*;
+
+#[test]
+fn test_frames_args() {
+    let assembler = VideoAssembler::new(25).unwrap();
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), Path::new("out.mp4"))
+        .unwrap();
+
+    assert_eq!(args[0], "-y");
+    assert_eq!(args[1..3], ["-framerate", "25"]);
+    assert!(args[4].ends_with("frame_%05d.png"));
+    assert_eq!(args[5..7], ["-i", "a.wav"]);
+    assert!(args.contains(&"-shortest".to_string()));
+    assert_eq!(args.last().unwrap(), "out.mp4");
+}
+
+#[test]
+fn test_frames_args_custom_pattern() {
+    let dir = tempfile::tempdir().unwrap();
+    let pattern: FramePattern = "render.{index:4}.png".parse().unwrap();
+    let assembler = VideoAssembler::new(30)
+        .unwrap()
+        .with_frames_dir(dir.path().join("frames"))
+        .unwrap()
+        .with_frame_pattern(pattern.with_start(1));
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), Path::new("out.mp4"))
+        .unwrap();
+
+    let start = args.iter().position(|a| a == "-start_number").unwrap();
+    assert_eq!(args[start + 1], "1");
+    assert_eq!(
+        args[start + 3],
+        path_arg(&dir.path().join("frames").join("render.%04d.png"))
+    );
+}
+
+#[test]
+fn test_frames_args_sync_length() {
+    // 100 frames at 25 fps is 4s of video against 4.2s of audio
+    let assembler = VideoAssembler::new(25)
+        .unwrap()
+        .with_sync_length(SyncLength::Stretch, 4.2);
+    let args = assembler
+        .frames_args(100, Path::new("a.wav"), Path::new("out.mp4"))
+        .unwrap();
+
+    let vf = args.iter().position(|a| a == "-vf").unwrap();
+    assert_eq!(args[vf + 1], "setpts=1.050000*PTS");
+    assert!(!args.contains(&"-shortest".to_string()));
+    assert_eq!(args.last().unwrap(), "out.mp4");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_frames_args_stream_to_fifo() {
+    let dir = tempfile::tempdir().unwrap();
+    let fifo = dir.path().join("live.mp4");
+    assert!(
+        std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap()
+            .success()
+    );
+
+    let assembler = VideoAssembler::new(25).unwrap();
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), &fifo)
+        .unwrap();
+    let flags = args.iter().position(|a| a == "-movflags").unwrap();
+    assert!(args[flags + 1].contains("empty_moov"));
+    assert_eq!(args.last().unwrap(), &path_arg(&fifo));
+
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), Path::new("out.mp4"))
+        .unwrap();
+    assert!(!args.contains(&"-movflags".to_string()));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_ffmpeg_uses_configured_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let stub = dir.path().join("ffmpeg-stub");
+    std::fs::write(&stub, "#!/bin/sh\necho \"ffmpeg version stub\"\n").unwrap();
+    std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config = FfmpegConfig::from_path(&stub).unwrap();
+    assert_eq!(check_ffmpeg(&config).unwrap(), "ffmpeg version stub");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_audio_passthrough_copies_aac_and_encodes_wav() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let ffmpeg = dir.path().join("ffmpeg");
+    let ffprobe = dir.path().join("ffprobe");
+    std::fs::write(&ffmpeg, "#!/bin/sh\n").unwrap();
+    // Reports AAC for .m4a inputs and PCM for everything else
+    std::fs::write(
+        &ffprobe,
+        "#!/bin/sh\nfor last; do :; done\ncase \"$last\" in *.m4a) echo aac;; *) echo pcm_s16le;; esac\n",
+    )
+    .unwrap();
+    for tool in [&ffmpeg, &ffprobe] {
+        std::fs::set_permissions(tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let config = FfmpegConfig::from_path(&ffmpeg).unwrap();
+    let output = Path::new("out.mp4");
+
+    for (audio, expected) in [("voice.m4a", "copy"), ("voice.wav", "aac")] {
+        let assembler = VideoAssembler::new(30)
+            .unwrap()
+            .with_ffmpeg(config.clone())
+            .with_audio_passthrough(Path::new(audio));
+        let args = assembler.frames_args(10, Path::new(audio), output).unwrap();
+        assert!(
+            args.windows(2).any(|w| w == ["-c:a", expected]),
+            "{audio}: {args:?}"
+        );
+    }
+}
+
+#[test]
+fn test_static_args() {
+    let assembler = VideoAssembler::new(30).unwrap();
+    let args = assembler.static_args(
+        Path::new("face.png"),
+        Path::new("a.wav"),
+        2.5,
+        Path::new("out.mp4"),
+    );
+
+    assert_eq!(args[1..5], ["-loop", "1", "-i", "face.png"]);
+    let t = args.iter().position(|a| a == "-t").unwrap();
+    assert_eq!(args[t + 1], "2.50");
+    assert_eq!(args.last().unwrap(), "out.mp4");
+}
+
+#[test]
+fn test_static_args_with_background() {
+    let assembler = VideoAssembler::new(30)
+        .unwrap()
+        .with_background(Background::Video(PathBuf::from("bg.mp4")));
+    let args = assembler.static_args(
+        Path::new("face.png"),
+        Path::new("a.wav"),
+        2.5,
+        Path::new("out.mp4"),
+    );
+
+    let inputs: Vec<_> = args
+        .windows(2)
+        .filter(|w| w[0] == "-i")
+        .map(|w| w[1].as_str())
+        .collect();
+    assert_eq!(inputs, ["bg.mp4", "face.png", "a.wav"]);
+    assert!(args.windows(2).any(|w| w == ["-stream_loop", "-1"]));
+    let filter = args.iter().position(|a| a == "-filter_complex").unwrap();
+    assert!(args[filter + 1].contains("overlay="));
+    assert!(args.windows(2).any(|w| w == ["-map", "2:a"]));
+    assert!(args.contains(&"-shortest".to_string()));
+}
+
+#[test]
+fn test_pix_fmt_replaces_default() {
+    let assembler = VideoAssembler::new(25)
+        .unwrap()
+        .with_pix_fmt("yuvj420p".parse().unwrap());
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), Path::new("o.mp4"))
+        .unwrap();
+
+    assert!(args.windows(2).any(|w| w == ["-pix_fmt", "yuvj420p"]));
+    assert!(!args.contains(&"yuv420p".to_string()));
+    assert!("rgb48".parse::<PixelFormat>().is_err());
+}
+
+#[test]
+fn test_frames_args_hold_last() {
+    let assembler = VideoAssembler::new(25)
+        .unwrap()
+        .with_hold_last(1.0)
+        .unwrap();
+    let args = assembler
+        .frames_args(100, Path::new("a.wav"), Path::new("o.mp4"))
+        .unwrap();
+
+    assert!(
+        args.windows(2)
+            .any(|w| w == ["-vf", "tpad=stop_mode=clone:stop_duration=1.000"])
+    );
+    assert!(args.windows(2).any(|w| w == ["-t", "5.000"]));
+    assert!(!args.contains(&"-shortest".to_string()));
+    assert!(
+        VideoAssembler::new(25)
+            .unwrap()
+            .with_hold_last(-0.5)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_audio_language_tagged_before_output() {
+    let assembler = VideoAssembler::new(25).unwrap().with_audio_language("de");
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), Path::new("o.de.mp4"))
+        .unwrap();
+
+    assert!(
+        args.windows(2)
+            .any(|w| w == ["-metadata:s:a:0", "language=de"])
+    );
+    assert_eq!(args.last().unwrap(), "o.de.mp4");
+}
+
+#[test]
+fn test_strip_metadata_keeps_explicit_tags() {
+    let assembler = VideoAssembler::new(25)
+        .unwrap()
+        .with_stripped_metadata()
+        .with_audio_language("de");
+    let args = assembler
+        .frames_args(10, Path::new("a.wav"), Path::new("o.mp4"))
+        .unwrap();
+
+    let strip = args
+        .windows(2)
+        .position(|w| w == ["-map_metadata", "-1"])
+        .expect("-map_metadata -1 missing");
+    let tag = args
+        .windows(2)
+        .position(|w| w == ["-metadata:s:a:0", "language=de"])
+        .unwrap();
+    assert!(strip < tag);
+    assert!(
+        !VideoAssembler::new(25)
+            .unwrap()
+            .frames_args(10, Path::new("a.wav"), Path::new("o.mp4"))
+            .unwrap()
+            .contains(&"-map_metadata".to_string())
+    );
+}
+
+#[test]
+fn test_named_work_dir_created_and_reused() {
+    let name = format!("musetalk-test-{}", std::process::id());
+    let first = VideoAssembler::named(25, &name).unwrap();
+    let dir = std::env::temp_dir().join(&name);
+    assert_eq!(first.frames_dir(), dir);
+    assert!(dir.is_dir());
+    std::fs::write(dir.join("marker"), b"kept").unwrap();
+    drop(first);
+
+    let second = VideoAssembler::named(25, &name).unwrap();
+    assert_eq!(second.frames_dir(), dir);
+    assert!(dir.join("marker").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(VideoAssembler::named(25, "../escape").is_err());
+    assert!(VideoAssembler::named(25, "").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reused_work_dir_drops_stale_frames() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let name = format!("musetalk-stale-test-{}", std::process::id());
+    let dir = std::env::temp_dir().join(&name);
+    let tools = tempfile::tempdir().unwrap();
+    let ffmpeg = tools.path().join("ffmpeg");
+    // Lists the staged files into the output, as a stand-in for encoding them
+    std::fs::write(
+        &ffmpeg,
+        format!(
+            "#!/bin/sh\nfor last; do :; done\nls '{}' > \"$last\"\n",
+            dir.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let config = FfmpegConfig::from_path(&ffmpeg).unwrap();
+    let output = tools.path().join("out.mp4");
+    let render = |frames: usize| {
+        let assembler = VideoAssembler::named(25, &name)
+            .unwrap()
+            .with_ffmpeg(config.clone());
+        let mut sink = assembler.sink(Path::new("a.wav"), &output);
+        for index in 0..frames {
+            sink.write_frame(index, b"png").unwrap();
+        }
+        sink.finish().unwrap();
+        std::fs::read_to_string(&output).unwrap()
+    };
+
+    render(4);
+    std::fs::write(dir.join("marker"), b"kept").unwrap();
+    let staged = render(2);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(staged, "frame_00000.png\nframe_00001.png\nmarker\n");
+}
+
+#[test]
+fn test_progress_bar_follows_quiet() {
+    let assembler = |quiet: &[&str]| {
+        let mut argv = vec!["musetalk-cli", "-r", "a.png", "-a", "a.wav", "-o", "o.mp4"];
+        argv.extend(quiet);
+        let args = Args::try_parse_from_args(argv).unwrap();
+        VideoAssembler::from_args(&args, 25, 1.0, None).unwrap()
+    };
+    assert!(assembler(&[]).progress_bar);
+    assert!(!assembler(&["--quiet"]).progress_bar);
+}
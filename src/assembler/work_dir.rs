@@ -0,0 +1,59 @@
+//! Working directory where frames are staged by default.
+//!
+//! Normally a random temp dir removed on drop. `--temp-name` swaps in a
+//! predictable directory instead, so intermediate files can be scripted
+//! around while debugging.
+
+use crate::error::{CliError, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// The assembler's working directory.
+pub(super) enum WorkDir {
+    /// Randomly named, removed when dropped.
+    Temp(tempfile::TempDir),
+    /// Predictably named and left in place.
+    Named(PathBuf),
+}
+
+impl WorkDir {
+    /// Creates a fresh, randomly named temp dir.
+    pub(super) fn temp() -> Result<Self> {
+        tempfile::tempdir()
+            .map(Self::Temp)
+            .map_err(|e| CliError::Video(format!("Failed to create temp dir: {e}")))
+    }
+
+    /// Creates (or reuses) `name` under the system temp dir.
+    ///
+    /// `name` must be a single path component so it can't escape the temp dir.
+    pub(super) fn named(name: &str) -> Result<Self> {
+        let mut components = Path::new(name).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(CliError::Video(format!(
+                "Invalid temp name '{name}': must be a single directory name"
+            )));
+        }
+        let dir = std::env::temp_dir().join(name);
+        let reused = dir.is_dir();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            CliError::Video(format!("Failed to create temp dir {}: {e}", dir.display()))
+        })?;
+        tracing::warn!(
+            "Using {} as the working directory{}; it will NOT be cleaned up, and runs sharing \
+             the name overwrite each other's files",
+            dir.display(),
+            if reused { " (reused)" } else { "" }
+        );
+        Ok(Self::Named(dir))
+    }
+
+    pub(super) fn path(&self) -> &Path {
+        match self {
+            Self::Temp(dir) => dir.path(),
+            Self::Named(dir) => dir,
+        }
+    }
+}
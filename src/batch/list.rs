@@ -0,0 +1,77 @@
+//! Batch jobs from a plain list of audio files (`--input-list`).
+//!
+//! The list holds one audio path per line, each rendered against the shared
+//! `--reference`. Blank lines and lines starting with `#` are skipped, and
+//! relative paths are resolved against the list's directory. Outputs are
+//! left empty for `--output-template` to name.
+
+use super::{BatchJob, Manifest};
+use crate::error::{CliError, Result};
+use std::path::{Path, PathBuf};
+
+/// One job per audio path listed in `list`, all against `reference`.
+pub fn list_jobs(list: &Path, reference: &Path) -> Result<Manifest> {
+    let text = std::fs::read_to_string(list).map_err(|e| {
+        CliError::Batch(format!("Failed to read input list {}: {e}", list.display()))
+    })?;
+    let base = list.parent().unwrap_or(Path::new(""));
+    let jobs = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| BatchJob {
+            reference: reference.to_path_buf(),
+            audio: base.join(line),
+            output: PathBuf::new(),
+            ..BatchJob::default()
+        })
+        .collect();
+    Ok(Manifest { jobs })
+}
+
+/// Audio files listed in `manifest` that don't exist, in list order.
+pub fn missing_audio(manifest: &Manifest) -> Vec<&Path> {
+    manifest
+        .jobs
+        .iter()
+        .map(|job| job.audio.as_path())
+        .filter(|audio| !audio.is_file())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_comments_and_blank_lines_skipped() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("a.wav"), b"").unwrap();
+        let list = dir.path().join("inputs.txt");
+        std::fs::write(
+            &list,
+            "# narration takes\n\na.wav\n   \n  # retakes\n  b.wav  \n",
+        )
+        .unwrap();
+
+        let manifest = list_jobs(&list, Path::new("avatar.png")).unwrap();
+
+        let audio: Vec<_> = manifest.jobs.iter().map(|j| j.audio.clone()).collect();
+        assert_eq!(audio, [dir.path().join("a.wav"), dir.path().join("b.wav")]);
+        assert!(
+            manifest
+                .jobs
+                .iter()
+                .all(|j| j.reference == Path::new("avatar.png") && j.output.as_os_str().is_empty())
+        );
+        assert_eq!(missing_audio(&manifest), [dir.path().join("b.wav")]);
+    }
+
+    #[test]
+    fn test_unreadable_list_rejected() {
+        let dir = tempdir().unwrap();
+        let err = list_jobs(&dir.path().join("missing.txt"), Path::new("a.png")).unwrap_err();
+        assert!(matches!(err, CliError::Batch(_)));
+    }
+}
@@ -0,0 +1,490 @@
+//! Batch rendering from a job manifest, with resumable progress.
+//!
+//! A manifest is a JSON file listing jobs:
+//!
+//! ```json
+//! {"jobs": [{"reference": "avatar.png", "audio": "a.wav", "output": "a.mp4"}]}
+//! ```
+//!
+//! A job may also set `"language"` to tag its output's audio stream, and
+//! `"fps"`, `"resolution"`, or `"codec"` to override the global flags for
+//! that job alone.
+//! Relative paths are resolved against the manifest's directory. Each
+//! finished output is recorded in a checkpoint file next to the manifest so
+//! an interrupted run can be resumed.
+
+pub mod list;
+pub mod pairs;
+pub mod report;
+pub mod template;
+pub mod tracks;
+
+use crate::assembler::VideoCodec;
+use crate::cli::Args;
+use crate::error::{CliError, Result};
+use crate::resolution::Resolution;
+use crate::schema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use tracing::Instrument;
+
+pub use list::{list_jobs, missing_audio};
+pub use pairs::{Pairing, pair_directory};
+pub use report::{BatchReport, JobOutcome, JobStatus};
+pub use template::{OutputTemplate, TemplateContext};
+pub use tracks::{AudioTrack, track_jobs};
+
+/// One render in a batch manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchJob {
+    pub reference: PathBuf,
+    pub audio: PathBuf,
+    /// May be omitted when `--output-template` names the outputs.
+    #[serde(default)]
+    pub output: PathBuf,
+    /// Language tag for the output's audio stream metadata.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Frame rate for this job instead of `--fps`.
+    #[serde(default)]
+    pub fps: Option<u32>,
+    /// Resolution (WxH) for this job instead of `--resolution`.
+    #[serde(default)]
+    pub resolution: Option<Resolution>,
+    /// Codec for this job instead of `--codec`.
+    #[serde(default)]
+    pub codec: Option<VideoCodec>,
+}
+
+impl BatchJob {
+    /// Returns `defaults` with this job's inputs, output, and overrides
+    /// merged in.
+    pub fn args(&self, defaults: &Args) -> Args {
+        Args {
+            reference: Some(self.reference.clone()),
+            audio: Some(self.audio.clone()),
+            output: Some(self.output.clone()),
+            batch: None,
+            input_list: None,
+            pair_dir: None,
+            audio_track: Vec::new(),
+            audio_language: self
+                .language
+                .clone()
+                .or_else(|| defaults.audio_language.clone()),
+            fps: self.fps.unwrap_or(defaults.fps),
+            resolution: self.resolution.unwrap_or(defaults.resolution),
+            codec: self.codec.or(defaults.codec),
+            ..defaults.clone()
+        }
+    }
+}
+
+/// A list of batch jobs.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub jobs: Vec<BatchJob>,
+}
+
+impl Manifest {
+    /// Loads a manifest, resolving relative paths against its directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            CliError::Batch(format!("Failed to read manifest {}: {e}", path.display()))
+        })?;
+        let mut manifest: Self = serde_json::from_str(&text).map_err(|e| {
+            CliError::Batch(format!(
+                "Invalid manifest {}: {}",
+                path.display(),
+                schema::explain(&e.to_string())
+            ))
+        })?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for job in &mut manifest.jobs {
+            for field in [&mut job.reference, &mut job.audio, &mut job.output] {
+                if !field.as_os_str().is_empty() {
+                    *field = base.join(&*field);
+                }
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Fails if a job has no output and no template supplied one.
+    pub fn check_outputs(&self) -> Result<()> {
+        match self
+            .jobs
+            .iter()
+            .position(|j| j.output.as_os_str().is_empty())
+        {
+            Some(i) => Err(CliError::Batch(format!(
+                "Job {} has no output; set \"output\" or pass --output-template",
+                i + 1
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An output recorded as complete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletedOutput {
+    /// File size when the job finished, used to detect partial rewrites.
+    pub size: u64,
+}
+
+/// Durable record of the outputs a batch has finished.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub completed: BTreeMap<PathBuf, CompletedOutput>,
+}
+
+impl Checkpoint {
+    /// Checkpoint location for a manifest (`jobs.json` -> `jobs.checkpoint.json`).
+    pub fn path_for(manifest: &Path) -> PathBuf {
+        manifest.with_extension("checkpoint.json")
+    }
+
+    /// Loads a checkpoint, or an empty one if the file does not exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| {
+                CliError::Batch(format!("Invalid checkpoint {}: {e}", path.display()))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the checkpoint atomically, so a crash never leaves it truncated.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| CliError::Batch(format!("Failed to encode checkpoint: {e}")))?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Records `output` as complete with its current size.
+    pub fn record(&mut self, output: &Path) -> Result<()> {
+        let size = std::fs::metadata(output)?.len();
+        self.completed
+            .insert(output.to_path_buf(), CompletedOutput { size });
+        Ok(())
+    }
+
+    /// Returns true if `output` was recorded and still matches on disk.
+    ///
+    /// A missing, empty, or resized file (e.g. partially rewritten after the
+    /// checkpoint) is not considered complete.
+    pub fn is_complete(&self, output: &Path) -> bool {
+        let Some(done) = self.completed.get(output) else {
+            return false;
+        };
+        std::fs::metadata(output).is_ok_and(|m| m.len() > 0 && m.len() == done.size)
+    }
+}
+
+/// Runs every job in the manifest through `render`, checkpointing each output.
+///
+/// See [`run_jobs`] for how failures, resuming, and logging are handled.
+pub async fn run_batch<F, Fut, E>(
+    manifest_path: &Path,
+    resume: bool,
+    retries: u32,
+    render: F,
+) -> Result<BatchReport>
+where
+    F: FnMut(BatchJob) -> Fut,
+    Fut: Future<Output = std::result::Result<(), E>>,
+    E: fmt::Display,
+{
+    let manifest = Manifest::load(manifest_path)?;
+    let checkpoint_path = Checkpoint::path_for(manifest_path);
+    run_jobs(manifest, &checkpoint_path, resume, retries, render).await
+}
+
+/// Runs every job through `render`, checkpointing each output at `checkpoint_path`.
+///
+/// With `resume`, jobs already recorded in the checkpoint are skipped. A
+/// failing job is retried up to `retries` times; if it still fails, it is
+/// recorded in the report and the batch moves on, leaving it for a resumed
+/// run. Only manifest and checkpoint errors abort the batch.
+///
+/// Each render runs inside a `job` span, so its logs (including the
+/// client's per-request spans) name the job they belong to.
+pub async fn run_jobs<F, Fut, E>(
+    manifest: Manifest,
+    checkpoint_path: &Path,
+    resume: bool,
+    retries: u32,
+    mut render: F,
+) -> Result<BatchReport>
+where
+    F: FnMut(BatchJob) -> Fut,
+    Fut: Future<Output = std::result::Result<(), E>>,
+    E: fmt::Display,
+{
+    let mut checkpoint = if resume {
+        Checkpoint::load(checkpoint_path)?
+    } else {
+        Checkpoint::default()
+    };
+
+    let total = manifest.jobs.len();
+    let mut report = BatchReport::default();
+    for (i, job) in manifest.jobs.into_iter().enumerate() {
+        let output = job.output.clone();
+        if checkpoint.is_complete(&output) {
+            tracing::info!("[{}/{total}] Skipping {} (done)", i + 1, output.display());
+            report.record(JobOutcome {
+                output,
+                status: JobStatus::Skipped,
+                attempts: 0,
+                error: None,
+            });
+            continue;
+        }
+
+        let mut attempts = 0;
+        let error = loop {
+            attempts += 1;
+            tracing::info!("[{}/{total}] Rendering {}", i + 1, output.display());
+            let span = tracing::info_span!("job", job = i + 1, output = %output.display());
+            match render(job.clone()).instrument(span).await {
+                Ok(()) => break None,
+                Err(e) if attempts <= retries => {
+                    tracing::warn!(
+                        "Job {} ({}) failed, retrying (attempt {attempts}/{retries}): {e:#}",
+                        i + 1,
+                        output.display()
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("Job {} ({}) failed: {e:#}", i + 1, output.display());
+                    break Some(format!("{e:#}"));
+                }
+            }
+        };
+
+        let status = match error {
+            Some(_) => JobStatus::Failed,
+            None => {
+                checkpoint.record(&output)?;
+                checkpoint.save(checkpoint_path)?;
+                JobStatus::Rendered
+            }
+        };
+        report.record(JobOutcome {
+            output,
+            status,
+            attempts,
+            error,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::test_support::{MockServer, frames_response, test_audio, test_image};
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    fn write_manifest(dir: &Path, outputs: &[&str]) -> PathBuf {
+        let jobs: Vec<_> = outputs
+            .iter()
+            .map(|o| serde_json::json!({"reference": "ref.png", "audio": "a.wav", "output": o}))
+            .collect();
+        let path = dir.join("jobs.json");
+        std::fs::write(&path, serde_json::json!({ "jobs": jobs }).to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_manifest_paths_relative_to_manifest() {
+        let dir = tempdir().unwrap();
+        let manifest = Manifest::load(&write_manifest(dir.path(), &["out.mp4"])).unwrap();
+        assert_eq!(manifest.jobs[0].output, dir.path().join("out.mp4"));
+        assert_eq!(manifest.jobs[0].reference, dir.path().join("ref.png"));
+    }
+
+    #[test]
+    fn test_job_overrides_merged_over_global_flags() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        std::fs::write(
+            &path,
+            r#"{"jobs": [
+                {"reference": "r.png", "audio": "a.wav", "output": "a.mp4", "fps": 60, "codec": "h265"},
+                {"reference": "r.png", "audio": "b.wav", "output": "b.mp4"}
+            ]}"#,
+        )
+        .unwrap();
+        let manifest = Manifest::load(&path).unwrap();
+        let defaults =
+            Args::try_parse_from_args(["musetalk-cli", "--batch", "jobs.json", "--fps", "25"])
+                .unwrap();
+
+        let dynamic = manifest.jobs[0].args(&defaults);
+        assert_eq!(dynamic.fps, 60);
+        assert_eq!(dynamic.codec, Some(VideoCodec::H265));
+        assert_eq!(dynamic.output, Some(dir.path().join("a.mp4")));
+        assert!(dynamic.batch.is_none());
+
+        let talking_head = manifest.jobs[1].args(&defaults);
+        assert_eq!(talking_head.fps, 25);
+        assert_eq!(talking_head.resolution.to_string(), "512x512");
+        assert_eq!(talking_head.codec, None);
+    }
+
+    #[test]
+    fn test_manifest_typo_suggests_key() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        std::fs::write(
+            &path,
+            r#"{"jobs": [{"reference": "r.png", "audoi": "a.wav", "output": "o.mp4"}]}"#,
+        )
+        .unwrap();
+
+        let message = Manifest::load(&path).unwrap_err().to_string();
+        assert!(message.contains("did you mean `audio`?"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_checkpointed_outputs() {
+        let dir = tempdir().unwrap();
+        let manifest = write_manifest(dir.path(), &["a.mp4", "b.mp4", "c.mp4"]);
+        let (a, b) = (dir.path().join("a.mp4"), dir.path().join("b.mp4"));
+        std::fs::write(&a, b"complete").unwrap();
+        std::fs::write(&b, b"complete").unwrap();
+
+        let mut checkpoint = Checkpoint::default();
+        checkpoint.record(&a).unwrap();
+        checkpoint.record(&b).unwrap();
+        checkpoint.save(&Checkpoint::path_for(&manifest)).unwrap();
+        // b was rewritten after the checkpoint and is now partial
+        std::fs::write(&b, b"part").unwrap();
+
+        let mut rendered = Vec::new();
+        let report = run_batch(&manifest, true, 0, |job| {
+            rendered.push(job.output.clone());
+            std::fs::write(&job.output, b"new").unwrap();
+            async { Ok::<(), CliError>(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(rendered, [b.clone(), dir.path().join("c.mp4")]);
+        assert_eq!((report.first_try(), report.skipped()), (2, 1));
+        let saved = Checkpoint::load(&Checkpoint::path_for(&manifest)).unwrap();
+        assert_eq!(saved.completed.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_jobs_reported_and_not_checkpointed() {
+        let dir = tempdir().unwrap();
+        let manifest = write_manifest(dir.path(), &["a.mp4", "b.mp4"]);
+
+        let report = run_batch(&manifest, false, 1, |_| async { Err("server down") })
+            .await
+            .unwrap();
+        assert_eq!(report.failed(), 2);
+        assert!(report.jobs.iter().all(|j| j.attempts == 2));
+        assert_eq!(report.jobs[0].error.as_deref(), Some("server down"));
+        assert!(!Checkpoint::path_for(&manifest).exists());
+    }
+
+    #[tokio::test]
+    async fn test_flaky_job_succeeds_on_retry() {
+        let dir = tempdir().unwrap();
+        let manifest = write_manifest(dir.path(), &["a.mp4"]);
+
+        let mut calls = 0;
+        let report = run_batch(&manifest, false, 2, |job| {
+            calls += 1;
+            let result = if calls < 2 {
+                Err("timeout")
+            } else {
+                std::fs::write(&job.output, b"done").unwrap();
+                Ok(())
+            };
+            async move { result }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!((report.retried(), report.failed()), (1, 0));
+        assert_eq!(report.jobs[0].attempts, 2);
+    }
+
+    /// Log sink shared with the test subscriber.
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_logs_carry_job_and_request_spans() {
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let server = MockServer::with_infer(|_| frames_response(1)).await;
+        let dir = tempdir().unwrap();
+        let manifest = write_manifest(dir.path(), &["a.mp4", "b.mp4"]);
+        run_batch(&manifest, false, 0, |job| {
+            let client = MuseTalkClient::new(server.url());
+            async move {
+                let options = InferenceOptions::new(1);
+                client
+                    .infer(
+                        ReferenceInput::Image(&test_image()),
+                        &test_audio(),
+                        &options,
+                    )
+                    .await?;
+                std::fs::write(&job.output, b"done")?;
+                Ok::<(), CliError>(())
+            }
+        })
+        .await
+        .unwrap();
+
+        let text = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let sends: Vec<_> = text
+            .lines()
+            .filter(|l| l.contains("Sending inference request"))
+            .collect();
+        assert_eq!(sends.len(), 2, "{text}");
+        for (i, line) in sends.iter().enumerate() {
+            assert!(line.contains(&format!("job{{job={}", i + 1)), "{line}");
+            assert!(
+                line.contains("request{kind=\"infer\" request_id="),
+                "{line}"
+            );
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Batch jobs from a directory of same-stem reference/audio pairs.
+//!
+//! With `--pair-dir`, `talk.wav` is rendered against `talk.mp4` (or
+//! `talk.png`) and written to `talk.lipsync.mp4` in the same directory.
+//! When a stem has both a video and an image, the video is used.
+
+use super::{BatchJob, Manifest};
+use crate::error::{CliError, Result};
+use crate::validation::{is_audio_path, is_image_reference, is_video_reference};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Extension inserted before `.mp4` in pair outputs.
+pub const OUTPUT_SUFFIX: &str = "lipsync";
+
+/// Pairs found in a directory, plus files without a partner.
+#[derive(Debug, Clone, Default)]
+pub struct Pairing {
+    pub manifest: Manifest,
+    /// Audio without a reference, and references without audio.
+    pub unmatched: Vec<PathBuf>,
+}
+
+/// Files sharing one stem.
+#[derive(Default)]
+struct Stem {
+    audio: Option<PathBuf>,
+    video: Option<PathBuf>,
+    image: Option<PathBuf>,
+}
+
+/// Pairs each audio file in `dir` with the reference sharing its stem.
+///
+/// Previous outputs (`*.lipsync.mp4`) and unrelated files are ignored.
+pub fn pair_directory(dir: &Path) -> Result<Pairing> {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        CliError::Batch(format!(
+            "Failed to read pair directory {}: {e}",
+            dir.display()
+        ))
+    })?;
+
+    let mut stems: BTreeMap<String, Stem> = BTreeMap::new();
+    for entry in entries {
+        let path = entry?.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || Path::new(stem).extension() == Some(OUTPUT_SUFFIX.as_ref()) {
+            continue;
+        }
+        let files = stems.entry(stem.to_string()).or_default();
+        if is_audio_path(&path) {
+            files.audio = Some(path);
+        } else if is_video_reference(&path) {
+            files.video = Some(path);
+        } else if is_image_reference(&path) {
+            files.image = Some(path);
+        }
+    }
+
+    let mut pairing = Pairing::default();
+    for (stem, files) in stems {
+        match (files.audio, files.video.or(files.image)) {
+            (Some(audio), Some(reference)) => pairing.manifest.jobs.push(BatchJob {
+                reference,
+                audio,
+                output: dir.join(format!("{stem}.{OUTPUT_SUFFIX}.mp4")),
+                ..BatchJob::default()
+            }),
+            (Some(lone), None) | (None, Some(lone)) => pairing.unmatched.push(lone),
+            (None, None) => {}
+        }
+    }
+    Ok(pairing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pairs_by_stem_and_reports_unmatched() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "alice.mp4",
+            "alice.wav",
+            "bob.png",
+            "bob.mp3",
+            "carol.jpg",
+            "carol.mp4",
+            "carol.flac",
+            "dave.wav",
+            "erin.png",
+            "alice.lipsync.mp4",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.path().join(name), b"x").unwrap();
+        }
+
+        let pairing = pair_directory(dir.path()).unwrap();
+        let pairs: Vec<_> = pairing
+            .manifest
+            .jobs
+            .iter()
+            .map(|j| {
+                let name = |p: &Path| p.file_name().unwrap().to_string_lossy().into_owned();
+                (name(&j.reference), name(&j.audio), name(&j.output))
+            })
+            .collect();
+        let expected = [
+            ("alice.mp4", "alice.wav", "alice.lipsync.mp4"),
+            ("bob.png", "bob.mp3", "bob.lipsync.mp4"),
+            ("carol.mp4", "carol.flac", "carol.lipsync.mp4"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(r, a, o)| (r.to_string(), a.to_string(), o.to_string()))
+            .collect();
+        assert_eq!(pairs, expected);
+        assert_eq!(
+            pairing.unmatched,
+            [dir.path().join("dave.wav"), dir.path().join("erin.png")]
+        );
+    }
+
+    #[test]
+    fn test_missing_directory_is_batch_error() {
+        assert!(matches!(
+            pair_directory(Path::new("/nonexistent/pairs")),
+            Err(CliError::Batch(_))
+        ));
+    }
+}
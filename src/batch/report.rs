@@ -0,0 +1,189 @@
+//! Per-job outcomes of a batch run, shown as a table and a JSON summary.
+
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Final state of one batch job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Rendered, possibly after retries.
+    Rendered,
+    /// Already complete in the checkpoint.
+    Skipped,
+    /// Still failing after every attempt.
+    Failed,
+}
+
+/// What happened to one job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobOutcome {
+    pub output: PathBuf,
+    pub status: JobStatus,
+    /// Render attempts made (0 for skipped jobs).
+    pub attempts: u32,
+    /// Error from the last failed attempt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl JobOutcome {
+    /// Label for the status column, separating first-try renders from retried ones.
+    fn label(&self) -> &'static str {
+        match self.status {
+            JobStatus::Rendered if self.attempts > 1 => "retried",
+            JobStatus::Rendered => "ok",
+            JobStatus::Skipped => "skipped",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Aggregated outcomes of a batch run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BatchReport {
+    pub jobs: Vec<JobOutcome>,
+}
+
+/// Counts included at the top of the JSON summary.
+#[derive(Serialize)]
+struct Summary<'a> {
+    first_try: usize,
+    retried: usize,
+    failed: usize,
+    skipped: usize,
+    jobs: &'a [JobOutcome],
+}
+
+impl BatchReport {
+    /// Adds a job's outcome.
+    pub fn record(&mut self, outcome: JobOutcome) {
+        self.jobs.push(outcome);
+    }
+
+    /// Jobs rendered on their first attempt.
+    pub fn first_try(&self) -> usize {
+        self.count(|j| j.status == JobStatus::Rendered && j.attempts <= 1)
+    }
+
+    /// Jobs rendered only after one or more retries.
+    pub fn retried(&self) -> usize {
+        self.count(|j| j.status == JobStatus::Rendered && j.attempts > 1)
+    }
+
+    /// Jobs that failed on every attempt.
+    pub fn failed(&self) -> usize {
+        self.count(|j| j.status == JobStatus::Failed)
+    }
+
+    /// Jobs skipped as already complete.
+    pub fn skipped(&self) -> usize {
+        self.count(|j| j.status == JobStatus::Skipped)
+    }
+
+    /// Returns true if any job ultimately failed.
+    pub fn has_failures(&self) -> bool {
+        self.failed() > 0
+    }
+
+    /// Machine-readable summary of the counts and every job.
+    pub fn to_json(&self) -> String {
+        let summary = Summary {
+            first_try: self.first_try(),
+            retried: self.retried(),
+            failed: self.failed(),
+            skipped: self.skipped(),
+            jobs: &self.jobs,
+        };
+        serde_json::to_string_pretty(&summary).unwrap_or_default()
+    }
+
+    fn count(&self, predicate: impl Fn(&JobOutcome) -> bool) -> usize {
+        self.jobs.iter().filter(|j| predicate(j)).count()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .jobs
+            .iter()
+            .map(|j| j.output.display().to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("Output".len());
+        writeln!(
+            f,
+            "{:<4} {:<8} {:>8}  {:<width$}  Error",
+            "Job", "Status", "Attempts", "Output"
+        )?;
+        for (i, job) in self.jobs.iter().enumerate() {
+            let line = format!(
+                "{:<4} {:<8} {:>8}  {:<width$}  {}",
+                i + 1,
+                job.label(),
+                job.attempts,
+                job.output.display(),
+                job.error.as_deref().unwrap_or("")
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        write!(
+            f,
+            "Batch complete: {} first try, {} retried, {} failed, {} skipped",
+            self.first_try(),
+            self.retried(),
+            self.failed(),
+            self.skipped()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(output: &str, status: JobStatus, attempts: u32, error: Option<&str>) -> JobOutcome {
+        JobOutcome {
+            output: PathBuf::from(output),
+            status,
+            attempts,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_report_from_mixed_outcomes() {
+        let mut report = BatchReport::default();
+        report.record(outcome("a.mp4", JobStatus::Rendered, 1, None));
+        report.record(outcome("b.mp4", JobStatus::Rendered, 3, None));
+        report.record(outcome("c.mp4", JobStatus::Failed, 2, Some("server down")));
+        report.record(outcome("d.mp4", JobStatus::Skipped, 0, None));
+
+        assert_eq!(
+            (
+                report.first_try(),
+                report.retried(),
+                report.failed(),
+                report.skipped()
+            ),
+            (1, 1, 1, 1)
+        );
+        assert!(report.has_failures());
+
+        let table = report.to_string();
+        assert!(table.contains("2    retried         3  b.mp4"), "{table}");
+        assert!(
+            table.contains("failed          2  c.mp4   server down"),
+            "{table}"
+        );
+        assert!(table.ends_with("1 first try, 1 retried, 1 failed, 1 skipped"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["jobs"][2]["status"], "failed");
+        assert_eq!(json["jobs"][2]["error"], "server down");
+        assert!(json["jobs"][0].get("error").is_none());
+    }
+}
@@ -0,0 +1,265 @@
+//! Output naming for batch jobs (`--output-template`).
+//!
+//! A template such as `out/{audio_stem}_{fps}fps_{date}.mp4` names each
+//! job's output from its inputs and the render settings. Relative results
+//! are resolved against the manifest's directory.
+
+use super::Manifest;
+use crate::error::{CliError, Result};
+use crate::resolution::Resolution;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Variables a template may reference.
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "audio_stem",
+    "reference_stem",
+    "fps",
+    "resolution",
+    "date",
+    "index",
+];
+
+/// A template placeholder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variable {
+    AudioStem,
+    ReferenceStem,
+    Fps,
+    Resolution,
+    Date,
+    Index,
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "audio_stem" => Some(Self::AudioStem),
+            "reference_stem" => Some(Self::ReferenceStem),
+            "fps" => Some(Self::Fps),
+            "resolution" => Some(Self::Resolution),
+            "date" => Some(Self::Date),
+            "index" => Some(Self::Index),
+            _ => None,
+        }
+    }
+}
+
+/// Piece of a parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Variable(Variable),
+}
+
+/// A parsed output filename template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    parts: Vec<Part>,
+}
+
+/// Render settings shared by every job.
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub fps: u32,
+    pub resolution: Resolution,
+    /// UTC date as `YYYY-MM-DD`.
+    pub date: String,
+}
+
+impl TemplateContext {
+    /// Context for the given settings, dated today (UTC).
+    pub fn new(fps: u32, resolution: Resolution) -> Self {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() / 86_400);
+        Self {
+            fps,
+            resolution,
+            date: civil_date(days as i64),
+        }
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            |reason: String| CliError::Batch(format!("Invalid output template '{s}': {reason}"));
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find('{') {
+            let close = rest[open..]
+                .find('}')
+                .map(|i| open + i)
+                .ok_or_else(|| invalid("unterminated placeholder".to_string()))?;
+            let name = &rest[open + 1..close];
+            let variable = Variable::from_name(name).ok_or_else(|| {
+                invalid(format!(
+                    "unknown variable {{{name}}} (known: {})",
+                    TEMPLATE_VARIABLES.join(", ")
+                ))
+            })?;
+            if open > 0 {
+                parts.push(Part::Literal(rest[..open].to_string()));
+            }
+            parts.push(Part::Variable(variable));
+            rest = &rest[close + 1..];
+        }
+        if rest.contains('}') {
+            return Err(invalid("unmatched '}'".to_string()));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        if !parts.iter().any(|p| matches!(p, Part::Variable(_))) {
+            return Err(invalid(
+                "needs at least one variable, or every job gets the same name".to_string(),
+            ));
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl OutputTemplate {
+    /// Renders the output path for the `index`th job (1-based).
+    pub fn render(
+        &self,
+        reference: &Path,
+        audio: &Path,
+        index: usize,
+        context: &TemplateContext,
+    ) -> PathBuf {
+        let stem = |p: &Path| {
+            p.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Variable(variable) => out.push_str(&match variable {
+                    Variable::AudioStem => stem(audio),
+                    Variable::ReferenceStem => stem(reference),
+                    Variable::Fps => context.fps.to_string(),
+                    Variable::Resolution => context.resolution.to_string(),
+                    Variable::Date => context.date.clone(),
+                    Variable::Index => index.to_string(),
+                }),
+            }
+        }
+        PathBuf::from(out)
+    }
+
+    /// Replaces every job's output with the rendered template, relative to
+    /// `base`, failing if two jobs would write the same file.
+    ///
+    /// A job's own `fps` and `resolution` take precedence over `context`.
+    pub fn apply(
+        &self,
+        manifest: &mut Manifest,
+        base: &Path,
+        context: &TemplateContext,
+    ) -> Result<()> {
+        let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+        for (i, job) in manifest.jobs.iter_mut().enumerate() {
+            let job_context = TemplateContext {
+                fps: job.fps.unwrap_or(context.fps),
+                resolution: job.resolution.unwrap_or(context.resolution),
+                date: context.date.clone(),
+            };
+            job.output = base.join(self.render(&job.reference, &job.audio, i + 1, &job_context));
+            if let Some(first) = seen.insert(job.output.clone(), i + 1) {
+                return Err(CliError::Batch(format!(
+                    "Output template gives jobs {first} and {} the same output {}; add {{index}} or a stem",
+                    i + 1,
+                    job.output.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts days since the Unix epoch to a `YYYY-MM-DD` date.
+fn civil_date(days: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::BatchJob;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            fps: 25,
+            resolution: "512x512".parse().unwrap(),
+            date: "2026-10-14".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_template_rendered_from_job_inputs() {
+        let template: OutputTemplate =
+            "renders/{audio_stem}_{reference_stem}_{fps}fps_{resolution}_{date}_{index}.mp4"
+                .parse()
+                .unwrap();
+        let path = template.render(
+            Path::new("refs/avatar.png"),
+            Path::new("audio/intro.wav"),
+            3,
+            &context(),
+        );
+        assert_eq!(
+            path,
+            Path::new("renders/intro_avatar_25fps_512x512_2026-10-14_3.mp4")
+        );
+    }
+
+    #[test]
+    fn test_unknown_variable_and_duplicate_outputs_rejected() {
+        let err = "{audio}.mp4".parse::<OutputTemplate>().unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variable {audio}"),
+            "{err}"
+        );
+        assert!("fixed.mp4".parse::<OutputTemplate>().is_err());
+
+        let job = |audio: &str| BatchJob {
+            reference: PathBuf::from("avatar.png"),
+            audio: PathBuf::from(audio),
+            output: PathBuf::new(),
+            ..BatchJob::default()
+        };
+        let mut manifest = Manifest {
+            jobs: vec![job("a.wav"), job("b.wav"), job("dir/a.wav")],
+        };
+        let template: OutputTemplate = "{audio_stem}.mp4".parse().unwrap();
+        let err = template
+            .apply(&mut manifest, Path::new("/out"), &context())
+            .unwrap_err();
+        assert!(err.to_string().contains("jobs 1 and 3"), "{err}");
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(20_740), "2026-10-14");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+    }
+}
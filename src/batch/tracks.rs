@@ -0,0 +1,132 @@
+//! One render per language from `--audio-track LANG=PATH` entries.
+//!
+//! Each track renders the same reference against its own audio and writes
+//! next to `--output` with the language inserted before the extension, so
+//! `talk.mp4` with tracks `en` and `de` gives `talk.en.mp4` and `talk.de.mp4`.
+
+use super::{BatchJob, Manifest};
+use crate::error::{CliError, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A language-tagged audio file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioTrack {
+    /// Language tag such as `en` or `pt-BR`.
+    pub language: String,
+    pub audio: PathBuf,
+}
+
+/// Parses `LANG=PATH`.
+impl FromStr for AudioTrack {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |why: &str| {
+            CliError::Batch(format!(
+                "Invalid audio track '{s}': {why} (expected LANG=PATH, e.g. en=intro.wav)"
+            ))
+        };
+        let (language, audio) = s.split_once('=').ok_or_else(|| invalid("missing '='"))?;
+        if language.is_empty()
+            || !language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(invalid("language must be letters, digits, or '-'"));
+        }
+        if audio.is_empty() {
+            return Err(invalid("missing audio path"));
+        }
+        Ok(Self {
+            language: language.to_string(),
+            audio: PathBuf::from(audio),
+        })
+    }
+}
+
+/// Output for `language`: `out/talk.mp4` -> `out/talk.<language>.mp4`.
+pub fn track_output(output: &Path, language: &str) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}.{language}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.{language}"),
+    };
+    output.with_file_name(name)
+}
+
+/// One job per track, all against `reference`.
+///
+/// Language tags must be unique (ignoring case), since they name the outputs.
+pub fn track_jobs(reference: &Path, output: &Path, tracks: &[AudioTrack]) -> Result<Manifest> {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for track in tracks {
+        if let Some(first) = seen.insert(track.language.to_ascii_lowercase(), &track.language) {
+            return Err(CliError::Batch(format!(
+                "Audio track language '{}' is given more than once (first as '{first}')",
+                track.language
+            )));
+        }
+    }
+    let jobs = tracks
+        .iter()
+        .map(|track| BatchJob {
+            reference: reference.to_path_buf(),
+            audio: track.audio.clone(),
+            output: track_output(output, &track.language),
+            language: Some(track.language.clone()),
+            ..BatchJob::default()
+        })
+        .collect();
+    Ok(Manifest { jobs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_tracks_give_language_named_outputs() {
+        let tracks: Vec<AudioTrack> = ["en=audio/intro_en.wav", "pt-BR=audio/intro_pt.wav"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let manifest =
+            track_jobs(Path::new("avatar.png"), Path::new("out/intro.mp4"), &tracks).unwrap();
+
+        let outputs: Vec<_> = manifest.jobs.iter().map(|j| j.output.clone()).collect();
+        assert_eq!(
+            outputs,
+            [
+                PathBuf::from("out/intro.en.mp4"),
+                PathBuf::from("out/intro.pt-BR.mp4")
+            ]
+        );
+        assert_eq!(manifest.jobs[1].audio, Path::new("audio/intro_pt.wav"));
+        assert_eq!(manifest.jobs[1].language.as_deref(), Some("pt-BR"));
+        assert!(
+            manifest
+                .jobs
+                .iter()
+                .all(|j| j.reference == Path::new("avatar.png"))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_and_malformed_tracks_rejected() {
+        let tracks = ["en=a.wav", "EN=b.wav"].map(|s| s.parse::<AudioTrack>().unwrap());
+        let err = track_jobs(Path::new("r.png"), Path::new("o.mp4"), &tracks).unwrap_err();
+        assert!(
+            err.to_string().contains("'EN' is given more than once"),
+            "{err}"
+        );
+
+        assert!("a.wav".parse::<AudioTrack>().is_err());
+        assert!("en/us=a.wav".parse::<AudioTrack>().is_err());
+        assert!("en=".parse::<AudioTrack>().is_err());
+    }
+}
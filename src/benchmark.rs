@@ -0,0 +1,175 @@
+//! Synthetic round-trip benchmark for capacity planning.
+//!
+//! Builds a fixed test image and a sine tone in memory, sends them through
+//! the normal inference path, and reports throughput without writing video.
+
+use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+use crate::error::Result;
+use crate::loader::{AudioData, ImageData, encode_png};
+use std::fmt;
+use std::time::Instant;
+
+/// Sample rate of the synthetic audio.
+const SAMPLE_RATE: u32 = 16000;
+
+/// Side length of the synthetic reference image.
+const IMAGE_SIZE: u32 = 256;
+
+/// Timing for one benchmark request.
+#[derive(Debug, Clone)]
+pub struct IterationTiming {
+    /// Round-trip latency in seconds.
+    pub latency_secs: f64,
+    /// Frames returned by the server.
+    pub frames: usize,
+    /// Total base64 frame data received, in bytes.
+    pub response_bytes: usize,
+}
+
+impl IterationTiming {
+    /// Frames generated per second of round-trip time.
+    pub fn frames_per_sec(&self) -> f64 {
+        if self.latency_secs > 0.0 {
+            self.frames as f64 / self.latency_secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Results of a benchmark run.
+#[derive(Debug, Clone)]
+pub struct BenchmarkReport {
+    /// Base64 payload sent per request, in bytes.
+    pub request_bytes: usize,
+    /// Seconds of synthetic audio per request.
+    pub audio_secs: f32,
+    pub iterations: Vec<IterationTiming>,
+}
+
+impl BenchmarkReport {
+    /// Mean round-trip latency in seconds.
+    pub fn mean_latency_secs(&self) -> f64 {
+        mean(self.iterations.iter().map(|i| i.latency_secs))
+    }
+
+    /// Mean frames per second across iterations.
+    pub fn mean_frames_per_sec(&self) -> f64 {
+        mean(self.iterations.iter().map(IterationTiming::frames_per_sec))
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Benchmark: {:.1}s audio, {:.2} MB request",
+            self.audio_secs,
+            self.request_bytes as f64 / 1_000_000.0
+        )?;
+        for (i, it) in self.iterations.iter().enumerate() {
+            writeln!(
+                f,
+                "  run {}: {:.3}s, {} frames, {:.1} fps, {:.2} MB response",
+                i + 1,
+                it.latency_secs,
+                it.frames,
+                it.frames_per_sec(),
+                it.response_bytes as f64 / 1_000_000.0
+            )?;
+        }
+        write!(
+            f,
+            "  mean: {:.3}s latency, {:.1} fps",
+            self.mean_latency_secs(),
+            self.mean_frames_per_sec()
+        )
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0), |(s, n), v| (s + v, n + 1));
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+/// Builds the fixed synthetic reference image and `audio_secs` of 220 Hz tone.
+pub fn synthetic_inputs(audio_secs: f32) -> Result<(ImageData, AudioData)> {
+    let img = image::RgbImage::from_fn(IMAGE_SIZE, IMAGE_SIZE, |x, y| {
+        image::Rgb([x as u8, y as u8, 128])
+    });
+    let image = ImageData::from_bytes(&encode_png(&img, None)?)?;
+
+    let count = (audio_secs * SAMPLE_RATE as f32) as usize;
+    let samples = (0..count)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            (t * 220.0 * 2.0 * std::f32::consts::PI).sin() * 0.5
+        })
+        .collect();
+    let audio = AudioData::from_samples(samples, SAMPLE_RATE, 1)?;
+    Ok((image, audio))
+}
+
+/// Runs `iterations` synthetic inference requests and times each one.
+pub async fn run_benchmark(
+    client: &MuseTalkClient,
+    audio_secs: f32,
+    iterations: u32,
+    options: &InferenceOptions,
+) -> Result<BenchmarkReport> {
+    let (image, audio) = synthetic_inputs(audio_secs)?;
+    let mut timings = Vec::new();
+    for i in 0..iterations {
+        let start = Instant::now();
+        let response = client
+            .infer(ReferenceInput::Image(&image), &audio, options)
+            .await?;
+        let latency_secs = start.elapsed().as_secs_f64();
+        tracing::debug!("Benchmark run {} took {latency_secs:.3}s", i + 1);
+        timings.push(IterationTiming {
+            latency_secs,
+            frames: response.frames.len(),
+            response_bytes: response.frames.iter().map(|f| f.data.len()).sum(),
+        });
+    }
+    Ok(BenchmarkReport {
+        request_bytes: image.base64_png.len() + audio.base64_wav.len(),
+        audio_secs: audio.duration_secs,
+        iterations: timings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockServer, frames_response};
+
+    #[test]
+    fn test_synthetic_inputs() {
+        let (image, audio) = synthetic_inputs(2.0).unwrap();
+        assert_eq!((image.width, image.height), (IMAGE_SIZE, IMAGE_SIZE));
+        assert_eq!(audio.sample_rate, SAMPLE_RATE);
+        assert!((audio.duration_secs - 2.0).abs() < 1e-3);
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_report_populated() {
+        let server = MockServer::with_infer(|_| frames_response(4)).await;
+        let client = MuseTalkClient::new(server.url());
+
+        let report = run_benchmark(&client, 1.0, 2, &InferenceOptions::new(25))
+            .await
+            .unwrap();
+
+        assert_eq!(report.iterations.len(), 2);
+        assert!(report.request_bytes > 0);
+        for it in &report.iterations {
+            assert_eq!(it.frames, 4);
+            assert!(it.latency_secs > 0.0);
+            assert!(it.response_bytes > 0);
+        }
+        assert!(report.mean_frames_per_sec() > 0.0);
+        assert!(report.to_string().contains("run 2"));
+        assert_eq!(server.requests_to("/infer").len(), 2);
+    }
+}
@@ -0,0 +1,497 @@
+//! Command-line interface argument parsing.
+
+use crate::assembler::{
+    Background, FrameBackground, FramePattern, PixelFormat, SyncLength, VideoCodec,
+};
+use crate::batch::{AudioTrack, OutputTemplate};
+use crate::client::{CertPin, HeaderArg};
+use crate::duration_check::DurationAssertion;
+use crate::face::FaceCenter;
+use crate::ffmpeg::FfmpegConfig;
+use crate::hook::PostHook;
+use crate::loader::{AudioUrl, WavFormat};
+use crate::resolution::Resolution;
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser};
+use std::path::PathBuf;
+
+/// MuseTalk CLI - Generate lip-synced avatar videos.
+///
+/// Takes a reference (static image or video) and an audio file, produces
+/// an animated video of the avatar speaking with realistic lip movements.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "musetalk-cli")]
+#[command(version, about, long_about = None)]
+#[command(group = ArgGroup::new("batch_input").args(["batch", "input_list"]))]
+pub struct Args {
+    /// Path to reference image (PNG/JPEG/WebP/BMP) or video (MP4)
+    #[arg(short = 'r', long, required_unless_present_any = ["init_config", "fetch", "fetch_frames", "benchmark", "compare_servers_matrix", "batch", "pair_dir", "list_codecs", "audio_info"])]
+    pub reference: Option<PathBuf>,
+
+    /// Path to audio file (WAV/MP3/FLAC)
+    #[arg(short, long, required_unless_present_any = ["init_config", "benchmark", "compare_servers_matrix", "batch", "input_list", "pair_dir", "list_codecs", "audio_url", "audio_track"])]
+    pub audio: Option<PathBuf>,
+
+    /// URL the server fetches the audio from, instead of uploading --audio
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with_all = ["audio", "batch", "start_time", "end_time", "lead_in", "audio_gain", "preprocess_audio", "mono"]
+    )]
+    pub audio_url: Option<AudioUrl>,
+
+    /// Path for output video (MP4)
+    #[arg(short, long, required_unless_present_any = ["init_config", "queue", "benchmark", "compare_servers_matrix", "batch", "input_list", "pair_dir", "list_codecs", "audio_info"])]
+    pub output: Option<PathBuf>,
+
+    /// Also encode the same frames into this file (repeatable, e.g. a .webm copy)
+    #[arg(long, value_name = "PATH", requires = "output", conflicts_with_all = ["batch", "queue"])]
+    pub also_output: Vec<PathBuf>,
+
+    /// MuseTalk server URL
+    #[arg(short, long, default_value = "http://localhost:3015")]
+    pub server: String,
+
+    /// Model variant to request (server default when unset)
+    #[arg(long, value_name = "NAME")]
+    pub model: Option<String>,
+
+    /// Output resolution (WxH); image references are fitted and padded to it
+    #[arg(long, default_value = "512x512")]
+    pub resolution: Resolution,
+
+    /// Frame rate
+    #[arg(short, long, default_value_t = 30)]
+    pub fps: u32,
+
+    /// Snap --fps to a common rate (24, 25, 30, 50, 60) within 10% to avoid mux timing drift
+    #[arg(long, alias = "normalize-fps-to-common")]
+    pub snap_fps: bool,
+
+    /// Cap total frames; lowers fps to fit the audio duration (experimental)
+    #[arg(long, value_name = "N")]
+    pub max_frames: Option<u32>,
+
+    /// Reject audio shorter than this many seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = crate::validation::DEFAULT_MIN_AUDIO_DURATION)]
+    pub min_audio_duration: f64,
+
+    /// Downmix audio to mono before sending
+    #[arg(long)]
+    pub mono: bool,
+
+    /// Resample audio to this rate (and downmix to mono) before sending
+    #[arg(long, value_name = "HZ", default_value_t = crate::loader::MUSETALK_SAMPLE_RATE, value_parser = clap::value_parser!(u32).range(1..))]
+    pub target_sample_rate: u32,
+
+    /// Drop source metadata (artist, software, ...) from the output video
+    #[arg(long)]
+    pub strip_audio_metadata: bool,
+
+    /// Convert audio to 16 kHz mono and normalize its peak before sending
+    #[arg(long)]
+    pub preprocess_audio: bool,
+
+    /// Measure the whole clip, then scale it to a fixed RMS for consistent loudness
+    #[arg(long, conflicts_with = "audio_url")]
+    pub two_pass_audio_analysis: bool,
+
+    /// Rebuild a WAV header that fails to parse, using --assume-format
+    #[arg(long, conflicts_with = "audio_url")]
+    pub repair_wav: bool,
+
+    /// Real format of a WAV repaired by --repair-wav, as RATE:CHANNELS:BITS
+    #[arg(long, value_name = "FORMAT", default_value_t = WavFormat::default(), requires = "repair_wav")]
+    pub assume_format: WavFormat,
+
+    /// Manual face center coordinates (X,Y); must lie inside an image reference
+    #[arg(long)]
+    pub face_center: Option<FaceCenter>,
+
+    /// PNG compression level (0-9) for re-encoded images; higher is smaller but slower
+    #[arg(long, value_name = "0-9", value_parser = clap::value_parser!(u8).range(0..=9))]
+    pub png_compression: Option<u8>,
+
+    /// Downscale an image reference whose encoded size exceeds this many megabytes
+    #[arg(long, value_name = "MB")]
+    pub downscale_reference_if_over: Option<f64>,
+
+    /// Convert images with an embedded ICC profile to sRGB before sending
+    #[arg(long)]
+    pub color_manage: bool,
+
+    /// Tone-map 16-bit or HDR reference images to SDR instead of clipping highlights
+    #[arg(long)]
+    pub tonemap: bool,
+
+    /// Extra HTTP header sent to the server and --upscale-server ("Key: Value", repeatable)
+    #[arg(long = "header", value_name = "KEY: VALUE")]
+    pub headers: Vec<HeaderArg>,
+
+    /// FFmpeg binary to run (ffprobe is looked up next to it)
+    #[arg(
+        long = "ffmpeg-path",
+        value_name = "PATH",
+        env = "FFMPEG_PATH",
+        default_value = "ffmpeg"
+    )]
+    pub ffmpeg: FfmpegConfig,
+
+    /// Video codec to encode with (default depends on the output container)
+    #[arg(long, value_enum, value_name = "CODEC")]
+    pub codec: Option<VideoCodec>,
+
+    /// If --codec's encoder is missing, fall back (h265 -> h264, vp9 -> vp8 -> h264)
+    #[arg(long, requires = "codec")]
+    pub codec_fallback: bool,
+
+    /// Output pixel format (e.g. yuvj420p for players that need full range)
+    #[arg(long, value_name = "FORMAT", default_value_t = PixelFormat::default())]
+    pub pix_fmt: PixelFormat,
+
+    /// Copy the input audio as-is when its codec fits the output (e.g. AAC into MP4)
+    #[arg(long)]
+    pub auto_audio: bool,
+
+    /// Have the server return a finished video when it supports it (skips local encoding)
+    #[arg(long, conflicts_with_all = [
+        "frame_manifest", "landmarks_out", "also_output", "hold_last", "sync_length",
+        "pix_fmt", "codec", "audio_language",
+    ])]
+    pub server_assemble: bool,
+
+    /// Encode frames while the server is still streaming them, when it supports streaming
+    #[arg(long, conflicts_with_all = [
+        "server_assemble", "also_output", "keep_frames", "frame_manifest", "landmarks_out",
+        "upscale_server", "sync_length", "hold_last",
+    ])]
+    pub pipeline: bool,
+
+    /// Print the audio's format, loudness (LUFS), and true peak, then exit
+    #[arg(long)]
+    pub audio_info: bool,
+
+    /// List which codecs the installed FFmpeg can encode and exit
+    #[arg(long)]
+    pub list_codecs: bool,
+
+    /// Render only from this many seconds into the audio
+    #[arg(long, value_name = "SECS")]
+    pub start_time: Option<f64>,
+
+    /// Render only up to this many seconds into the audio
+    #[arg(long, value_name = "SECS")]
+    pub end_time: Option<f64>,
+
+    /// Write the samples sent to the server as a (frames, channels) float32 .npy file
+    #[arg(long, value_name = "PATH.npy")]
+    pub dump_samples: Option<PathBuf>,
+
+    /// Apply a fixed gain in dB (negative attenuates); peaks are clamped
+    #[arg(long, value_name = "DB", allow_hyphen_values = true)]
+    pub audio_gain: Option<f32>,
+
+    /// Prepend this much silence so the first phoneme isn't clipped (try 100)
+    #[arg(long, value_name = "MILLISECONDS")]
+    pub lead_in: Option<u32>,
+
+    /// Loop a video reference shorter than the audio to cover its length
+    #[arg(long)]
+    pub reference_loop: bool,
+
+    /// Transcode a video reference to H.264 at --resolution and --fps before sending
+    #[arg(long)]
+    pub normalize_reference: bool,
+
+    /// Composite the static fallback over this image or looping video
+    #[arg(long, value_name = "PATH")]
+    pub background: Option<Background>,
+
+    /// If the server is down and the reference is a video, reuse the last good render of the same inputs
+    #[arg(long, conflicts_with = "audio_url")]
+    pub stale_ok: bool,
+
+    /// Flatten transparent server frames over a #RRGGBB color or an image (default black)
+    #[arg(long, value_name = "COLOR|PATH")]
+    pub frame_background: Option<FrameBackground>,
+
+    /// Keep the server's frames in this directory instead of a temp dir
+    #[arg(long, value_name = "DIR")]
+    pub keep_frames: Option<PathBuf>,
+
+    /// Stage files in a fixed, persistent directory under the system temp dir (debugging)
+    #[arg(long, value_name = "NAME", conflicts_with = "keep_frames")]
+    pub temp_name: Option<String>,
+
+    /// Write a JSON map of frame indices to the audio time each request covered
+    #[arg(long, value_name = "PATH")]
+    pub frame_manifest: Option<PathBuf>,
+
+    /// Write the server's per-frame face box and landmarks as JSON, when it sends them
+    #[arg(long, value_name = "PATH")]
+    pub landmarks_out: Option<PathBuf>,
+
+    /// Frame filename template with one {index} or {index:WIDTH} placeholder
+    #[arg(long, value_name = "PATTERN", default_value = "frame_{index:5}.png")]
+    pub frame_pattern: FramePattern,
+
+    /// Number of the first frame file (e.g. 1 for one-based naming)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub frame_start: u32,
+
+    /// Upscale returned frames through this super-resolution server before assembly
+    #[arg(long, value_name = "URL")]
+    pub upscale_server: Option<String>,
+
+    /// Show a full-screen progress display (needs the `tui` build feature)
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Render and open a fast low-res preview before the full render
+    #[arg(long)]
+    pub preview_stream: bool,
+
+    /// Send a tiny synthetic inference first so the model is loaded
+    #[arg(long)]
+    pub warmup: bool,
+
+    /// Submit the job to the server's queue, print its ID, and exit
+    #[arg(long, conflicts_with = "fetch")]
+    pub queue: bool,
+
+    /// Fetch and assemble a previously queued job (needs --audio and --output)
+    #[arg(long, value_name = "JOB_ID")]
+    pub fetch: Option<String>,
+
+    /// Re-download a finished job's stored frames in pages and assemble them (needs --audio and --output)
+    #[arg(long, value_name = "JOB_ID", conflicts_with_all = ["fetch", "queue"])]
+    pub fetch_frames: Option<String>,
+
+    /// Render every job in a JSON manifest, checkpointing finished outputs
+    #[arg(long, value_name = "MANIFEST", conflicts_with_all = ["queue", "fetch"])]
+    pub batch: Option<PathBuf>,
+
+    /// Render --reference against each audio path listed in a file (one per line, # comments)
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires_all = ["reference", "output_template"],
+        conflicts_with_all = ["audio", "audio_url", "queue", "fetch"]
+    )]
+    pub input_list: Option<PathBuf>,
+
+    /// Render every same-stem reference/audio pair in a directory (e.g. talk.mp4 + talk.wav)
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["batch", "input_list", "queue", "fetch", "audio_url"])]
+    pub pair_dir: Option<PathBuf>,
+
+    /// Render one output per language, e.g. --audio-track en=intro_en.wav (repeatable)
+    #[arg(
+        long,
+        value_name = "LANG=PATH",
+        requires = "output",
+        conflicts_with_all = ["audio", "audio_url", "batch", "input_list", "pair_dir", "queue", "fetch"]
+    )]
+    pub audio_track: Vec<AudioTrack>,
+
+    /// Tag each --audio-track output's audio stream with its language
+    #[arg(long, requires = "audio_track")]
+    pub tag_language: bool,
+
+    /// Language tag written to the output's audio stream metadata
+    #[arg(long, value_name = "LANG")]
+    pub audio_language: Option<String>,
+
+    /// Skip batch jobs whose outputs the checkpoint records as complete
+    #[arg(long, requires = "batch_input")]
+    pub resume_batch: bool,
+
+    /// Name batch outputs from a template, e.g. {audio_stem}_{fps}fps_{date}.mp4
+    #[arg(long, value_name = "TEMPLATE", requires = "batch_input")]
+    pub output_template: Option<OutputTemplate>,
+
+    /// Retry each failed batch job up to this many times
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "batch_input")]
+    pub batch_retries: u32,
+
+    /// Match the video length to the audio: trim, pad, or stretch
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub sync_length: Option<SyncLength>,
+
+    /// Hold the last frame this many seconds after the speech ends (adds silence)
+    #[arg(long, value_name = "SECONDS", conflicts_with = "sync_length")]
+    pub hold_last: Option<f64>,
+
+    /// Seconds to wait for a connection to the server
+    #[arg(long, value_name = "SECS", default_value_t = crate::client::timeouts::DEFAULT_CONNECT_TIMEOUT_SECS)]
+    pub connect_timeout: u64,
+
+    /// Seconds a server health check is reused by later renders (0 disables)
+    #[arg(long, value_name = "SECS", default_value_t = crate::client::health_cache::DEFAULT_HEALTH_CACHE_TTL_SECS)]
+    pub health_cache_ttl: u64,
+
+    /// Seconds to wait for inference to finish once connected
+    #[arg(long, value_name = "SECS", default_value_t = crate::client::timeouts::DEFAULT_READ_TIMEOUT_SECS)]
+    pub read_timeout: u64,
+
+    /// Retries when the server is busy (429 with Retry-After), unreachable, or
+    /// returns 502/503/504 (with exponential backoff), or, with
+    /// --retry-on-empty, returns no frames
+    #[arg(long, value_name = "N", default_value_t = crate::client::retry::DEFAULT_MAX_RETRIES)]
+    pub max_retries: u32,
+
+    /// Retry responses with no (or far too few) frames, up to --max-retries
+    #[arg(long, overrides_with = "no_retry_on_empty")]
+    pub retry_on_empty: bool,
+
+    /// Don't retry empty responses, even if the config file enables it
+    #[arg(long, overrides_with = "retry_on_empty")]
+    pub no_retry_on_empty: bool,
+
+    /// Reject responses reporting more than this multiple of the expected frames
+    #[arg(long, value_name = "FACTOR", default_value_t = crate::client::limits::DEFAULT_FRAME_SAFETY_FACTOR)]
+    pub frame_safety_factor: f64,
+
+    /// Largest inference request to send, in megabytes
+    #[arg(long, value_name = "MB", default_value_t = crate::client::payload::DEFAULT_MAX_REQUEST_MB)]
+    pub max_request_size: f64,
+
+    /// Only trust a server certificate with this SHA-256 fingerprint (replaces CA checks)
+    #[arg(long, value_name = "HEX")]
+    pub pin_sha256: Option<CertPin>,
+
+    /// Treat compatibility, audio quality, and server warnings as errors
+    #[arg(long, overrides_with = "no_strict")]
+    pub strict: bool,
+
+    /// Keep warnings as warnings, even if the config file sets strict
+    #[arg(long, overrides_with = "strict")]
+    pub no_strict: bool,
+
+    /// Fail if the output differs from this golden video
+    #[arg(long, value_name = "FILE")]
+    pub compare_to: Option<PathBuf>,
+
+    /// Maximum mean frame difference (0.0-1.0) accepted by --compare-to
+    #[arg(long, value_name = "DIFF", default_value_t = crate::compare::DEFAULT_COMPARE_THRESHOLD, requires = "compare_to")]
+    pub compare_threshold: f64,
+
+    /// Fail unless the output lasts this long, e.g. 12.5+-0.2 (seconds; default tolerance 0.1)
+    #[arg(long, value_name = "SECONDS+-TOL")]
+    pub assert_duration: Option<DurationAssertion>,
+
+    /// Run this command on each finished output (path appended as the last argument; quote words with spaces)
+    #[arg(long, value_name = "CMD")]
+    pub post_hook: Option<PostHook>,
+
+    /// Write a zip of request/response/ffmpeg diagnostics for support tickets
+    #[arg(long, value_name = "PATH")]
+    pub debug_bundle: Option<PathBuf>,
+
+    /// Emit JSON-lines lifecycle events on stderr for integrations
+    #[arg(long)]
+    pub events: bool,
+
+    /// Emit JSON-lines lifecycle events on this inherited file descriptor
+    #[arg(long, value_name = "N", conflicts_with = "events")]
+    pub events_fd: Option<u32>,
+
+    /// Write a folded-stack timing profile (render with inferno-flamegraph)
+    #[arg(long, value_name = "PATH")]
+    pub profile: Option<PathBuf>,
+
+    /// Enable verbose output
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Suppress all output except errors
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Dry run - validate inputs without processing
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Validate inputs, then check the server with a tiny synthetic inference
+    #[arg(long, conflicts_with = "dry_run")]
+    pub smoke_test: bool,
+
+    /// Time synthetic inference round-trips against the server and exit
+    #[arg(long)]
+    pub benchmark: bool,
+
+    /// Number of benchmark requests
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "benchmark")]
+    pub benchmark_iterations: u32,
+
+    /// Seconds of synthetic audio per benchmark request
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 5.0,
+        requires = "benchmark"
+    )]
+    pub benchmark_duration: f32,
+
+    /// Render every --matrix-audio on every --matrix-server and write the results (.csv or .json)
+    #[arg(long, value_name = "PATH", requires_all = ["matrix_server", "matrix_audio"])]
+    pub compare_servers_matrix: Option<PathBuf>,
+
+    /// Server in the --compare-servers-matrix pool (repeatable)
+    #[arg(long, value_name = "URL", requires = "compare_servers_matrix")]
+    pub matrix_server: Vec<String>,
+
+    /// Audio input for --compare-servers-matrix (repeatable)
+    #[arg(long, value_name = "PATH", requires = "compare_servers_matrix")]
+    pub matrix_audio: Vec<PathBuf>,
+
+    /// Read option defaults from this TOML file (default: ~/.config/musetalk/config.toml)
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Write a commented config template and exit (default ./musetalk.toml; it is
+    /// only read via --config, or once moved to ~/.config/musetalk/config.toml)
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = crate::config::DEFAULT_CONFIG_FILE
+    )]
+    pub init_config: Option<PathBuf>,
+
+    /// Allow --init-config to replace an existing file
+    #[arg(long, requires = "init_config")]
+    pub overwrite: bool,
+}
+
+impl Args {
+    /// Parse arguments from command line, with defaults from the config file.
+    pub fn parse_args() -> Self {
+        Self::try_parse_with_config(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse arguments from an iterator, then fill options not given there
+    /// from `--config` or the default config file.
+    pub fn try_parse_with_config<I, T>(iter: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let mut command = Self::command();
+        let matches = command.try_get_matches_from_mut(iter)?;
+        let mut args = Self::from_arg_matches(&matches)?;
+        crate::config::apply_config(&mut args, &matches)
+            .map_err(|e| command.error(clap::error::ErrorKind::InvalidValue, e))?;
+        Ok(args)
+    }
+
+    /// Parse arguments from an iterator (for testing).
+    pub fn try_parse_from_args<I, T>(iter: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        Self::try_parse_from(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests;
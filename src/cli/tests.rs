@@ -0,0 +1,362 @@
+//! Argument parsing tests.
+
+use super::*;
+
+#[test]
+fn test_parse_minimal_args() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+    ])
+    .unwrap();
+
+    assert_eq!(args.reference, Some(PathBuf::from("avatar.png")));
+    assert_eq!(args.audio, Some(PathBuf::from("audio.wav")));
+    assert_eq!(args.output, Some(PathBuf::from("output.mp4")));
+    assert_eq!(args.server, "http://localhost:3015");
+    assert_eq!(args.fps, 30);
+    assert_eq!(args.target_sample_rate, 16000);
+    assert!(!args.verbose);
+    assert!(!args.quiet);
+}
+
+#[test]
+fn test_parse_all_args() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+        "-s",
+        "http://gpu:8000",
+        "--resolution",
+        "1024x1024",
+        "-f",
+        "60",
+        "--face-center",
+        "256,300",
+        "-v",
+        "-n",
+    ])
+    .unwrap();
+
+    assert_eq!(args.server, "http://gpu:8000");
+    assert_eq!(args.resolution.to_string(), "1024x1024");
+    assert_eq!(args.fps, 60);
+    assert_eq!(args.face_center, Some(FaceCenter { x: 256, y: 300 }));
+    assert!(args.verbose);
+    assert!(args.dry_run);
+}
+
+#[test]
+fn test_parse_video_reference() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "--reference",
+        "avatar.mp4",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+    ])
+    .unwrap();
+
+    assert_eq!(args.reference, Some(PathBuf::from("avatar.mp4")));
+}
+
+#[test]
+fn test_dry_run_flag() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+        "--dry-run",
+    ])
+    .unwrap();
+
+    assert!(args.dry_run);
+}
+
+#[test]
+fn test_repeatable_header_flag() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+        "--header",
+        "X-Tenant-Id: acme",
+        "--header",
+        "X-Trace-Id: abc",
+    ])
+    .unwrap();
+
+    assert_eq!(args.headers.len(), 2);
+    assert_eq!(args.headers[0].name, "X-Tenant-Id");
+    assert_eq!(args.headers[1].value, "abc");
+}
+
+#[test]
+fn test_malformed_header_rejected() {
+    let result = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+        "--header",
+        "not-a-header",
+    ]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_malformed_resolution_rejected() {
+    let result = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "-a",
+        "audio.wav",
+        "-o",
+        "output.mp4",
+        "--resolution",
+        "512",
+    ]);
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains("Invalid resolution"), "{message}");
+}
+
+#[test]
+fn test_init_config_without_inputs() {
+    let args = Args::try_parse_from_args(["musetalk-cli", "--init-config"]).unwrap();
+    assert_eq!(args.init_config, Some(PathBuf::from("musetalk.toml")));
+    assert!(args.reference.is_none());
+
+    let args =
+        Args::try_parse_from_args(["musetalk-cli", "--init-config", "my.toml", "--overwrite"])
+            .unwrap();
+    assert_eq!(args.init_config, Some(PathBuf::from("my.toml")));
+    assert!(args.overwrite);
+}
+
+#[test]
+fn test_audio_url_replaces_audio() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "a.png",
+        "-o",
+        "c.mp4",
+        "--audio-url",
+        "https://host/a.wav",
+    ])
+    .unwrap();
+    assert_eq!(args.audio_url.unwrap().as_str(), "https://host/a.wav");
+
+    let both = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "a.png",
+        "-a",
+        "b.wav",
+        "-o",
+        "c.mp4",
+        "--audio-url",
+        "https://host/a.wav",
+    ]);
+    assert!(both.is_err());
+    let invalid = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "a.png",
+        "-o",
+        "c.mp4",
+        "--audio-url",
+        "a.wav",
+    ]);
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn test_list_codecs_without_inputs() {
+    let args = Args::try_parse_from_args(["musetalk-cli", "--list-codecs"]).unwrap();
+    assert!(args.list_codecs);
+
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "a.png",
+        "-a",
+        "b.wav",
+        "-o",
+        "c.mp4",
+        "--codec",
+        "h264-nvenc",
+    ])
+    .unwrap();
+    assert_eq!(args.codec, Some(VideoCodec::H264Nvenc));
+}
+
+#[test]
+fn test_queue_and_fetch_requirements() {
+    let args =
+        Args::try_parse_from_args(["musetalk-cli", "-r", "avatar.png", "-a", "a.wav", "--queue"])
+            .unwrap();
+    assert!(args.queue);
+    assert!(args.output.is_none());
+
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "--fetch",
+        "job-7",
+        "-a",
+        "a.wav",
+        "-o",
+        "out.mp4",
+    ])
+    .unwrap();
+    assert_eq!(args.fetch.as_deref(), Some("job-7"));
+    assert!(args.reference.is_none());
+
+    assert!(
+        Args::try_parse_from_args(["musetalk-cli", "--fetch", "job-7", "-a", "a.wav"]).is_err()
+    );
+
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "--fetch-frames",
+        "job-7",
+        "-a",
+        "a.wav",
+        "-o",
+        "out.mp4",
+    ])
+    .unwrap();
+    assert_eq!(args.fetch_frames.as_deref(), Some("job-7"));
+    assert!(
+        Args::try_parse_from_args([
+            "musetalk-cli",
+            "--fetch-frames",
+            "job-7",
+            "--fetch",
+            "job-7",
+            "-a",
+            "a.wav",
+            "-o",
+            "out.mp4",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn test_server_matrix_requires_pool_and_inputs() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "--compare-servers-matrix",
+        "matrix.csv",
+        "--matrix-server",
+        "http://a:3015",
+        "--matrix-server",
+        "http://b:3015",
+        "--matrix-audio",
+        "a.wav",
+    ])
+    .unwrap();
+    assert_eq!(args.matrix_server.len(), 2);
+    assert_eq!(args.matrix_audio, [PathBuf::from("a.wav")]);
+
+    assert!(
+        Args::try_parse_from_args(["musetalk-cli", "--compare-servers-matrix", "m.csv"]).is_err()
+    );
+    assert!(
+        Args::try_parse_from_args(["musetalk-cli", "--matrix-server", "http://a:3015"]).is_err()
+    );
+}
+
+#[test]
+fn test_input_list_requires_reference_and_template() {
+    let args = Args::try_parse_from_args([
+        "musetalk-cli",
+        "-r",
+        "avatar.png",
+        "--input-list",
+        "takes.txt",
+        "--output-template",
+        "{audio_stem}.mp4",
+        "--resume-batch",
+    ])
+    .unwrap();
+    assert_eq!(args.input_list, Some(PathBuf::from("takes.txt")));
+    assert!(args.resume_batch);
+
+    assert!(
+        Args::try_parse_from_args(["musetalk-cli", "-r", "a.png", "--input-list", "t.txt"])
+            .is_err()
+    );
+    assert!(
+        Args::try_parse_from_args([
+            "musetalk-cli",
+            "-r",
+            "a.png",
+            "--input-list",
+            "t.txt",
+            "--batch",
+            "m.json",
+            "--output-template",
+            "{index}.mp4",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn test_missing_required_args() {
+    let result = Args::try_parse_from_args(["musetalk-cli", "-r", "avatar.png"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_server_assemble_rejects_local_encoding_options() {
+    let base = [
+        "musetalk-cli",
+        "-r",
+        "a.png",
+        "-a",
+        "a.wav",
+        "-o",
+        "out.mp4",
+    ];
+    let parse = |extra: &[&str]| {
+        Args::try_parse_from_args(base.iter().chain(extra).chain(&["--server-assemble"]))
+    };
+    assert!(parse(&[]).is_ok());
+    for extra in [
+        &["--also-output", "out.webm"][..],
+        &["--hold-last", "1"],
+        &["--sync-length", "trim"],
+        &["--pix-fmt", "yuvj420p"],
+        &["--codec", "h265"],
+        &["--audio-language", "eng"],
+    ] {
+        assert!(parse(extra).is_err(), "{extra:?}");
+    }
+}
@@ -0,0 +1,177 @@
+//! Actionable messages for requests that never reached the server.
+//!
+//! reqwest reports DNS, TLS, refused, and timed-out connections alike as
+//! "error sending request". The cause is buried in the error's source chain,
+//! so the chain is walked to name the failure and suggest a fix.
+
+use crate::error::CliError;
+use std::error::Error as StdError;
+use std::io::ErrorKind;
+
+/// Why a request failed before an HTTP response arrived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectFailure {
+    /// The server's hostname could not be resolved.
+    Dns,
+    /// The TLS handshake or certificate check failed.
+    Tls,
+    /// Nothing is listening at the server's address.
+    Refused,
+    /// The connection or response took too long.
+    Timeout,
+    /// Anything else.
+    Other,
+}
+
+impl ConnectFailure {
+    /// Classifies `error` by walking its source chain.
+    pub fn classify(error: &(dyn StdError + 'static)) -> Self {
+        let mut current = Some(error);
+        while let Some(e) = current {
+            if let Some(failure) = Self::classify_one(e) {
+                return failure;
+            }
+            current = e.source();
+        }
+        Self::Other
+    }
+
+    /// Classifies a single link of the chain, if it says enough.
+    fn classify_one(error: &(dyn StdError + 'static)) -> Option<Self> {
+        if error
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(reqwest::Error::is_timeout)
+        {
+            return Some(Self::Timeout);
+        }
+        if error.is::<rustls::Error>() {
+            return Some(Self::Tls);
+        }
+        if let Some(io) = error.downcast_ref::<std::io::Error>() {
+            match io.kind() {
+                ErrorKind::ConnectionRefused => return Some(Self::Refused),
+                ErrorKind::TimedOut => return Some(Self::Timeout),
+                _ => {}
+            }
+        }
+        let message = error.to_string().to_lowercase();
+        if [
+            "dns error",
+            "failed to lookup address",
+            "name or service not known",
+        ]
+        .iter()
+        .any(|m| message.contains(m))
+        {
+            return Some(Self::Dns);
+        }
+        if ["certificate", "tls handshake", "invalid peer"]
+            .iter()
+            .any(|m| message.contains(m))
+        {
+            return Some(Self::Tls);
+        }
+        None
+    }
+
+    /// What the user should check, if the failure is recognized.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            Self::Dns => Some("could not resolve the server hostname; check the --server URL"),
+            Self::Tls => Some(
+                "TLS handshake failed; check the server certificate (or --pin-sha256 fingerprint)",
+            ),
+            Self::Refused => {
+                Some("connection refused; is the MuseTalk server running at that address?")
+            }
+            Self::Timeout => {
+                Some("request timed out; increase --connect-timeout or --read-timeout")
+            }
+            Self::Other => None,
+        }
+    }
+}
+
+/// Converts a failed request into a [`CliError::ServerConnection`] that
+/// names the cause and how to fix it.
+pub fn connection_error(error: reqwest::Error) -> CliError {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    if let Some(hint) = ConnectFailure::classify(&error).hint() {
+        message = format!("{hint} ({message})");
+    }
+    CliError::ServerConnection(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    /// An error with a fixed message wrapping an optional cause.
+    #[derive(Debug)]
+    struct Wrapped(&'static str, Option<Box<dyn StdError + 'static>>);
+
+    impl fmt::Display for Wrapped {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl StdError for Wrapped {
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            self.1.as_deref()
+        }
+    }
+
+    fn sending(cause: impl StdError + 'static) -> Wrapped {
+        Wrapped("error sending request", Some(Box::new(cause)))
+    }
+
+    #[test]
+    fn test_classifies_synthetic_failures() {
+        let dns = sending(Wrapped(
+            "dns error: failed to lookup address information",
+            None,
+        ));
+        assert_eq!(ConnectFailure::classify(&dns), ConnectFailure::Dns);
+
+        let refused = sending(std::io::Error::from(ErrorKind::ConnectionRefused));
+        assert_eq!(ConnectFailure::classify(&refused), ConnectFailure::Refused);
+
+        let timeout = sending(std::io::Error::from(ErrorKind::TimedOut));
+        assert_eq!(ConnectFailure::classify(&timeout), ConnectFailure::Timeout);
+
+        let tls = sending(std::io::Error::other(rustls::Error::InvalidCertificate(
+            rustls::CertificateError::UnknownIssuer,
+        )));
+        assert_eq!(ConnectFailure::classify(&tls), ConnectFailure::Tls);
+
+        let other = sending(Wrapped("connection reset", None));
+        assert_eq!(ConnectFailure::classify(&other), ConnectFailure::Other);
+        assert!(ConnectFailure::Other.hint().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_refused_connection_message_is_actionable() {
+        // Bind then drop a listener so the port is known to be closed
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let error = reqwest::get(format!("http://127.0.0.1:{port}/health"))
+            .await
+            .unwrap_err();
+
+        let message = connection_error(error).to_string();
+        assert!(
+            message.contains("is the MuseTalk server running"),
+            "{message}"
+        );
+    }
+}
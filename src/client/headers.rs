@@ -0,0 +1,119 @@
+//! Custom HTTP headers supplied on the command line.
+
+use crate::error::{CliError, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::fmt;
+use std::str::FromStr;
+
+/// Substrings that mark a header as carrying a secret.
+const SENSITIVE_MARKERS: &[&str] = &["token", "key", "auth", "secret", "password", "cookie"];
+
+/// A single `Key: Value` header parsed from `--header`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HeaderArg {
+    pub name: String,
+    pub value: String,
+}
+
+impl HeaderArg {
+    /// Returns true if the header name looks like it carries a secret.
+    pub fn is_sensitive(&self) -> bool {
+        is_sensitive_header(&self.name)
+    }
+
+    /// Returns the value safe for logging (redacted if sensitive).
+    pub fn display_value(&self) -> &str {
+        if self.is_sensitive() {
+            "<redacted>"
+        } else {
+            &self.value
+        }
+    }
+}
+
+impl fmt::Debug for HeaderArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.display_value())
+    }
+}
+
+impl FromStr for HeaderArg {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| CliError::InvalidHeader(format!("expected 'Key: Value', got '{s}'")))?;
+
+        let name = name.trim();
+        let value = value.trim();
+
+        HeaderName::from_str(name)
+            .map_err(|_| CliError::InvalidHeader(format!("invalid header name '{name}'")))?;
+        HeaderValue::from_str(value)
+            .map_err(|_| CliError::InvalidHeader(format!("invalid value for header '{name}'")))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Returns true if a header with this name likely carries a secret.
+pub fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_MARKERS.iter().any(|m| name.contains(m))
+}
+
+/// Builds a header map from parsed header arguments.
+pub fn build_header_map(headers: &[HeaderArg]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for header in headers {
+        let name = HeaderName::from_str(&header.name)
+            .map_err(|_| CliError::InvalidHeader(header.name.clone()))?;
+        let mut value = HeaderValue::from_str(&header.value)
+            .map_err(|_| CliError::InvalidHeader(header.name.clone()))?;
+        value.set_sensitive(header.is_sensitive());
+        tracing::debug!("Custom header: {header:?}");
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let header: HeaderArg = "X-Tenant-Id: acme".parse().unwrap();
+        assert_eq!(header.name, "X-Tenant-Id");
+        assert_eq!(header.value, "acme");
+    }
+
+    #[test]
+    fn test_parse_header_value_with_colon() {
+        let header: HeaderArg = "X-Origin: http://host:8080".parse().unwrap();
+        assert_eq!(header.value, "http://host:8080");
+    }
+
+    #[test]
+    fn test_parse_malformed_headers() {
+        assert!("NoColonHere".parse::<HeaderArg>().is_err());
+        assert!(": value".parse::<HeaderArg>().is_err());
+        assert!("Bad Name: value".parse::<HeaderArg>().is_err());
+        assert!("X-Bad: line\nbreak".parse::<HeaderArg>().is_err());
+    }
+
+    #[test]
+    fn test_sensitive_headers_redacted() {
+        let header: HeaderArg = "X-Api-Key: hunter2".parse().unwrap();
+        assert!(header.is_sensitive());
+        assert!(!format!("{header:?}").contains("hunter2"));
+
+        let header: HeaderArg = "X-Trace-Id: abc123".parse().unwrap();
+        assert!(!header.is_sensitive());
+        assert!(format!("{header:?}").contains("abc123"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Short-lived cache of the server's health (`--health-cache-ttl`).
+//!
+//! Batch renders each probe `/health` before inference. A [`HealthCache`]
+//! shared by their clients answers repeat probes within the TTL from the
+//! last result, and is cleared when a request fails to reach the server.
+
+use super::ServerHealth;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default time a health result stays fresh.
+pub const DEFAULT_HEALTH_CACHE_TTL_SECS: u64 = 5;
+
+/// Shared health result; the default (zero TTL) never caches.
+#[derive(Debug, Clone, Default)]
+pub struct HealthCache {
+    ttl: Duration,
+    entry: Arc<Mutex<Option<(Instant, ServerHealth)>>>,
+}
+
+impl HealthCache {
+    /// Caches health results for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Arc::default(),
+        }
+    }
+
+    /// The cached result, if it is still fresh.
+    pub fn get(&self) -> Option<ServerHealth> {
+        let entry = self.entry.lock().unwrap();
+        entry
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < self.ttl)
+            .map(|(_, health)| health.clone())
+    }
+
+    /// Records a successful probe.
+    pub fn store(&self, health: &ServerHealth) {
+        if !self.ttl.is_zero() {
+            *self.entry.lock().unwrap() = Some((Instant::now(), health.clone()));
+        }
+    }
+
+    /// Forgets the cached result so the next check probes the server.
+    pub fn invalidate(&self) {
+        *self.entry.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::test_support::{MockServer, frames_response, test_audio, test_image};
+
+    #[tokio::test]
+    async fn test_two_quick_jobs_share_one_probe() {
+        let server = MockServer::with_infer(|_| frames_response(1)).await;
+        let cache = HealthCache::new(Duration::from_secs(60));
+
+        for _ in 0..2 {
+            let client = MuseTalkClient::new(server.url()).with_health_cache(cache.clone());
+            client.health_check().await.unwrap();
+        }
+
+        assert_eq!(server.requests_to("/health").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connection_failure_invalidates() {
+        let cache = HealthCache::new(Duration::from_secs(60));
+        cache.store(&ServerHealth {
+            status: "ok".to_string(),
+            version: None,
+        });
+        let client = MuseTalkClient::new("http://127.0.0.1:1")
+            .with_health_cache(cache.clone())
+            .with_connect_timeout(Duration::from_secs(1))
+            .unwrap()
+            .with_max_retries(0);
+
+        let image = test_image();
+        let result = client
+            .infer(
+                ReferenceInput::Image(&image),
+                &test_audio(),
+                &InferenceOptions::default(),
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert!(cache.get().is_none());
+    }
+
+    #[test]
+    fn test_zero_ttl_never_caches() {
+        let cache = HealthCache::default();
+        cache.store(&ServerHealth {
+            status: "ok".to_string(),
+            version: None,
+        });
+        assert!(cache.get().is_none());
+    }
+}
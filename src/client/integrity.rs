@@ -0,0 +1,115 @@
+//! Per-frame checksum verification.
+//!
+//! Servers may attach a SHA-256 of each decoded frame. When they do, the
+//! frames are checked so corruption, drops, or reordering in transit fail
+//! loudly instead of showing up as visual glitches. Responses without
+//! checksums are passed through unchecked.
+
+use super::types::Frame;
+use crate::error::{CliError, Result, decode_base64};
+use ring::digest::{SHA256, digest};
+
+/// Verifies the checksums and order of frames that carry a `sha256`.
+pub fn verify_frames(frames: &[Frame]) -> Result<()> {
+    verify_frames_from(0, frames)
+}
+
+/// Like [`verify_frames`], for frames expected to start at index `first`
+/// (a response resumed from `first`).
+pub fn verify_frames_from(first: usize, frames: &[Frame]) -> Result<()> {
+    if frames.iter().all(|f| f.sha256.is_none()) {
+        return Ok(());
+    }
+    for (position, frame) in (first..).zip(frames) {
+        verify_frame(position, frame)?;
+    }
+    Ok(())
+}
+
+/// Verifies that `frame` belongs at `position` and matches its `sha256`,
+/// if it carries one.
+pub fn verify_frame(position: usize, frame: &Frame) -> Result<()> {
+    if frame.index != position {
+        return Err(CliError::FrameIntegrity(format!(
+            "frame {} arrived at position {position}",
+            frame.index
+        )));
+    }
+    let Some(expected) = &frame.sha256 else {
+        return Ok(());
+    };
+    let bytes = decode_base64(&frame.data, || format!("frame {position}"))?;
+    let actual = sha256_hex(&bytes);
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(CliError::FrameIntegrity(format!(
+            "frame {position} has SHA-256 {actual}, server declared {expected}"
+        )));
+    }
+    Ok(())
+}
+
+/// Lowercase hex SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    digest(&SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::tiny_png_base64;
+
+    fn frame(index: usize, sha256: Option<String>) -> Frame {
+        Frame {
+            index,
+            data: tiny_png_base64(),
+            sha256,
+            landmarks: None,
+            bbox: None,
+        }
+    }
+
+    fn png_hash() -> String {
+        let png = decode_base64(tiny_png_base64(), String::new).unwrap();
+        sha256_hex(&png)
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_matching_checksums_pass() {
+        let frames = [
+            frame(0, Some(png_hash())),
+            frame(1, Some(png_hash().to_uppercase())),
+        ];
+        assert!(verify_frames(&frames).is_ok());
+        // Without checksums nothing is verified, not even order
+        assert!(verify_frames(&[frame(3, None), frame(1, None)]).is_ok());
+    }
+
+    #[test]
+    fn test_mismatched_checksum_rejected() {
+        let frames = [frame(0, Some(png_hash())), frame(1, Some("00".repeat(32)))];
+        let err = verify_frames(&frames).unwrap_err();
+        assert!(matches!(err, CliError::FrameIntegrity(_)));
+        assert!(err.to_string().contains("frame 1"));
+    }
+
+    #[test]
+    fn test_reordered_frames_rejected() {
+        let frames = [frame(1, Some(png_hash())), frame(0, Some(png_hash()))];
+        assert!(matches!(
+            verify_frames(&frames),
+            Err(CliError::FrameIntegrity(_))
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+//! Asynchronous job submission and retrieval.
+//!
+//! Servers that support queued jobs accept the same payload as `/infer` at
+//! `POST /jobs`, returning a job ID. `GET /jobs/{id}` answers `202` while the
+//! job runs and `200` with the inference response once it is done.
+//!
+//! A finished job's frames can also be downloaded again in pages from
+//! `GET /jobs/{id}/frames?offset=N&limit=M`, which likewise answers `202`
+//! while the job is still running.
+
+use super::diagnose::connection_error;
+use super::integrity;
+use super::types::{Frame, FramePage, JobProgress, JobSubmission};
+use super::{InferenceOptions, InferenceResponse, MuseTalkClient, ReferenceInput, build_request};
+use crate::error::{CliError, Result};
+use crate::loader::AudioData;
+use reqwest::StatusCode;
+
+/// Frames requested per page by [`MuseTalkClient::fetch_job_frames`].
+pub const DEFAULT_FRAME_PAGE_SIZE: usize = 500;
+
+/// State of a queued job.
+#[derive(Debug, Clone)]
+pub enum JobState {
+    /// Still queued or running, with the server's status text.
+    Pending(String),
+    /// Finished with generated frames.
+    Complete(InferenceResponse),
+}
+
+impl MuseTalkClient {
+    /// Submits an inference job without waiting for its frames.
+    ///
+    /// Returns the server-assigned job ID.
+    pub async fn submit_job(
+        &self,
+        reference: ReferenceInput<'_>,
+        audio: &AudioData,
+        options: &InferenceOptions,
+    ) -> Result<String> {
+        let url = format!("{}/jobs", self.base_url);
+        tracing::debug!("Submitting job: {url}");
+
+        let request = build_request(reference, audio, options);
+        let response = self
+            .client
+            .post(&url)
+            .headers(self.headers.clone())
+            .json(&request)
+            .timeout(self.read_timeout)
+            .send()
+            .await
+            .map_err(connection_error)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(CliError::ServerConnection(format!(
+                "Job submission failed: {status} - {body}"
+            )));
+        }
+
+        let submission: JobSubmission = response
+            .json()
+            .await
+            .map_err(|e| CliError::ServerConnection(format!("Invalid job response: {e}")))?;
+        Ok(submission.job_id)
+    }
+
+    /// Fetches the state of a previously submitted job.
+    pub async fn fetch_job(&self, job_id: &str) -> Result<JobState> {
+        let url = self.job_url(job_id, &[])?;
+        tracing::debug!("Fetching job: {url}");
+
+        let response = self
+            .client
+            .get(url)
+            .headers(self.headers.clone())
+            .timeout(self.read_timeout)
+            .send()
+            .await
+            .map_err(connection_error)?;
+
+        match response.status() {
+            StatusCode::ACCEPTED => {
+                let progress: JobProgress = response
+                    .json()
+                    .await
+                    .map_err(|e| CliError::ServerConnection(format!("Invalid job status: {e}")))?;
+                Ok(JobState::Pending(progress.status))
+            }
+            StatusCode::NOT_FOUND => {
+                Err(CliError::ServerConnection(format!("Unknown job: {job_id}")))
+            }
+            status if status.is_success() => {
+                let result: InferenceResponse = response
+                    .json()
+                    .await
+                    .map_err(|e| CliError::ServerConnection(format!("Invalid job result: {e}")))?;
+                integrity::verify_frames(&result.frames)?;
+                Ok(JobState::Complete(result))
+            }
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(CliError::ServerConnection(format!(
+                    "Job fetch failed: {status} - {body}"
+                )))
+            }
+        }
+    }
+
+    /// Downloads every stored frame of a finished job, `page_size` at a time.
+    ///
+    /// Fails if the job is still running, the pages don't add up to the
+    /// job's frame count, or the frames fail their checksums.
+    pub async fn fetch_job_frames(&self, job_id: &str, page_size: usize) -> Result<Vec<Frame>> {
+        let url = self.job_url(job_id, &["frames"])?;
+        let mut frames = Vec::new();
+        let mut offset = 0;
+        loop {
+            tracing::debug!("Fetching frames {offset}.. of job {job_id}");
+            let page = self
+                .fetch_frame_page(&url, job_id, offset, page_size)
+                .await?;
+            frames.extend(page.frames);
+            match page.next_offset {
+                Some(next) if next > offset => offset = next,
+                Some(next) => {
+                    return Err(CliError::ServerConnection(format!(
+                        "Frame pages for job {job_id} don't advance (offset {offset}, next {next})"
+                    )));
+                }
+                None if frames.len() == page.total_frames => {
+                    // Checked as one set: indices run on across pages
+                    integrity::verify_frames(&frames)?;
+                    return Ok(frames);
+                }
+                None => {
+                    return Err(CliError::FrameIntegrity(format!(
+                        "job {job_id} returned {} of {} frames",
+                        frames.len(),
+                        page.total_frames
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Fetches one page of a finished job's frames from `url`.
+    async fn fetch_frame_page(
+        &self,
+        url: &reqwest::Url,
+        job_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<FramePage> {
+        let response = self
+            .client
+            .get(url.clone())
+            .query(&[("offset", offset), ("limit", limit)])
+            .headers(self.headers.clone())
+            .timeout(self.read_timeout)
+            .send()
+            .await
+            .map_err(connection_error)?;
+        match response.status() {
+            StatusCode::ACCEPTED => Err(CliError::ServerConnection(format!(
+                "Job {job_id} is not finished yet; try again later"
+            ))),
+            StatusCode::NOT_FOUND => Err(CliError::ServerConnection(format!(
+                "Unknown job or no stored frames: {job_id}"
+            ))),
+            status if status.is_success() => response
+                .json()
+                .await
+                .map_err(|e| CliError::ServerConnection(format!("Invalid frame page: {e}"))),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(CliError::ServerConnection(format!(
+                    "Frame fetch failed: {status} - {body}"
+                )))
+            }
+        }
+    }
+
+    /// `/jobs/{job_id}` followed by `tail`, with the job ID path-encoded so
+    /// an ID containing `/` or `?` can't address another resource.
+    fn job_url(&self, job_id: &str, tail: &[&str]) -> Result<reqwest::Url> {
+        let invalid =
+            || CliError::ServerConnection(format!("Invalid server URL: {}", self.base_url));
+        let mut url = reqwest::Url::parse(&self.base_url).map_err(|_| invalid())?;
+        url.path_segments_mut()
+            .map_err(|()| invalid())?
+            .pop_if_empty()
+            .push("jobs")
+            .push(job_id)
+            .extend(tail);
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::decode_base64;
+    use crate::test_support::{
+        MockResponse, MockServer, frames_response, test_audio, test_image, tiny_png_base64,
+    };
+
+    fn job_server() -> impl Fn(&crate::test_support::RecordedRequest) -> MockResponse {
+        |req| match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/jobs") => MockResponse::json(serde_json::json!({"job_id": "job-7"})),
+            ("GET", "/jobs/job-7") => frames_response(3),
+            ("GET", "/jobs/job-8") => {
+                MockResponse::status(202).with_body(r#"{"status":"running"}"#.to_string())
+            }
+            _ => MockResponse::status(404),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_job_returns_id() {
+        let server = MockServer::start(job_server()).await;
+        let client = MuseTalkClient::new(server.url());
+        let image = test_image();
+        let audio = test_audio();
+
+        let job_id = client
+            .submit_job(
+                ReferenceInput::Image(&image),
+                &audio,
+                &InferenceOptions::new(25),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(job_id, "job-7");
+        let body = server.requests_to("/jobs")[0].json();
+        assert_eq!(body["fps"], 25);
+        assert_eq!(body["audio"], "UklGRg==");
+    }
+
+    #[tokio::test]
+    async fn test_submit_job_honors_read_timeout() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(serde_json::json!({"job_id": "job-7"}))
+                .with_delay(std::time::Duration::from_secs(2))
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url())
+            .with_read_timeout(std::time::Duration::from_millis(200));
+
+        let start = std::time::Instant::now();
+        let result = client
+            .submit_job(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(25),
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_job_states() {
+        let server = MockServer::start(job_server()).await;
+        let client = MuseTalkClient::new(server.url());
+
+        match client.fetch_job("job-7").await.unwrap() {
+            JobState::Complete(response) => assert_eq!(response.total_frames, 3),
+            other => panic!("expected complete job, got {other:?}"),
+        }
+        assert!(matches!(
+            client.fetch_job("job-8").await.unwrap(),
+            JobState::Pending(status) if status == "running"
+        ));
+        assert!(client.fetch_job("missing").await.is_err());
+    }
+
+    /// Serves `total` stored frames of `job-7` in pages; `job-8` is running.
+    fn paged_server(
+        total: usize,
+    ) -> impl Fn(&crate::test_support::RecordedRequest) -> MockResponse {
+        move |req| {
+            let (path, query) = req.path.split_once('?').unwrap_or((&req.path, ""));
+            let param = |name: &str| {
+                query
+                    .split('&')
+                    .find_map(|p| p.strip_prefix(&format!("{name}=")))
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap()
+            };
+            match path {
+                "/jobs/job-7/frames" => {
+                    let (offset, limit) = (param("offset"), param("limit"));
+                    let end = (offset + limit).min(total);
+                    let png = tiny_png_base64();
+                    let sha256 = integrity::sha256_hex(&decode_base64(&png, String::new).unwrap());
+                    let frames: Vec<_> = (offset..end)
+                        .map(|i| serde_json::json!({"index": i, "data": png, "sha256": sha256}))
+                        .collect();
+                    MockResponse::json(serde_json::json!({
+                        "frames": frames,
+                        "total_frames": total,
+                        "next_offset": (end < total).then_some(end),
+                    }))
+                }
+                "/jobs/job-8/frames" => {
+                    MockResponse::status(202).with_body(r#"{"status":"running"}"#.to_string())
+                }
+                _ => MockResponse::status(404),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_job_frames_paginates_full_set() {
+        let server = MockServer::start(paged_server(7)).await;
+        let client = MuseTalkClient::new(server.url());
+
+        let frames = client.fetch_job_frames("job-7", 3).await.unwrap();
+
+        let indices: Vec<_> = frames.iter().map(|f| f.index).collect();
+        assert_eq!(indices, (0..7).collect::<Vec<_>>());
+        let pages: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            pages,
+            [
+                "/jobs/job-7/frames?offset=0&limit=3",
+                "/jobs/job-7/frames?offset=3&limit=3",
+                "/jobs/job-7/frames?offset=6&limit=3",
+            ]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<_> = frames.into_iter().map(|f| f.data).collect();
+        crate::assembler::VideoAssembler::new(25)
+            .unwrap()
+            .with_frames_dir(dir.path().to_path_buf())
+            .unwrap()
+            .stage_frames(&data)
+            .unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_job_id_is_path_encoded() {
+        let server = MockServer::start(paged_server(7)).await;
+        let client = MuseTalkClient::new(server.url());
+
+        assert!(client.fetch_job("../health?x").await.is_err());
+        assert!(client.fetch_job_frames("job-7/../x", 3).await.is_err());
+
+        let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/jobs/..%2Fhealth%3Fx",
+                "/jobs/job-7%2F..%2Fx/frames?offset=0&limit=3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_job_frames_requires_finished_job() {
+        let server = MockServer::start(paged_server(7)).await;
+        let client = MuseTalkClient::new(server.url());
+
+        let err = client.fetch_job_frames("job-8", 3).await.unwrap_err();
+        assert!(err.to_string().contains("not finished"), "{err}");
+        assert!(client.fetch_job_frames("missing", 3).await.is_err());
+    }
+}
@@ -0,0 +1,76 @@
+//! Sanity limits on server-reported frame counts.
+//!
+//! A misbehaving server can report an absurd `total_frames`; anything far
+//! beyond what the audio can produce is rejected before it is trusted.
+
+use crate::error::{CliError, Result};
+
+/// Default multiple of the expected frame count a response may report.
+pub const DEFAULT_FRAME_SAFETY_FACTOR: f64 = 2.0;
+
+/// Frames always allowed on top of the scaled estimate, for very short clips.
+const MIN_FRAME_ALLOWANCE: usize = 10;
+
+/// Largest plausible frame count for `duration_secs` of audio at `fps`.
+pub fn max_plausible_frames(duration_secs: f32, fps: u32, safety_factor: f64) -> usize {
+    let scaled = (duration_secs as f64 * fps as f64 * safety_factor).ceil();
+    (scaled as usize).saturating_add(MIN_FRAME_ALLOWANCE)
+}
+
+/// Fails if the reported or received frame count exceeds `limit`.
+pub fn check_frame_count(total_frames: usize, received: usize, limit: usize) -> Result<()> {
+    let count = total_frames.max(received);
+    if count <= limit {
+        return Ok(());
+    }
+    Err(CliError::Video(format!(
+        "server reported {count} frames, more than the {limit} plausible for this audio. \
+         The response looks malformed (raise --frame-safety-factor if it is genuine)"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::test_support::{MockResponse, MockServer, test_audio, test_image};
+
+    #[test]
+    fn test_max_plausible_frames() {
+        assert_eq!(max_plausible_frames(1.0, 25, 2.0), 60);
+        assert_eq!(max_plausible_frames(0.0, 25, 2.0), MIN_FRAME_ALLOWANCE);
+        assert_eq!(max_plausible_frames(10.0, 30, 1.0), 310);
+    }
+
+    #[test]
+    fn test_check_frame_count() {
+        assert!(check_frame_count(60, 60, 60).is_ok());
+        assert!(matches!(
+            check_frame_count(4_000_000_000, 2, 60),
+            Err(CliError::Video(_))
+        ));
+        assert!(check_frame_count(0, 61, 60).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_absurd_total_frames_rejected() {
+        let server = MockServer::with_infer(|_| {
+            MockResponse::json(serde_json::json!({
+                "status": "success",
+                "total_frames": 4_000_000_000u64,
+                "frames": [],
+            }))
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+
+        let result = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(25),
+            )
+            .await;
+        assert!(matches!(result, Err(CliError::Video(_))));
+    }
+}
@@ -1,13 +1,52 @@
 //! HTTP client for MuseTalk server communication.
 
+pub mod diagnose;
+pub mod headers;
+pub mod health_cache;
+pub mod integrity;
+pub mod jobs;
+pub mod limits;
+pub mod payload;
+pub mod pinning;
+pub mod resume;
+pub mod retry;
+pub mod stream;
+pub mod timeouts;
 pub mod types;
+pub mod upscale;
 
+use crate::cli::Args;
+use crate::debug_bundle::{ResponseMeta, SharedBundle};
 use crate::error::{CliError, Result};
 use crate::loader::{AudioData, ImageData, VideoData};
-use std::error::Error as StdError;
-pub use types::{InferenceRequest, InferenceResponse, ServerHealth};
+use diagnose::connection_error;
+pub use headers::{HeaderArg, build_header_map};
+pub use health_cache::HealthCache;
+pub use jobs::JobState;
+pub use pinning::CertPin;
+use reqwest::header::HeaderMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+pub use stream::StreamSummary;
+use timeouts::{DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_READ_TIMEOUT_SECS, http_client};
+use tracing::Instrument;
+pub use types::{
+    Frame, InferenceOptions, InferenceRequest, InferenceResponse, ServerCapabilities, ServerHealth,
+};
+pub use upscale::UpscaleClient;
+
+/// Source of per-process request IDs attached to client log spans.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a span for one server request, so its logs can be told apart
+/// from other renders running at the same time.
+fn request_span(kind: &str) -> tracing::Span {
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    tracing::info_span!("request", kind, request_id)
+}
 
 /// Reference input for inference (image or video).
+#[derive(Clone, Copy)]
 pub enum ReferenceInput<'a> {
     /// Static image reference.
     Image(&'a ImageData),
@@ -19,6 +58,17 @@ pub enum ReferenceInput<'a> {
 pub struct MuseTalkClient {
     base_url: String,
     client: reqwest::Client,
+    connect_timeout: Duration,
+    cert_pin: Option<CertPin>,
+    headers: HeaderMap,
+    read_timeout: Duration,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    frame_safety_factor: f64,
+    max_request_bytes: u64,
+    retry_on_empty: bool,
+    health_cache: HealthCache,
+    debug_bundle: Option<SharedBundle>,
 }
 
 impl MuseTalkClient {
@@ -26,22 +76,136 @@ impl MuseTalkClient {
     pub fn new(base_url: &str) -> Self {
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
-            client: reqwest::Client::new(),
+            // Without a pin only TLS backend setup can fail, which reqwest's
+            // own default client doesn't survive either
+            client: http_client(Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS), None)
+                .unwrap_or_default(),
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            cert_pin: None,
+            headers: HeaderMap::new(),
+            read_timeout: Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+            max_retries: retry::DEFAULT_MAX_RETRIES,
+            retry_base_delay: retry::DEFAULT_BASE_DELAY,
+            frame_safety_factor: limits::DEFAULT_FRAME_SAFETY_FACTOR,
+            max_request_bytes: payload::megabytes(payload::DEFAULT_MAX_REQUEST_MB),
+            retry_on_empty: false,
+            health_cache: HealthCache::default(),
+            debug_bundle: None,
+        }
+    }
+
+    /// Creates a client from the server connection arguments.
+    pub fn from_args(args: &Args, bundle: Option<&SharedBundle>) -> Result<Self> {
+        let mut client = Self::new(&args.server)
+            .with_headers(build_header_map(&args.headers)?)
+            .with_connect_timeout(Duration::from_secs(args.connect_timeout))?
+            .with_read_timeout(Duration::from_secs(args.read_timeout))
+            .with_max_retries(args.max_retries)
+            .with_max_request_size(payload::megabytes(args.max_request_size))
+            .with_retry_on_empty(args.retry_on_empty)
+            .with_frame_safety_factor(args.frame_safety_factor);
+        if let Some(pin) = args.pin_sha256 {
+            client = client.with_cert_pin(pin)?;
+        }
+        if let Some(bundle) = bundle {
+            client = client.with_debug_bundle(bundle.clone());
+        }
+        Ok(client)
+    }
+
+    /// Sets extra headers sent with every request.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets how long to wait for a connection before giving up.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Result<Self> {
+        self.connect_timeout = timeout;
+        self.client = http_client(timeout, self.cert_pin)?;
+        Ok(self)
+    }
+
+    /// Only accepts a server presenting the certificate with this fingerprint.
+    ///
+    /// Fails for a plain `http://` server, which has no certificate to pin.
+    pub fn with_cert_pin(mut self, pin: CertPin) -> Result<Self> {
+        if !self.base_url.starts_with("https://") {
+            return Err(CliError::Config(format!(
+                "A certificate pin needs an https:// server, not {}",
+                self.base_url
+            )));
         }
+        self.cert_pin = Some(pin);
+        self.client = http_client(self.connect_timeout, self.cert_pin)?;
+        Ok(self)
+    }
+
+    /// Sets how long to wait for an inference or job result once connected.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Sets how many times a busy, unreachable, or failing gateway is retried.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the first backoff delay for retries without a `Retry-After` hint.
+    pub fn with_retry_base_delay(mut self, delay: Duration) -> Self {
+        self.retry_base_delay = delay;
+        self
+    }
+
+    /// Retries responses with no (or far too few) frames, up to the retry limit.
+    pub fn with_retry_on_empty(mut self, retry_on_empty: bool) -> Self {
+        self.retry_on_empty = retry_on_empty;
+        self
+    }
+
+    /// Sets how many times the expected frame count a response may report.
+    pub fn with_frame_safety_factor(mut self, factor: f64) -> Self {
+        self.frame_safety_factor = factor;
+        self
+    }
+
+    /// Sets the largest inference request, in bytes, the client will send.
+    pub fn with_max_request_size(mut self, bytes: u64) -> Self {
+        self.max_request_bytes = bytes;
+        self
+    }
+
+    /// Answers repeat health checks from `cache`; clones share its result.
+    pub fn with_health_cache(mut self, cache: HealthCache) -> Self {
+        self.health_cache = cache;
+        self
+    }
+
+    /// Records requests and response metadata into the given debug bundle.
+    pub fn with_debug_bundle(mut self, bundle: SharedBundle) -> Self {
+        self.debug_bundle = Some(bundle);
+        self
     }
 
     /// Checks if the server is healthy and returns version info.
     pub async fn health_check(&self) -> Result<ServerHealth> {
+        if let Some(health) = self.health_cache.get() {
+            tracing::debug!("Health check: cached {}", health.status);
+            return Ok(health);
+        }
         let url = format!("{}/health", self.base_url);
         tracing::debug!("Health check: {url}");
 
         let response = self
             .client
             .get(&url)
-            .timeout(std::time::Duration::from_secs(10))
+            .headers(self.headers.clone())
+            .timeout(Duration::from_secs(10))
             .send()
             .await
-            .map_err(|e| CliError::ServerConnection(e.to_string()))?;
+            .map_err(connection_error)?;
 
         if !response.status().is_success() {
             return Err(CliError::ServerConnection(format!(
@@ -50,10 +214,45 @@ impl MuseTalkClient {
             )));
         }
 
+        let health: ServerHealth = response
+            .json()
+            .await
+            .map_err(|e| CliError::ServerConnection(format!("Invalid health response: {e}")))?;
+        self.health_cache.store(&health);
+        Ok(health)
+    }
+
+    /// Fetches the server's advertised capabilities.
+    ///
+    /// Returns `None` if the server does not expose `/capabilities`.
+    pub async fn capabilities(&self) -> Result<Option<ServerCapabilities>> {
+        let url = format!("{}/capabilities", self.base_url);
+        tracing::debug!("Capabilities: {url}");
+
+        let response = self
+            .client
+            .get(&url)
+            .headers(self.headers.clone())
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(connection_error)?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(CliError::ServerConnection(format!(
+                "Capabilities request failed: {}",
+                response.status()
+            )));
+        }
+
         response
             .json()
             .await
-            .map_err(|e| CliError::ServerConnection(format!("Invalid health response: {e}")))
+            .map(Some)
+            .map_err(|e| CliError::ServerConnection(format!("Invalid capabilities response: {e}")))
     }
 
     /// Sends an inference request with image reference and returns generated frames.
@@ -61,15 +260,10 @@ impl MuseTalkClient {
         &self,
         image: &ImageData,
         audio: &AudioData,
-        fps: u32,
+        options: &InferenceOptions,
     ) -> Result<InferenceResponse> {
-        let request = InferenceRequest {
-            image: Some(image.base64_png.clone()),
-            video: None,
-            audio: audio.base64_wav.clone(),
-            fps,
-        };
-        self.send_inference_request(request).await
+        self.infer(ReferenceInput::Image(image), audio, options)
+            .await
     }
 
     /// Sends an inference request with video reference and returns generated frames.
@@ -77,70 +271,201 @@ impl MuseTalkClient {
         &self,
         video: &VideoData,
         audio: &AudioData,
-        fps: u32,
+        options: &InferenceOptions,
     ) -> Result<InferenceResponse> {
-        let request = InferenceRequest {
-            image: None,
-            video: Some(video.base64_mp4.clone()),
-            audio: audio.base64_wav.clone(),
-            fps,
-        };
-        self.send_inference_request(request).await
+        self.infer(ReferenceInput::Video(video), audio, options)
+            .await
     }
 
     /// Sends an inference request with a reference input (image or video).
+    ///
+    /// With [`InferenceOptions::resume`], a response that breaks off or
+    /// comes back short is continued from its first missing frame; otherwise
+    /// a response with too few frames is retried from scratch when
+    /// [`MuseTalkClient::with_retry_on_empty`] is set.
     pub async fn infer(
         &self,
         reference: ReferenceInput<'_>,
         audio: &AudioData,
-        fps: u32,
+        options: &InferenceOptions,
     ) -> Result<InferenceResponse> {
-        match reference {
-            ReferenceInput::Image(image) => self.infer_with_image(image, audio, fps).await,
-            ReferenceInput::Video(video) => self.infer_with_video(video, audio, fps).await,
+        let request = build_request(reference, audio, options);
+        if options.resume {
+            return self.infer_resuming(request, audio, options).await;
+        }
+        let expected = (audio.duration_secs * options.fps as f32).round() as usize;
+        let limit = limits::max_plausible_frames(
+            audio.duration_secs,
+            options.fps,
+            self.frame_safety_factor,
+        );
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .send_inference_request(&request, options.retry_timeouts)
+                .instrument(request_span("infer"))
+                .await?;
+            limits::check_frame_count(response.total_frames, response.frames.len(), limit)?;
+            integrity::verify_frames(&response.frames)?;
+            if response.video_out.is_some() {
+                return Ok(response);
+            }
+            match retry::incomplete_response(response.frames.len(), expected) {
+                Some(reason) if self.retry_on_empty && attempt < self.max_retries => {
+                    attempt += 1;
+                    tracing::warn!(
+                        "{reason}, retrying (attempt {attempt}/{})",
+                        self.max_retries
+                    );
+                }
+                _ => return Ok(response),
+            }
         }
     }
 
     /// Internal helper to send inference request.
-    async fn send_inference_request(&self, request: InferenceRequest) -> Result<InferenceResponse> {
+    ///
+    /// `retry_timeouts` allows sending it again after a timeout.
+    async fn send_inference_request(
+        &self,
+        request: &InferenceRequest,
+        retry_timeouts: bool,
+    ) -> Result<InferenceResponse> {
         let url = format!("{}/infer", self.base_url);
         tracing::debug!("Inference request: {url}");
 
         // Log request size for debugging
-        let request_size = request.image.as_ref().map(|s| s.len()).unwrap_or(0)
-            + request.video.as_ref().map(|s| s.len()).unwrap_or(0)
-            + request.audio.len();
+        let request_size = payload::request_size(request);
         tracing::info!(
             "Sending inference request: {} MB total",
             request_size as f64 / 1_000_000.0
         );
+        payload::check_request_size(request_size, self.max_request_bytes)?;
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&request)
-            .timeout(std::time::Duration::from_secs(900)) // 15 minutes for video processing
-            .send()
-            .await
-            .map_err(|e| {
-                tracing::error!("Request failed: {e:?}");
-                let source_msg = StdError::source(&e)
-                    .map(|s| format!(": {s}"))
-                    .unwrap_or_default();
-                CliError::ServerConnection(format!("{e}{source_msg}"))
-            })?;
+        if let Some(bundle) = &self.debug_bundle {
+            bundle
+                .lock()
+                .unwrap()
+                .record_request(request, &header_pairs(&self.headers));
+        }
+
+        let mut attempt = 0;
+        let response = loop {
+            let outcome = self.post_inference(&url, request).await;
+            let backoff = retry::backoff(self.retry_base_delay, attempt);
+            let retry = match &outcome {
+                Ok(response) => match retry::retry_after(response.status(), response.headers()) {
+                    Some(wait) => Some((wait, "Server busy".to_string())),
+                    None => retry::is_transient_status(response.status())
+                        .then(|| (backoff, format!("Server returned {}", response.status()))),
+                },
+                Err(e) => retry::is_transient_error(e, retry_timeouts)
+                    .then(|| (backoff, format!("Request failed ({e})"))),
+            };
+            match retry {
+                Some((wait, reason)) if attempt < self.max_retries => {
+                    attempt += 1;
+                    tracing::info!(
+                        "{reason}, retrying in {:.1}s (attempt {attempt}/{})",
+                        wait.as_secs_f64(),
+                        self.max_retries
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                _ => break outcome.map_err(connection_error)?,
+            }
+        };
+
+        let mut meta = ResponseMeta {
+            status: response.status().as_u16(),
+            headers: header_pairs(response.headers()),
+            frame_count: None,
+            warnings: Vec::new(),
+        };
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
+            self.record_response(meta);
+            self.health_cache.invalidate();
             return Err(CliError::ServerConnection(format!(
                 "Inference failed: {status} - {body}"
             )));
         }
 
-        response
-            .json()
+        let parsed = resume::read_response(response).await;
+        if let Ok(response) = &parsed {
+            meta.frame_count = Some(response.total_frames);
+            meta.warnings = response.warnings.clone();
+        }
+        self.record_response(meta);
+        parsed
+    }
+
+    /// Posts the inference request once.
+    async fn post_inference(
+        &self,
+        url: &str,
+        request: &InferenceRequest,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(url)
+            .headers(self.headers.clone())
+            .json(request)
+            .timeout(self.read_timeout)
+            .send()
             .await
-            .map_err(|e| CliError::ServerConnection(format!("Invalid inference response: {e}")))
+            .inspect_err(|e| {
+                tracing::error!("Request failed: {e:?}");
+                self.health_cache.invalidate();
+            })
+    }
+
+    fn record_response(&self, meta: ResponseMeta) {
+        if let Some(bundle) = &self.debug_bundle {
+            bundle.lock().unwrap().record_response(meta);
+        }
+    }
+}
+
+/// Builds the request payload for a reference, audio, and options.
+fn build_request(
+    reference: ReferenceInput<'_>,
+    audio: &AudioData,
+    options: &InferenceOptions,
+) -> InferenceRequest {
+    let (image, video) = match reference {
+        ReferenceInput::Image(image) => (Some(image.base64_png.clone()), None),
+        ReferenceInput::Video(video) => (None, Some(video.base64_mp4.clone())),
+    };
+    InferenceRequest {
+        image,
+        video,
+        audio: match options.audio_url {
+            Some(_) => None,
+            None => Some(audio.base64_wav.clone()),
+        },
+        audio_url: options.audio_url.clone(),
+        fps: options.fps,
+        model: options.model.clone(),
+        preview: options.preview,
+        output: options.output.clone(),
+        face_center: options.face_center,
+        resume_from: None,
+        stream: false,
     }
 }
+
+/// Converts a header map into name/value pairs for diagnostics.
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.to_str().unwrap_or("<binary>").to_string();
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
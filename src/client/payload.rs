@@ -0,0 +1,75 @@
+//! Request payload size limits.
+//!
+//! Oversized uploads tend to hang until the 15-minute inference timeout
+//! instead of failing, so the size is checked before sending.
+
+use super::types::InferenceRequest;
+use crate::error::{CliError, Result};
+
+/// Default request size limit in megabytes, in line with common proxy limits.
+pub const DEFAULT_MAX_REQUEST_MB: f64 = 100.0;
+
+/// Total size in bytes of the encoded media in a request.
+pub fn request_size(request: &InferenceRequest) -> usize {
+    request.image.as_ref().map_or(0, String::len)
+        + request.video.as_ref().map_or(0, String::len)
+        + request.audio.as_ref().map_or(0, String::len)
+}
+
+/// Fails if `size` bytes exceeds `limit` bytes.
+pub fn check_request_size(size: usize, limit: u64) -> Result<()> {
+    if size as u64 <= limit {
+        return Ok(());
+    }
+    Err(CliError::PayloadTooLarge(format!(
+        "request is {:.1} MB, limit is {:.1} MB. Downscale the reference \
+         (--resolution), shorten the audio, or raise --max-request-size",
+        size as f64 / 1_000_000.0,
+        limit as f64 / 1_000_000.0
+    )))
+}
+
+/// Converts a limit in megabytes to bytes.
+pub fn megabytes(mb: f64) -> u64 {
+    (mb * 1_000_000.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::test_support::{MockServer, frames_response, test_audio, test_image};
+
+    #[test]
+    fn test_check_request_size() {
+        let limit = megabytes(1.5);
+        assert_eq!(limit, 1_500_000);
+        assert!(check_request_size(1_500_000, limit).is_ok());
+        assert!(matches!(
+            check_request_size(1_500_001, limit),
+            Err(CliError::PayloadTooLarge(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_audio_url_sent_instead_of_audio() {
+        let server = MockServer::with_infer(|_| frames_response(25)).await;
+        let options = InferenceOptions {
+            audio_url: Some("https://bucket.example.com/talk.wav".to_string()),
+            ..InferenceOptions::new(25)
+        };
+
+        MuseTalkClient::new(server.url())
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+            )
+            .await
+            .unwrap();
+
+        let body = server.requests_to("/infer")[0].json();
+        assert_eq!(body["audio_url"], "https://bucket.example.com/talk.wav");
+        assert!(body.get("audio").is_none());
+    }
+}
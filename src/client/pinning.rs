@@ -0,0 +1,217 @@
+//! Server certificate pinning (`--pin-sha256`).
+//!
+//! A pinned connection accepts exactly one leaf certificate, identified by
+//! the SHA-256 of its DER encoding, instead of any certificate a trusted CA
+//! issued. CA and hostname checks are replaced by the pin, not added to it,
+//! so self-signed server certificates work too. Handshake signatures are
+//! still verified, which proves the server holds the pinned key.
+
+use crate::error::{CliError, Result};
+use ring::digest::{SHA256, digest};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// SHA-256 fingerprint of a pinned leaf certificate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CertPin([u8; 32]);
+
+impl CertPin {
+    /// Fingerprint of a DER-encoded certificate.
+    pub fn of(cert_der: &[u8]) -> Self {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(digest(&SHA256, cert_der).as_ref());
+        Self(bytes)
+    }
+
+    /// Returns true if `cert_der` is the pinned certificate.
+    pub fn matches(&self, cert_der: &[u8]) -> bool {
+        *self == Self::of(cert_der)
+    }
+}
+
+/// Parses 64 hex digits, optionally separated by colons (as `openssl x509
+/// -fingerprint -sha256` prints them).
+impl FromStr for CertPin {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex: String = s.chars().filter(|c| *c != ':').collect();
+        let invalid = || {
+            CliError::Config(format!(
+                "Invalid certificate pin '{s}': expected a SHA-256 fingerprint (64 hex digits)"
+            ))
+        };
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for CertPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+/// Accepts only the pinned leaf certificate.
+#[derive(Debug)]
+struct PinnedVerifier {
+    pin: CertPin,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        if self.pin.matches(end_entity) {
+            return Ok(ServerCertVerified::assertion());
+        }
+        Err(rustls::Error::General(format!(
+            "certificate pin mismatch: expected SHA-256 {}, server presented {}",
+            self.pin,
+            CertPin::of(end_entity)
+        )))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// Builds a TLS configuration that only trusts the pinned certificate.
+pub(super) fn pinned_tls_config(pin: CertPin) -> Result<rustls::ClientConfig> {
+    let provider = rustls::crypto::ring::default_provider();
+    let verifier = PinnedVerifier {
+        pin,
+        algorithms: provider.signature_verification_algorithms,
+    };
+    Ok(
+        rustls::ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| CliError::Config(format!("Failed to set up TLS: {e}")))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &[u8] = b"0\x82\x01\x0a not a real certificate";
+
+    #[test]
+    fn test_pin_matches_only_its_certificate() {
+        let pin: CertPin = CertPin::of(CERT).to_string().parse().unwrap();
+
+        assert!(pin.matches(CERT));
+        assert!(!pin.matches(b"another certificate"));
+        assert!(!pin.matches(&CERT[..CERT.len() - 1]));
+    }
+
+    #[test]
+    fn test_pin_parses_colon_separated_uppercase() {
+        let hex = CertPin::of(CERT).to_string();
+        let openssl_style = hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).unwrap().to_uppercase())
+            .collect::<Vec<_>>()
+            .join(":");
+
+        assert_eq!(openssl_style.parse::<CertPin>().unwrap(), CertPin::of(CERT));
+        assert!("abc123".parse::<CertPin>().is_err());
+        assert!("zz".repeat(32).parse::<CertPin>().is_err());
+    }
+
+    #[test]
+    fn test_mismatch_is_reported_with_both_fingerprints() {
+        let pin = CertPin::of(b"pinned");
+        let verifier = PinnedVerifier {
+            pin,
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        };
+        let err = verifier
+            .verify_server_cert(
+                &CertificateDer::from(CERT),
+                &[],
+                &ServerName::try_from("gpu.local").unwrap(),
+                &[],
+                UnixTime::now(),
+            )
+            .unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains(&pin.to_string()), "{message}");
+        assert!(
+            message.contains(&CertPin::of(CERT).to_string()),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_pinned_client_builds() {
+        let _client = crate::client::MuseTalkClient::new("https://gpu.local:3015")
+            .with_cert_pin(CertPin::of(CERT))
+            .unwrap()
+            .with_connect_timeout(std::time::Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_pin_rejected_for_plain_http() {
+        let args = crate::cli::Args::try_parse_from_args([
+            "musetalk-cli",
+            "-r",
+            "avatar.png",
+            "-a",
+            "audio.wav",
+            "-o",
+            "out.mp4",
+            "--server",
+            "http://gpu.local:3015",
+            "--pin-sha256",
+            &CertPin::of(CERT).to_string(),
+        ])
+        .unwrap();
+
+        let result = crate::client::MuseTalkClient::from_args(&args, None);
+        assert!(matches!(result, Err(CliError::Config(m)) if m.contains("https://")));
+    }
+}
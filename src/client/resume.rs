@@ -0,0 +1,250 @@
+//! Resuming interrupted inference from the first missing frame.
+//!
+//! A server advertising `supports_resume` is asked to continue a response
+//! that broke off mid-body or came back short: the next request carries
+//! `resume_from` set to the first missing frame instead of rendering
+//! everything again. This doesn't need `--retry-on-empty`, though attempts
+//! still count against `--max-retries`. Frames from each attempt are merged
+//! by index. Streams continue the same way (see
+//! [`MuseTalkClient::infer_stream`]). Servers without resume support get a
+//! full restart under `--retry-on-empty`, as before.
+
+use super::types::{Frame, InferenceOptions, InferenceRequest, InferenceResponse};
+use super::{MuseTalkClient, integrity, limits, request_span, retry};
+use crate::error::{CliError, Result};
+use crate::loader::AudioData;
+use std::collections::BTreeMap;
+use tracing::Instrument;
+
+/// Frames received so far, keyed by index.
+#[derive(Debug, Default)]
+pub(super) struct PartialFrames {
+    frames: BTreeMap<usize, Frame>,
+}
+
+impl PartialFrames {
+    /// Adds an attempt's frames; later copies of an index replace earlier ones.
+    pub(super) fn merge(&mut self, frames: Vec<Frame>) {
+        self.frames
+            .extend(frames.into_iter().map(|frame| (frame.index, frame)));
+    }
+
+    /// Index of the first missing frame, i.e. one past the last good frame
+    /// of the unbroken run starting at 0.
+    pub(super) fn resume_from(&self) -> usize {
+        self.frames
+            .keys()
+            .zip(0..)
+            .take_while(|(index, expected)| **index == *expected)
+            .count()
+    }
+
+    /// The unbroken run of frames from index 0.
+    pub(super) fn contiguous(&self) -> Vec<Frame> {
+        self.frames
+            .values()
+            .take(self.resume_from())
+            .cloned()
+            .collect()
+    }
+}
+
+impl MuseTalkClient {
+    /// [`MuseTalkClient::infer`] for a server that supports resume.
+    ///
+    /// Once the retries run out, a short response is returned as it is and
+    /// a broken-off one fails.
+    pub(super) async fn infer_resuming(
+        &self,
+        mut request: InferenceRequest,
+        audio: &AudioData,
+        options: &InferenceOptions,
+    ) -> Result<InferenceResponse> {
+        let fps = options.fps;
+        let expected = (audio.duration_secs * fps as f32).round() as usize;
+        let limit =
+            limits::max_plausible_frames(audio.duration_secs, fps, self.frame_safety_factor);
+        let mut partial = PartialFrames::default();
+        let mut attempt = 0;
+        loop {
+            let sent = self
+                .send_inference_request(&request, options.retry_timeouts)
+                .instrument(request_span("infer"))
+                .await;
+            let reason = match sent {
+                Err(CliError::ResponseTruncated(reason)) if attempt < self.max_retries => reason,
+                sent => {
+                    let mut response = sent?;
+                    limits::check_frame_count(response.total_frames, response.frames.len(), limit)?;
+                    let first = request.resume_from.unwrap_or(0);
+                    integrity::verify_frames_from(first, &response.frames)?;
+                    if response.video_out.is_some() {
+                        return Ok(response);
+                    }
+                    partial.merge(std::mem::take(&mut response.frames));
+                    response.frames = partial.contiguous();
+                    match shortfall(response.frames.len(), response.total_frames, expected) {
+                        Some(reason) if attempt < self.max_retries => reason,
+                        _ => return Ok(response),
+                    }
+                }
+            };
+            attempt += 1;
+            request.resume_from = Some(partial.resume_from());
+            tracing::warn!(
+                "{reason}, resuming from frame {} (attempt {attempt}/{})",
+                partial.resume_from(),
+                self.max_retries
+            );
+        }
+    }
+}
+
+/// Reads an inference response body.
+///
+/// A body that breaks off is a [`CliError::ResponseTruncated`], told apart
+/// from one that doesn't parse, so it can be resumed.
+pub(super) async fn read_response(response: reqwest::Response) -> Result<InferenceResponse> {
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| CliError::ResponseTruncated(format!("connection dropped ({e})")))?;
+    serde_json::from_slice(&body)
+        .map_err(|e| CliError::ServerConnection(format!("Invalid inference response: {e}")))
+}
+
+/// Why `frames` merged frames look cut off, if they do: short of the
+/// frames the audio needs, or of the server's own `total`.
+fn shortfall(frames: usize, total: usize, expected: usize) -> Option<String> {
+    retry::incomplete_response(frames, expected)
+        .or_else(|| (frames < total).then(|| format!("Server sent {frames} of {total} frames")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::test_support::{
+        MockResponse, MockServer, frames_range_response, test_audio, test_image,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_truncated_response_resumes_to_full_frame_set() {
+        let server = MockServer::with_infer(|req| match req.json()["resume_from"].as_u64() {
+            None => frames_range_response(0..8, 25),
+            Some(from) => frames_range_response(from as usize..25, 25),
+        })
+        .await;
+        let options = InferenceOptions {
+            resume: true,
+            ..InferenceOptions::new(25)
+        };
+
+        // Resuming doesn't need --retry-on-empty
+        let client = MuseTalkClient::new(server.url());
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+            )
+            .await
+            .unwrap();
+
+        let indices: Vec<_> = response.frames.iter().map(|f| f.index).collect();
+        assert_eq!(indices, (0..25).collect::<Vec<_>>());
+        let requests = server.requests_to("/infer");
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].json().get("resume_from").is_none());
+        assert_eq!(requests[1].json()["resume_from"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_connection_resumes() {
+        let calls = AtomicUsize::new(0);
+        let server = MockServer::with_infer(move |req| {
+            match (
+                calls.fetch_add(1, Ordering::SeqCst),
+                req.json()["resume_from"].as_u64(),
+            ) {
+                (0, None) => frames_range_response(0..8, 25),
+                // Breaks off halfway through the rest
+                (1, Some(8)) => {
+                    let rest = frames_range_response(8..25, 25);
+                    let half = rest.body.len() / 2;
+                    rest.with_drop_after(half)
+                }
+                (_, Some(from)) => frames_range_response(from as usize..25, 25),
+                (_, None) => MockResponse::status(500),
+            }
+        })
+        .await;
+        let options = InferenceOptions {
+            resume: true,
+            ..InferenceOptions::new(25)
+        };
+
+        let client = MuseTalkClient::new(server.url());
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.frames.len(), 25);
+        let resumed: Vec<_> = server
+            .requests_to("/infer")
+            .iter()
+            .map(|r| r.json()["resume_from"].as_u64())
+            .collect();
+        assert_eq!(resumed, [None, Some(8), Some(8)]);
+    }
+
+    #[tokio::test]
+    async fn test_without_resume_support_restarts() {
+        let server = MockServer::with_infer(|_| frames_range_response(0..8, 25)).await;
+
+        let client = MuseTalkClient::new(server.url())
+            .with_retry_on_empty(true)
+            .with_max_retries(1);
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(25),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.frames.len(), 8);
+        let requests = server.requests_to("/infer");
+        assert_eq!(requests.len(), 2);
+        assert!(
+            requests
+                .iter()
+                .all(|r| r.json().get("resume_from").is_none())
+        );
+    }
+
+    #[test]
+    fn test_resume_point_stops_at_first_gap() {
+        let frame = |index| Frame {
+            index,
+            data: String::new(),
+            sha256: None,
+            landmarks: None,
+            bbox: None,
+        };
+        let mut partial = PartialFrames::default();
+        partial.merge(vec![frame(0), frame(1), frame(3)]);
+        assert_eq!(partial.resume_from(), 2);
+
+        partial.merge(vec![frame(2)]);
+        assert_eq!(partial.resume_from(), 4);
+        assert_eq!(partial.contiguous().len(), 4);
+    }
+}
@@ -0,0 +1,294 @@
+//! Retry decisions for busy or flaky servers.
+//!
+//! A server whose queue is full answers `429 Too Many Requests` with a
+//! `Retry-After` hint, either a number of seconds or an HTTP-date. Some
+//! servers instead return an empty frame set under load, which is retried
+//! when the client opts in.
+//!
+//! Failed connections and gateway errors (502/503/504) carry no hint, so
+//! they are retried with exponential backoff and jitter. A request that
+//! timed out may still be rendering, so it is only sent again to a server
+//! that deduplicates repeats; one that broke off mid-upload never is. Other
+//! 4xx/5xx responses are returned as-is: retrying won't change them.
+
+use super::diagnose::ConnectFailure;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+/// Default number of retries when the server is busy or unreachable.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Default delay before the first backoff retry; doubled on each attempt.
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between backoff retries, before jitter.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Longest wait honored from a single `Retry-After` hint.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Fraction of the expected frames below which a response looks incomplete.
+const MIN_FRAME_FRACTION: f64 = 0.5;
+
+/// Returns how long to wait before retrying, if the response asks for it.
+///
+/// Only `429` responses carrying a parseable `Retry-After` qualify.
+pub fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, SystemTime::now()).map(|d| d.min(MAX_RETRY_AFTER))
+}
+
+/// Returns true for gateway responses worth retrying (502, 503, 504).
+pub fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Returns true if a failed request may succeed when sent again without
+/// the server rendering it twice.
+///
+/// Only a failed connection is certain not to have reached the server; a
+/// timeout counts too when `retry_timeouts` says the server deduplicates
+/// repeats. DNS and TLS failures are configuration problems, so they
+/// aren't retried.
+pub fn is_transient_error(error: &reqwest::Error, retry_timeouts: bool) -> bool {
+    (error.is_connect() || (retry_timeouts && error.is_timeout()))
+        && !matches!(
+            ConnectFailure::classify(error),
+            ConnectFailure::Dns | ConnectFailure::Tls
+        )
+}
+
+/// Delay before backoff retry `attempt` (0-based): `base * 2^attempt`,
+/// capped at [`MAX_BACKOFF`], plus up to 50% random jitter so clients that
+/// failed together don't retry together.
+pub fn backoff(base: Duration, attempt: u32) -> Duration {
+    let delay = base.saturating_mul(1 << attempt.min(16)).min(MAX_BACKOFF);
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    delay + delay.mul_f64((random % 1000) as f64 / 2000.0)
+}
+
+/// Parses a `Retry-After` value (delay in seconds or HTTP-date) relative to `now`.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Describes why a response with `frames` frames looks incomplete, if it does.
+///
+/// `expected` is the frame count implied by the audio duration and fps.
+pub fn incomplete_response(frames: usize, expected: usize) -> Option<String> {
+    if frames == 0 {
+        return Some("Server returned no frames".to_string());
+    }
+    if (frames as f64) < expected as f64 * MIN_FRAME_FRACTION {
+        return Some(format!(
+            "Server returned {frames} of ~{expected} expected frames"
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::error::CliError;
+    use crate::test_support::{MockResponse, MockServer, frames_response, test_audio, test_image};
+    use reqwest::header::HeaderValue;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        let now = SystemTime::now();
+        assert_eq!(parse_retry_after("2", now), Some(Duration::from_secs(2)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        // Dates in the past mean retry immediately
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_retry_after_requires_429() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("5"));
+        assert_eq!(
+            retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(retry_after(StatusCode::SERVICE_UNAVAILABLE, &headers), None);
+        assert_eq!(
+            retry_after(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new()),
+            None
+        );
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("86400"));
+        assert_eq!(
+            retry_after(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(MAX_RETRY_AFTER)
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_with_jitter() {
+        let base = Duration::from_millis(100);
+        for attempt in 0..3 {
+            let expected = base * (1 << attempt);
+            let delay = backoff(base, attempt);
+            assert!(
+                delay >= expected && delay <= expected.mul_f64(1.5),
+                "{delay:?}"
+            );
+        }
+        assert!(backoff(base, 40) <= MAX_BACKOFF.mul_f64(1.5));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_transient_status(StatusCode::BAD_REQUEST));
+    }
+
+    #[tokio::test]
+    async fn test_gateway_errors_retried_with_backoff() {
+        let calls = AtomicUsize::new(0);
+        let server = MockServer::with_infer(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => MockResponse::status(503),
+            1 => MockResponse::status(502),
+            _ => frames_response(2),
+        })
+        .await;
+        let client =
+            MuseTalkClient::new(server.url()).with_retry_base_delay(Duration::from_millis(10));
+
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(30),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.total_frames, 2);
+        assert_eq!(server.requests_to("/infer").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_not_retried() {
+        let server = MockServer::with_infer(|_| MockResponse::status(400)).await;
+        let client =
+            MuseTalkClient::new(server.url()).with_retry_base_delay(Duration::from_millis(10));
+
+        let result = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(30),
+            )
+            .await;
+
+        assert!(matches!(result, Err(CliError::ServerConnection(_))));
+        assert_eq!(server.requests_to("/infer").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_retried_only_for_idempotent_servers() {
+        for (retry_timeouts, sent) in [(false, 1), (true, 2)] {
+            let server = MockServer::with_infer(|_| {
+                frames_response(2).with_delay(Duration::from_millis(500))
+            })
+            .await;
+            let client = MuseTalkClient::new(server.url())
+                .with_read_timeout(Duration::from_millis(100))
+                .with_retry_base_delay(Duration::from_millis(10))
+                .with_max_retries(1);
+            let options = InferenceOptions {
+                retry_timeouts,
+                ..InferenceOptions::new(30)
+            };
+
+            let result = client
+                .infer(
+                    ReferenceInput::Image(&test_image()),
+                    &test_audio(),
+                    &options,
+                )
+                .await;
+
+            assert!(result.is_err());
+            assert_eq!(server.requests_to("/infer").len(), sent, "{retry_timeouts}");
+        }
+    }
+
+    #[test]
+    fn test_incomplete_response() {
+        assert!(incomplete_response(0, 25).is_some());
+        assert!(incomplete_response(0, 0).is_some());
+        assert!(incomplete_response(10, 25).is_some());
+        assert!(incomplete_response(20, 25).is_none());
+        assert!(incomplete_response(30, 25).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_empty_response() {
+        let calls = AtomicUsize::new(0);
+        let server = MockServer::with_infer(move |_| {
+            let count = if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                0
+            } else {
+                25
+            };
+            frames_response(count)
+        })
+        .await;
+        let options = InferenceOptions::new(25);
+
+        let client = MuseTalkClient::new(server.url()).with_retry_on_empty(true);
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.total_frames, 25);
+        assert_eq!(server.requests_to("/infer").len(), 2);
+
+        // Without the flag the empty response is returned as-is
+        let server = MockServer::with_infer(|_| frames_response(0)).await;
+        let client = MuseTalkClient::new(server.url());
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert!(response.frames.is_empty());
+        assert_eq!(server.requests_to("/infer").len(), 1);
+    }
+}
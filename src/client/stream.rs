@@ -0,0 +1,456 @@
+//! Streamed inference (`--pipeline`).
+//!
+//! A server advertising `supports_stream` answers a request with `stream`
+//! set by sending frames as newline-delimited JSON (one [`Frame`] per line)
+//! while it renders. Each frame is handed on as soon as its line arrives, so
+//! decoding and encoding overlap with the rest of the render instead of
+//! waiting for the whole response. The last line may instead be a
+//! [`StreamEnd`] with the server's frame count and warnings.
+//!
+//! A stream that breaks off is continued from the next frame when the server
+//! supports resume (see [`super::resume`]); frames already handed on are
+//! never sent again.
+
+use super::diagnose::connection_error;
+use super::types::{Frame, InferenceRequest, InferenceResponse};
+use super::{
+    MuseTalkClient, ReferenceInput, build_request, integrity, limits, payload, request_span, retry,
+};
+use crate::client::InferenceOptions;
+use crate::error::{CliError, Result};
+use crate::loader::AudioData;
+use serde::Deserialize;
+use tracing::Instrument;
+
+/// Content type of a streamed response.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// The closing line of a stream.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamEnd {
+    /// Frames the server rendered.
+    #[serde(default)]
+    pub total_frames: Option<usize>,
+    /// Non-fatal issues the server noticed while rendering.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// One line of a streamed response.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StreamLine {
+    Frame(Frame),
+    End(StreamEnd),
+}
+
+/// What a finished stream delivered.
+#[derive(Debug, Default)]
+pub struct StreamSummary {
+    /// Frames handed on.
+    pub frames: usize,
+    /// Server warnings, from the closing line or a regular response.
+    pub warnings: Vec<String>,
+}
+
+/// Splits response chunks into complete lines.
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Adds `chunk`, returning the lines it completes (without newlines).
+    fn push(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let mut line: Vec<u8> = self.pending.drain(..=end).collect();
+            line.pop();
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// The final line, if the body didn't end with a newline.
+    fn finish(self) -> Option<Vec<u8>> {
+        Some(self.pending).filter(|line| !line.is_empty())
+    }
+}
+
+impl MuseTalkClient {
+    /// Sends a streaming inference request, calling `on_frame` with each
+    /// frame in order as it arrives.
+    ///
+    /// Frames already handed on can't be taken back, so unlike
+    /// [`MuseTalkClient::infer`] the request is never restarted. With
+    /// [`InferenceOptions::resume`], a stream that breaks off or ends short
+    /// of the expected frames is continued from the next frame, up to the
+    /// retry limit; otherwise it fails instead of being accepted. A server
+    /// that ignores `stream` and sends a regular response still works; its
+    /// frames are passed on once the whole body has arrived.
+    pub async fn infer_stream(
+        &self,
+        reference: ReferenceInput<'_>,
+        audio: &AudioData,
+        options: &InferenceOptions,
+        mut on_frame: impl FnMut(Frame) -> Result<()>,
+    ) -> Result<StreamSummary> {
+        let mut request = build_request(reference, audio, options);
+        request.stream = true;
+        payload::check_request_size(payload::request_size(&request), self.max_request_bytes)?;
+        let expected = (audio.duration_secs * options.fps as f32).round() as usize;
+        let limit = limits::max_plausible_frames(
+            audio.duration_secs,
+            options.fps,
+            self.frame_safety_factor,
+        );
+        let mut count = 0;
+        let mut attempt = 0;
+        let end = loop {
+            let mut accept = |frame: Frame| -> Result<()> {
+                integrity::verify_frame(count, &frame)?;
+                count += 1;
+                limits::check_frame_count(0, count, limit)?;
+                on_frame(frame)
+            };
+            let outcome = self
+                .stream_response(&request, &mut accept)
+                .await
+                .and_then(|end| check_complete(count, expected, end.total_frames).map(|()| end));
+            match outcome {
+                Err(CliError::ResponseTruncated(reason))
+                    if options.resume && attempt < self.max_retries =>
+                {
+                    attempt += 1;
+                    request.resume_from = Some(count);
+                    tracing::warn!(
+                        "{reason}, resuming from frame {count} (attempt {attempt}/{})",
+                        self.max_retries
+                    );
+                }
+                outcome => break outcome?,
+            }
+        };
+        Ok(StreamSummary {
+            frames: count,
+            warnings: end.warnings,
+        })
+    }
+
+    /// Sends `request` once, handing each frame of the response to `accept`.
+    async fn stream_response(
+        &self,
+        request: &InferenceRequest,
+        accept: &mut impl FnMut(Frame) -> Result<()>,
+    ) -> Result<StreamEnd> {
+        let url = format!("{}/infer", self.base_url);
+        tracing::debug!("Streaming inference request: {url}");
+        let response = self
+            .post_inference(&url, request)
+            .instrument(request_span("infer_stream"))
+            .await
+            .map_err(connection_error)?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            self.health_cache.invalidate();
+            return Err(CliError::ServerConnection(format!(
+                "Inference failed: {status} - {body}"
+            )));
+        }
+
+        let streamed = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with(NDJSON_CONTENT_TYPE));
+        if !streamed {
+            tracing::warn!("Server ignored the stream request; waiting for the full response");
+            let parsed: InferenceResponse = response.json().await.map_err(|e| {
+                CliError::ServerConnection(format!("Invalid inference response: {e}"))
+            })?;
+            parsed.frames.into_iter().try_for_each(accept)?;
+            return Ok(StreamEnd {
+                total_frames: Some(parsed.total_frames),
+                warnings: parsed.warnings,
+            });
+        }
+        read_lines(response, accept).await
+    }
+}
+
+/// Hands each frame line of a streamed body to `accept` as it arrives,
+/// returning the closing line (or a default one if the server sent none).
+async fn read_lines(
+    mut response: reqwest::Response,
+    accept: &mut impl FnMut(Frame) -> Result<()>,
+) -> Result<StreamEnd> {
+    let mut end = None;
+    let mut lines = LineBuffer::default();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| CliError::ResponseTruncated(format!("connection dropped ({e})")))?
+    {
+        for line in lines.push(&chunk) {
+            accept_line(&line, &mut end, accept)?;
+        }
+    }
+    if let Some(line) = lines.finish() {
+        accept_line(&line, &mut end, accept)?;
+    }
+    Ok(end.unwrap_or_default())
+}
+
+/// Parses one streamed line, handing frames to `accept` and keeping the
+/// closing line in `end`; blank lines are skipped.
+fn accept_line(
+    line: &[u8],
+    end: &mut Option<StreamEnd>,
+    accept: &mut impl FnMut(Frame) -> Result<()>,
+) -> Result<()> {
+    if line.trim_ascii().is_empty() {
+        return Ok(());
+    }
+    if end.is_some() {
+        return Err(CliError::ServerConnection(
+            "Streamed data after the closing line".to_string(),
+        ));
+    }
+    match serde_json::from_slice(line)
+        .map_err(|e| CliError::ServerConnection(format!("Invalid streamed frame: {e}")))?
+    {
+        StreamLine::Frame(frame) => accept(frame),
+        StreamLine::End(closing) => {
+            *end = Some(closing);
+            Ok(())
+        }
+    }
+}
+
+/// Fails a stream that stopped before the render was done: short of the
+/// server's own count, or of the frames the audio needs.
+fn check_complete(frames: usize, expected: usize, total: Option<usize>) -> Result<()> {
+    if let Some(total) = total.filter(|total| frames < *total) {
+        return Err(CliError::ResponseTruncated(format!(
+            "stream stopped after {frames} of {total} frames"
+        )));
+    }
+    match retry::incomplete_response(frames, expected) {
+        Some(reason) => Err(CliError::ResponseTruncated(reason)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        MockResponse, MockServer, frames_response, ndjson_frames, test_audio, test_image,
+        tiny_png_base64,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_lines_split_across_chunks() {
+        let mut lines = LineBuffer::default();
+        assert!(lines.push(b"{\"a\":").is_empty());
+        assert_eq!(lines.push(b"1}\n{\"b\""), [b"{\"a\":1}".to_vec()]);
+        assert_eq!(lines.push(b":2}\n\n"), [b"{\"b\":2}".to_vec(), Vec::new()]);
+        assert!(lines.finish().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_frames_passed_on_in_order() {
+        let server = MockServer::with_infer(|_| {
+            MockResponse::status(200)
+                .with_body(ndjson_frames(0..3))
+                .with_header("Content-Type", NDJSON_CONTENT_TYPE)
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+
+        let mut indices = Vec::new();
+        let summary = client
+            .infer_stream(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(3),
+                |frame| {
+                    indices.push(frame.index);
+                    Ok(())
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(summary.frames, 3);
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(server.requests_to("/infer")[0].json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_frames_handed_on_before_response_ends() {
+        let delay = Duration::from_millis(150);
+        let server = MockServer::with_infer(move |_| {
+            MockResponse::status(200)
+                .with_body(ndjson_frames(0..3))
+                .with_header("Content-Type", NDJSON_CONTENT_TYPE)
+                .with_line_delay(delay)
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+
+        let mut arrivals = Vec::new();
+        client
+            .infer_stream(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(3),
+                |_| {
+                    arrivals.push(Instant::now());
+                    Ok(())
+                },
+            )
+            .await
+            .unwrap();
+
+        // Buffering the whole body would deliver all three at once
+        assert_eq!(arrivals.len(), 3);
+        assert!(arrivals[2] - arrivals[0] >= delay * 3 / 2, "{arrivals:?}");
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_frame_rejected() {
+        let server = MockServer::with_infer(|_| {
+            let body = format!(
+                "{}\n",
+                serde_json::json!({"index": 1, "data": tiny_png_base64()})
+            );
+            MockResponse::status(200)
+                .with_body(body)
+                .with_header("Content-Type", NDJSON_CONTENT_TYPE)
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+
+        let err = client
+            .infer_stream(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(3),
+                |_| Ok(()),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::FrameIntegrity(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_regular_response_still_accepted() {
+        let server = MockServer::with_infer(|_| frames_response(2)).await;
+        let client = MuseTalkClient::new(server.url());
+
+        let summary = client
+            .infer_stream(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(2),
+                |_| Ok(()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.frames, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ending_early_fails() {
+        // A dropped connection: 2 of the 25 frames a second at 25 fps needs
+        let server = MockServer::with_infer(|_| {
+            MockResponse::status(200)
+                .with_body(ndjson_frames(0..2))
+                .with_header("Content-Type", NDJSON_CONTENT_TYPE)
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+
+        let err = client
+            .infer_stream(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(25),
+                |_| Ok(()),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("ended early"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_resumes_from_next_frame() {
+        let server = MockServer::with_infer(|req| {
+            let response =
+                MockResponse::status(200).with_header("Content-Type", NDJSON_CONTENT_TYPE);
+            match req.json()["resume_from"].as_u64() {
+                // The connection drops after two of the four frames
+                None => response
+                    .with_body(ndjson_frames(0..4))
+                    .with_drop_after(ndjson_frames(0..2).len()),
+                Some(from) => response.with_body(ndjson_frames(from as usize..4)),
+            }
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+        let options = InferenceOptions {
+            resume: true,
+            ..InferenceOptions::new(4)
+        };
+
+        let mut indices = Vec::new();
+        let summary = client
+            .infer_stream(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &options,
+                |frame| {
+                    indices.push(frame.index);
+                    Ok(())
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(summary.frames, 4);
+        assert_eq!(indices, [0, 1, 2, 3]);
+        let requests = server.requests_to("/infer");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].json()["resume_from"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_closing_line_counts_and_warns() {
+        let server = MockServer::with_infer(|req| {
+            let total = if req.json()["fps"] == 3 { 3 } else { 4 };
+            let closing = serde_json::json!({"total_frames": total, "warnings": ["blurry"]});
+            MockResponse::status(200)
+                .with_body(format!("{}{closing}\n", ndjson_frames(0..3)))
+                .with_header("Content-Type", NDJSON_CONTENT_TYPE)
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+        let (image, audio) = (test_image(), test_audio());
+        let options = [InferenceOptions::new(3), InferenceOptions::new(4)];
+        let stream = |options| {
+            client.infer_stream(ReferenceInput::Image(&image), &audio, options, |_| Ok(()))
+        };
+
+        let summary = stream(&options[0]).await.unwrap();
+        assert_eq!(summary.frames, 3);
+        assert_eq!(summary.warnings, ["blurry"]);
+        // The server said it rendered 4, but only 3 arrived
+        let err = stream(&options[1]).await.unwrap_err();
+        assert!(err.to_string().contains("3 of 4 frames"), "{err}");
+    }
+}
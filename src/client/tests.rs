@@ -0,0 +1,180 @@
+//! Client request building and server interaction tests.
+
+use super::*;
+use crate::test_support::{MockResponse, MockServer, frames_response, test_audio, test_image};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn test_build_request_model() {
+    let image = test_image();
+    let options = InferenceOptions {
+        model: Some("musetalk-v15".to_string()),
+        ..InferenceOptions::new(25)
+    };
+    let request = build_request(ReferenceInput::Image(&image), &test_audio(), &options);
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["model"], "musetalk-v15");
+    assert_eq!(json["fps"], 25);
+
+    let request = build_request(
+        ReferenceInput::Image(&image),
+        &test_audio(),
+        &InferenceOptions::new(25),
+    );
+    let json = serde_json::to_value(&request).unwrap();
+    assert!(json.get("model").is_none());
+}
+
+#[test]
+fn test_face_center_forwarded() {
+    let options = InferenceOptions {
+        face_center: Some([330, 80]),
+        ..InferenceOptions::new(25)
+    };
+    let request = build_request(
+        ReferenceInput::Image(&test_image()),
+        &test_audio(),
+        &options,
+    );
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["face_center"], serde_json::json!([330, 80]));
+
+    let request = build_request(
+        ReferenceInput::Image(&test_image()),
+        &test_audio(),
+        &InferenceOptions::new(25),
+    );
+    let json = serde_json::to_value(&request).unwrap();
+    assert!(json.get("face_center").is_none());
+}
+
+#[tokio::test]
+async fn test_preview_flag_only_on_preview_request() {
+    let server = MockServer::with_infer(|_| frames_response(1)).await;
+    let client = MuseTalkClient::new(server.url());
+    let image = test_image();
+    let preview = InferenceOptions {
+        preview: true,
+        ..InferenceOptions::new(30)
+    };
+
+    for options in [&preview, &InferenceOptions::new(30)] {
+        client
+            .infer(ReferenceInput::Image(&image), &test_audio(), options)
+            .await
+            .unwrap();
+    }
+
+    let requests = server.requests_to("/infer");
+    assert_eq!(requests[0].json()["preview"], true);
+    assert!(requests[1].json().get("preview").is_none());
+}
+
+#[tokio::test]
+async fn test_capabilities_missing_endpoint() {
+    let server = MockServer::with_infer(|_| frames_response(1)).await;
+    let client = MuseTalkClient::new(server.url());
+    assert!(client.capabilities().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_capabilities_parsed() {
+    let server = MockServer::start(|_| {
+        MockResponse::json(serde_json::json!({"supported_formats": ["h264", "png"]}))
+    })
+    .await;
+    let client = MuseTalkClient::new(server.url());
+    let caps = client.capabilities().await.unwrap().unwrap();
+    assert_eq!(caps.supported_formats, ["h264", "png"]);
+}
+
+#[tokio::test]
+async fn test_oversized_request_rejected_before_sending() {
+    let server = MockServer::with_infer(|_| frames_response(1)).await;
+    let client = MuseTalkClient::new(server.url()).with_max_request_size(8);
+
+    let result = client
+        .infer(
+            ReferenceInput::Image(&test_image()),
+            &test_audio(),
+            &InferenceOptions::new(30),
+        )
+        .await;
+
+    assert!(matches!(result, Err(CliError::PayloadTooLarge(_))));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn test_custom_headers_reach_server() {
+    let server = MockServer::with_infer(|_| frames_response(1)).await;
+    let headers: Vec<HeaderArg> = vec![
+        "X-Tenant-Id: acme".parse().unwrap(),
+        "X-Trace-Id: trace-42".parse().unwrap(),
+    ];
+    let client =
+        MuseTalkClient::new(server.url()).with_headers(build_header_map(&headers).unwrap());
+
+    client.health_check().await.unwrap();
+    client
+        .infer(
+            ReferenceInput::Image(&test_image()),
+            &test_audio(),
+            &InferenceOptions::new(30),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(request.headers["x-tenant-id"], "acme");
+        assert_eq!(request.headers["x-trace-id"], "trace-42");
+    }
+}
+
+#[tokio::test]
+async fn test_busy_server_retry_after_honored() {
+    let calls = AtomicUsize::new(0);
+    let server = MockServer::with_infer(move |_| {
+        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            MockResponse::status(429).with_header("Retry-After", "2")
+        } else {
+            frames_response(2)
+        }
+    })
+    .await;
+    let client = MuseTalkClient::new(server.url());
+
+    let start = std::time::Instant::now();
+    let response = client
+        .infer(
+            ReferenceInput::Image(&test_image()),
+            &test_audio(),
+            &InferenceOptions::new(30),
+        )
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() >= std::time::Duration::from_secs(2));
+    assert_eq!(response.total_frames, 2);
+    assert_eq!(server.requests_to("/infer").len(), 2);
+}
+
+#[tokio::test]
+async fn test_busy_server_gives_up_after_max_retries() {
+    let server =
+        MockServer::with_infer(|_| MockResponse::status(429).with_header("Retry-After", "0")).await;
+    let client = MuseTalkClient::new(server.url()).with_max_retries(1);
+
+    let result = client
+        .infer(
+            ReferenceInput::Image(&test_image()),
+            &test_audio(),
+            &InferenceOptions::new(30),
+        )
+        .await;
+
+    assert!(matches!(result, Err(CliError::ServerConnection(_))));
+    assert_eq!(server.requests_to("/infer").len(), 2);
+}
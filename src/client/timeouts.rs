@@ -0,0 +1,60 @@
+//! Connection and read timeouts.
+//!
+//! An unreachable server should fail fast, while a long render on a
+//! reachable one should be waited out, so the two are configured apart.
+
+use super::pinning::{CertPin, pinned_tls_config};
+use crate::error::{CliError, Result};
+use std::time::Duration;
+
+/// Default seconds to wait for a TCP connection to the server.
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// Default seconds to wait for inference to finish once connected.
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 900;
+
+/// Builds an HTTP client that gives up connecting after `connect_timeout`,
+/// trusting only the pinned certificate when one is given.
+pub(super) fn http_client(
+    connect_timeout: Duration,
+    pin: Option<CertPin>,
+) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().connect_timeout(connect_timeout);
+    if let Some(pin) = pin {
+        builder = builder.use_preconfigured_tls(pinned_tls_config(pin)?);
+    }
+    builder
+        .build()
+        .map_err(|e| CliError::Config(format!("Failed to set up the HTTP client: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+    use crate::error::CliError;
+    use crate::test_support::{test_audio, test_image};
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_dead_host_fails_within_connect_timeout() {
+        // A non-routable address never answers the TCP handshake
+        let client = MuseTalkClient::new("http://10.255.255.1:81")
+            .with_connect_timeout(Duration::from_millis(200))
+            .unwrap()
+            .with_read_timeout(Duration::from_secs(60))
+            .with_max_retries(0);
+
+        let start = Instant::now();
+        let result = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(25),
+            )
+            .await;
+
+        assert!(matches!(result, Err(CliError::ServerConnection(_))));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -10,9 +10,85 @@ pub struct ServerHealth {
     pub version: Option<String>,
 }
 
+/// Optional server capabilities advertised at `/capabilities`.
+///
+/// All fields default to empty, meaning "unknown" rather than "unsupported".
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerCapabilities {
+    /// Reference codecs/formats the server can decode (e.g. `h264`, `png`).
+    #[serde(default)]
+    pub supported_formats: Vec<String>,
+    /// Server requires mono audio.
+    #[serde(default)]
+    pub requires_mono: bool,
+    /// Model variants the server can run.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Server can render a fast low-resolution preview.
+    #[serde(default)]
+    pub supports_preview: bool,
+    /// Model weights are already loaded, so there is no cold start.
+    #[serde(default)]
+    pub model_loaded: bool,
+    /// Server can fetch audio itself from an `audio_url`.
+    #[serde(default)]
+    pub supports_audio_url: bool,
+    /// Output formats the server can assemble itself (e.g. `mp4`).
+    #[serde(default)]
+    pub assembles: Vec<String>,
+    /// Server can continue a render from `resume_from`.
+    #[serde(default)]
+    pub supports_resume: bool,
+    /// Highest frame rate the server renders (unlimited when unset).
+    #[serde(default)]
+    pub max_fps: Option<u32>,
+    /// Server can stream frames as they render (`stream` requests).
+    #[serde(default)]
+    pub supports_stream: bool,
+    /// Server answers a repeated request from the same job instead of
+    /// rendering it again.
+    #[serde(default)]
+    pub idempotent: bool,
+}
+
+/// Per-request inference options.
+#[derive(Debug, Clone, Default)]
+pub struct InferenceOptions {
+    /// Target frames per second.
+    pub fps: u32,
+    /// Model variant; the server default is used when unset.
+    pub model: Option<String>,
+    /// Request a fast low-resolution preview instead of the full render.
+    pub preview: bool,
+    /// Have the server fetch the audio from this URL instead of uploading it.
+    pub audio_url: Option<String>,
+    /// Ask the server to return a finished video in this format.
+    pub output: Option<String>,
+    /// Face center `[x, y]` in reference pixels, instead of server detection.
+    pub face_center: Option<[u32; 2]>,
+    /// Continue responses that break off or come back short with
+    /// `resume_from`, instead of restarting or failing (needs a server with
+    /// `supports_resume`).
+    pub resume: bool,
+    /// Send a request that timed out again, which is only safe when the
+    /// server deduplicates repeats (`idempotent`).
+    pub retry_timeouts: bool,
+}
+
+impl InferenceOptions {
+    /// Creates options for the given frame rate.
+    pub fn new(fps: u32) -> Self {
+        Self {
+            fps,
+            ..Default::default()
+        }
+    }
+}
+
 /// Inference request payload.
 ///
-/// Either `image` or `video` should be provided, not both.
+/// Either `image` or `video` should be provided, not both, and likewise
+/// either `audio` or `audio_url`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InferenceRequest {
     /// Base64-encoded PNG image (optional, use for static image reference).
@@ -22,9 +98,31 @@ pub struct InferenceRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub video: Option<String>,
     /// Base64-encoded WAV audio.
-    pub audio: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    /// URL the server fetches the audio from instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_url: Option<String>,
     /// Target frames per second.
     pub fps: u32,
+    /// Model variant to run (server default when absent).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Fast low-resolution preview render (omitted for full renders).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preview: bool,
+    /// Finished video format requested from the server (e.g. `mp4`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Face center `[x, y]` in reference pixels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub face_center: Option<[u32; 2]>,
+    /// First frame to render when continuing an interrupted response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resume_from: Option<usize>,
+    /// Send frames as newline-delimited JSON while rendering (omitted otherwise).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
 }
 
 /// Inference response with generated frames.
@@ -33,6 +131,12 @@ pub struct InferenceResponse {
     pub status: String,
     pub total_frames: usize,
     pub frames: Vec<Frame>,
+    /// Non-fatal diagnostics such as "face partially occluded".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Base64 video assembled by the server, sent instead of frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_out: Option<String>,
 }
 
 /// A single generated frame.
@@ -41,4 +145,35 @@ pub struct Frame {
     pub index: usize,
     /// Base64-encoded PNG frame data.
     pub data: String,
+    /// Hex SHA-256 of the decoded frame bytes, when the server provides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Face landmarks `[x, y]` in reference pixels, when the server provides them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub landmarks: Option<Vec<[f32; 2]>>,
+    /// Crop box `[x, y, width, height]` the server used for this frame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<[u32; 4]>,
+}
+
+/// Response to a queued job submission at `/jobs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSubmission {
+    pub job_id: String,
+}
+
+/// One page of a finished job's stored frames, from `/jobs/{id}/frames`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FramePage {
+    pub frames: Vec<Frame>,
+    pub total_frames: usize,
+    /// Offset of the next page; absent on the last page.
+    #[serde(default)]
+    pub next_offset: Option<usize>,
+}
+
+/// Progress of a queued job that has not finished yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub status: String,
 }
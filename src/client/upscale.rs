@@ -0,0 +1,167 @@
+//! Client for an external super-resolution server.
+//!
+//! Frames are POSTed one at a time to `{url}/upscale` as `{"image": <base64
+//! PNG>}` and the server answers with the upscaled PNG in the same shape.
+//! Requests carry the same `--header`s and timeouts as the MuseTalk server's.
+
+use super::diagnose::connection_error;
+use super::headers::build_header_map;
+use super::timeouts::{DEFAULT_CONNECT_TIMEOUT_SECS, http_client};
+use crate::cli::Args;
+use crate::error::{CliError, Result};
+use crate::throttle::ThrottledLog;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default seconds to wait for one frame to be upscaled.
+pub const DEFAULT_UPSCALE_TIMEOUT_SECS: u64 = 60;
+
+/// Request and response body for `/upscale`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UpscalePayload {
+    image: String,
+}
+
+/// Client for a frame upscaling server.
+pub struct UpscaleClient {
+    base_url: String,
+    client: reqwest::Client,
+    headers: HeaderMap,
+    timeout: Duration,
+}
+
+impl UpscaleClient {
+    /// Creates a client for the given upscaling server URL.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            // As for `MuseTalkClient::new`, only TLS backend setup can fail
+            client: http_client(Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS), None)
+                .unwrap_or_default(),
+            headers: HeaderMap::new(),
+            timeout: Duration::from_secs(DEFAULT_UPSCALE_TIMEOUT_SECS),
+        }
+    }
+
+    /// Creates a client for `url` with the `--header`s, `--connect-timeout`,
+    /// and `--read-timeout` given for the MuseTalk server.
+    pub fn from_args(url: &str, args: &Args) -> Result<Self> {
+        Ok(Self {
+            client: http_client(Duration::from_secs(args.connect_timeout), None)?,
+            headers: build_header_map(&args.headers)?,
+            timeout: Duration::from_secs(args.read_timeout),
+            ..Self::new(url)
+        })
+    }
+
+    /// Upscales a single base64-encoded PNG frame.
+    pub async fn upscale_frame(&self, frame: &str) -> Result<String> {
+        let url = format!("{}/upscale", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .headers(self.headers.clone())
+            .json(&UpscalePayload {
+                image: frame.to_string(),
+            })
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(connection_error)?;
+
+        if !response.status().is_success() {
+            return Err(CliError::ServerConnection(format!(
+                "Upscale failed: {}",
+                response.status()
+            )));
+        }
+
+        let payload: UpscalePayload = response
+            .json()
+            .await
+            .map_err(|e| CliError::ServerConnection(format!("Invalid upscale response: {e}")))?;
+        Ok(payload.image)
+    }
+
+    /// Upscales every frame, keeping the original for any frame that fails.
+    pub async fn upscale_frames(&self, frames: Vec<String>) -> Vec<String> {
+        let mut upscaled = Vec::with_capacity(frames.len());
+        let mut failures = 0;
+        let mut log = ThrottledLog::new("Upscaled", frames.len());
+        for (i, frame) in frames.into_iter().enumerate() {
+            match self.upscale_frame(&frame).await {
+                Ok(frame) => upscaled.push(frame),
+                Err(e) => {
+                    tracing::debug!("Frame {i} kept at original size: {e}");
+                    failures += 1;
+                    upscaled.push(frame);
+                }
+            }
+            log.tick();
+        }
+        log.finish();
+        if failures > 0 {
+            tracing::warn!(
+                "{failures} of {} frames could not be upscaled; using originals",
+                upscaled.len()
+            );
+        }
+        upscaled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_upscale_round_trip_with_fallback() {
+        let server = MockServer::start(|req| {
+            let image = req.json()["image"].as_str().unwrap().to_string();
+            if image == "bad" {
+                MockResponse::status(500)
+            } else {
+                MockResponse::json(serde_json::json!({"image": format!("{image}-2x")}))
+            }
+        })
+        .await;
+        let client = UpscaleClient::new(server.url());
+
+        let frames = vec!["a".to_string(), "bad".to_string(), "c".to_string()];
+        let upscaled = client.upscale_frames(frames).await;
+
+        assert_eq!(upscaled, ["a-2x", "bad", "c-2x"]);
+        assert_eq!(server.requests_to("/upscale").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_headers_and_timeout_from_args() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(serde_json::json!({"image": "2x"}))
+                .with_delay(Duration::from_millis(1500))
+        })
+        .await;
+        let args = Args::try_parse_from_args([
+            "musetalk-cli",
+            "-r",
+            "avatar.png",
+            "-a",
+            "audio.wav",
+            "-o",
+            "out.mp4",
+            "--header",
+            "X-Tenant-Id: acme",
+            "--read-timeout",
+            "1",
+        ])
+        .unwrap();
+        let client = UpscaleClient::from_args(server.url(), &args).unwrap();
+
+        // The reply comes after --read-timeout, so the frame is kept as-is
+        assert_eq!(client.upscale_frames(vec!["a".to_string()]).await, ["a"]);
+        let request = &server.requests_to("/upscale")[0];
+        assert_eq!(request.headers["x-tenant-id"], "acme");
+    }
+}
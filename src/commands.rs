@@ -0,0 +1,450 @@
+//! Standalone modes of the binary that bypass the normal render.
+
+use crate::stages::Inference;
+use anyhow::{Context, Result};
+use musetalk_cli::assembler::{VideoAssembler, available_codecs, check_ffmpeg, write_server_video};
+use musetalk_cli::batch::{
+    Checkpoint, Manifest, TemplateContext, list_jobs, missing_audio, pair_directory, run_jobs,
+    track_jobs,
+};
+use musetalk_cli::benchmark::{run_benchmark, synthetic_inputs};
+use musetalk_cli::client::jobs::DEFAULT_FRAME_PAGE_SIZE;
+use musetalk_cli::client::{Frame, InferenceOptions, JobState, MuseTalkClient};
+use musetalk_cli::config::{default_config_path, write_config_template};
+use musetalk_cli::debug_bundle::SharedBundle;
+use musetalk_cli::events::EventStream;
+use musetalk_cli::hook::HookFailed;
+use musetalk_cli::loader::{AudioData, load_audio_with, load_image};
+use musetalk_cli::matrix::{MatrixInput, run_matrix};
+use musetalk_cli::smoke;
+use musetalk_cli::validation::{validate_audio_path, validate_output_path};
+use musetalk_cli::{Args, validate_inputs};
+use std::cell::Cell;
+use std::path::Path;
+
+/// Runs a mode that replaces the render, if one was requested.
+pub async fn standalone(args: &Args, bundle: Option<&SharedBundle>) -> Option<Result<()>> {
+    if let Some(path) = &args.init_config {
+        return Some(init_config(path, args.overwrite));
+    }
+    if args.audio_info {
+        return Some(audio_info(args));
+    }
+    if args.list_codecs {
+        return Some(list_codecs(args));
+    }
+    if args.benchmark {
+        return Some(benchmark(args, bundle).await);
+    }
+    if let Some(path) = &args.compare_servers_matrix {
+        return Some(server_matrix(args, path, bundle).await);
+    }
+    if let Some(job_id) = &args.fetch {
+        return Some(fetch_queued_job(args, job_id, bundle).await);
+    }
+    if let Some(job_id) = &args.fetch_frames {
+        return Some(fetch_job_frames(args, job_id, bundle).await);
+    }
+    None
+}
+
+/// Writes a commented config template to `path`.
+fn init_config(path: &Path, overwrite: bool) -> Result<()> {
+    write_config_template(path, overwrite).context("Failed to write config")?;
+    println!("Config template written to {}", path.display());
+    if let Some(default) = default_config_path() {
+        println!(
+            "Pass it with --config, or move it to {} to load it by default",
+            default.display()
+        );
+    }
+    Ok(())
+}
+
+/// Reports the format and loudness of `--audio`.
+pub fn audio_info(args: &Args) -> Result<()> {
+    let path = crate::stages::required_path(&args.audio, "--audio")?;
+    let audio = load_audio_with(path, &crate::stages::audio_options(args))
+        .context("Failed to load audio")?;
+    println!("Audio: {}", path.display());
+    println!(
+        "  {:.2}s, {} Hz, {} channel(s)",
+        audio.duration_secs, audio.sample_rate, audio.channels
+    );
+    println!("{}", audio.loudness());
+    println!("  Clipped samples: {}", audio.clip_count());
+    Ok(())
+}
+
+/// Reports which supported codecs the configured FFmpeg can encode.
+pub fn list_codecs(args: &Args) -> Result<()> {
+    let report = available_codecs(&args.ffmpeg).context("Failed to list FFmpeg encoders")?;
+    print!("{report}");
+    Ok(())
+}
+
+/// Times synthetic inference round-trips against the server.
+pub async fn benchmark(args: &Args, bundle: Option<&SharedBundle>) -> Result<()> {
+    let client = MuseTalkClient::from_args(args, bundle)?;
+    let options = InferenceOptions {
+        model: args.model.clone(),
+        ..InferenceOptions::new(args.fps)
+    };
+    if args.warmup {
+        let caps = client.capabilities().await.unwrap_or_default();
+        smoke::warm_up(&client, caps.as_ref(), &options).await?;
+    }
+    let report = run_benchmark(
+        &client,
+        args.benchmark_duration,
+        args.benchmark_iterations,
+        &options,
+    )
+    .await
+    .context("Benchmark failed")?;
+    println!("{report}");
+    Ok(())
+}
+
+/// Renders each `--matrix-audio` on each `--matrix-server` and writes the matrix.
+///
+/// Uses `--reference` when given (image only), else the benchmark's synthetic image.
+pub async fn server_matrix(args: &Args, path: &Path, bundle: Option<&SharedBundle>) -> Result<()> {
+    let reference = match &args.reference {
+        Some(reference) => load_image(reference).context("Failed to load reference")?,
+        None => synthetic_inputs(1.0)?.0,
+    };
+    let inputs = args
+        .matrix_audio
+        .iter()
+        .map(|audio| {
+            let audio_data = load_audio_with(audio, &crate::stages::audio_options(args))
+                .with_context(|| format!("Failed to load {}", audio.display()))?;
+            Ok(MatrixInput {
+                name: audio.display().to_string(),
+                audio: audio_data,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let servers = args
+        .matrix_server
+        .iter()
+        .map(|server| {
+            let server_args = Args {
+                server: server.clone(),
+                ..args.clone()
+            };
+            Ok((
+                server.clone(),
+                MuseTalkClient::from_args(&server_args, bundle)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let options = InferenceOptions {
+        model: args.model.clone(),
+        ..InferenceOptions::new(args.fps)
+    };
+    println!(
+        "Rendering {} input(s) on {} server(s)...",
+        inputs.len(),
+        servers.len()
+    );
+    let matrix = run_matrix(servers, reference, inputs, options).await;
+    print!("{}", matrix.to_csv());
+    matrix
+        .write(path)
+        .context("Failed to write server matrix")?;
+    println!("Server matrix written to {}", path.display());
+    anyhow::ensure!(
+        matrix.failed() == 0,
+        "{} of {} matrix cells failed",
+        matrix.failed(),
+        matrix.cells.len()
+    );
+    Ok(())
+}
+
+/// Renders every job in a batch manifest, then prints the batch report.
+pub async fn batch(
+    args: &Args,
+    manifest: &Path,
+    bundle: Option<&SharedBundle>,
+    events: &EventStream,
+) -> Result<()> {
+    let mut jobs = Manifest::load(manifest)?;
+    match &args.output_template {
+        Some(template) => {
+            let base = manifest.parent().unwrap_or(Path::new(""));
+            template.apply(
+                &mut jobs,
+                base,
+                &TemplateContext::new(args.fps, args.resolution),
+            )?;
+        }
+        None => jobs.check_outputs()?,
+    }
+    if args.dry_run {
+        return check_jobs(&jobs, &format!("manifest {}", manifest.display()));
+    }
+    let checkpoint = Checkpoint::path_for(manifest);
+    render_jobs(args, jobs, &checkpoint, bundle, events).await
+}
+
+/// Renders `--reference` against each audio file listed in `list`.
+///
+/// Every listed file is checked first; missing ones are listed and fail the
+/// run before rendering.
+pub async fn input_list(
+    args: &Args,
+    list: &Path,
+    bundle: Option<&SharedBundle>,
+    events: &EventStream,
+) -> Result<()> {
+    let reference = crate::stages::required_path(&args.reference, "--reference")?;
+    let template = args
+        .output_template
+        .as_ref()
+        .context("--input-list needs --output-template to name the outputs")?;
+    let mut jobs = list_jobs(list, reference)?;
+    let missing = missing_audio(&jobs);
+    for path in &missing {
+        println!("Missing: {}", path.display());
+    }
+    anyhow::ensure!(
+        missing.is_empty(),
+        "{} file(s) listed in {} do not exist",
+        missing.len(),
+        list.display()
+    );
+    anyhow::ensure!(
+        !jobs.jobs.is_empty(),
+        "No audio files listed in {}",
+        list.display()
+    );
+    let base = list.parent().unwrap_or(Path::new(""));
+    template.apply(
+        &mut jobs,
+        base,
+        &TemplateContext::new(args.fps, args.resolution),
+    )?;
+    if args.dry_run {
+        return check_jobs(&jobs, &format!("input list {}", list.display()));
+    }
+    let checkpoint = Checkpoint::path_for(list);
+    render_jobs(args, jobs, &checkpoint, bundle, events).await
+}
+
+/// Renders each same-stem reference/audio pair in `dir` as a batch.
+///
+/// Files without a partner are listed and fail the run before rendering.
+pub async fn pair_dir(
+    args: &Args,
+    dir: &Path,
+    bundle: Option<&SharedBundle>,
+    events: &EventStream,
+) -> Result<()> {
+    let pairing = pair_directory(dir)?;
+    for path in &pairing.unmatched {
+        println!("Unmatched: {} has no same-stem partner", path.display());
+    }
+    anyhow::ensure!(
+        pairing.unmatched.is_empty(),
+        "{} file(s) in {} have no partner",
+        pairing.unmatched.len(),
+        dir.display()
+    );
+    anyhow::ensure!(
+        !pairing.manifest.jobs.is_empty(),
+        "No reference/audio pairs found in {}",
+        dir.display()
+    );
+    if args.dry_run {
+        return check_jobs(
+            &pairing.manifest,
+            &format!("pair directory {}", dir.display()),
+        );
+    }
+    let checkpoint = Checkpoint::path_for(&dir.join("pairs.json"));
+    render_jobs(args, pairing.manifest, &checkpoint, bundle, events).await
+}
+
+/// Renders `--reference` once per `--audio-track`, one output per language.
+///
+/// With `--tag-language`, each output's audio stream carries its language.
+pub async fn audio_tracks(
+    args: &Args,
+    bundle: Option<&SharedBundle>,
+    events: &EventStream,
+) -> Result<()> {
+    let reference = crate::stages::required_path(&args.reference, "--reference")?;
+    let output = crate::stages::required_path(&args.output, "--output")?;
+    let mut jobs = track_jobs(reference, output, &args.audio_track)?;
+    if !args.tag_language {
+        jobs.jobs.iter_mut().for_each(|job| job.language = None);
+    }
+    if args.dry_run {
+        return check_jobs(&jobs, &format!("{} audio tracks", jobs.jobs.len()));
+    }
+    let checkpoint = Checkpoint::path_for(&output.with_extension("tracks.json"));
+    render_jobs(args, jobs, &checkpoint, bundle, events).await
+}
+
+/// Renders `jobs` through the normal pipeline and reports per-job outcomes.
+///
+/// A job whose `--post-hook` failed still counts as rendered, so it isn't
+/// rendered again; the batch then ends with [`HookFailed`].
+async fn render_jobs(
+    args: &Args,
+    jobs: Manifest,
+    checkpoint: &Path,
+    bundle: Option<&SharedBundle>,
+    events: &EventStream,
+) -> Result<()> {
+    let hook_failures = Cell::new(0);
+    let report = run_jobs(
+        jobs,
+        checkpoint,
+        args.resume_batch,
+        args.batch_retries,
+        |job| {
+            let job_args = job.args(args);
+            let hook_failures = &hook_failures;
+            async move {
+                crate::run(&job_args, bundle, events).await.or_else(|e| {
+                    let failed = e.downcast::<HookFailed>()?;
+                    hook_failures.set(hook_failures.get() + failed.failures);
+                    anyhow::Ok(())
+                })
+            }
+        },
+    )
+    .await?;
+    println!("{report}");
+    println!("{}", report.to_json());
+    anyhow::ensure!(
+        !report.has_failures(),
+        "{} of {} batch jobs failed",
+        report.failed(),
+        report.jobs.len()
+    );
+    match hook_failures.get() {
+        0 => Ok(()),
+        failures => Err(HookFailed { failures }.into()),
+    }
+}
+
+/// Validates each job's inputs without rendering anything.
+fn check_jobs(jobs: &Manifest, source: &str) -> Result<()> {
+    for (i, job) in jobs.jobs.iter().enumerate() {
+        validate_inputs(&job.reference, &job.audio, &job.output)
+            .with_context(|| format!("Job {} is invalid", i + 1))?;
+    }
+    println!("Dry run: {source} is valid ({} jobs)", jobs.jobs.len());
+    Ok(())
+}
+
+/// Submits the render to the server's queue (`--queue`) and prints its ID.
+pub async fn queue_job(inference: &Inference<'_>, audio: &AudioData) -> Result<()> {
+    let job_id = inference
+        .client
+        .submit_job(inference.reference, audio, inference.options)
+        .await
+        .context("Job submission failed")?;
+    println!("Queued job: {job_id}");
+    println!("Fetch the result with --fetch {job_id}");
+    Ok(())
+}
+
+/// Retrieves a queued job and assembles its frames into the output video.
+pub async fn fetch_queued_job(
+    args: &Args,
+    job_id: &str,
+    bundle: Option<&SharedBundle>,
+) -> Result<()> {
+    let (audio, output) = job_output_paths(args)?;
+
+    let client = MuseTalkClient::from_args(args, bundle)?;
+    let response = match client.fetch_job(job_id).await.context("Job fetch failed")? {
+        JobState::Pending(status) => {
+            println!("Job {job_id} is not finished yet ({status}); try again later");
+            return Ok(());
+        }
+        JobState::Complete(response) => response,
+    };
+    if let Some(video) = &response.video_out {
+        write_server_video(video, &[output]).context("Failed to write server-assembled video")?;
+        println!("Output video created: {}", output.display());
+        return Ok(());
+    }
+
+    println!(
+        "Received {} frames for job {job_id}, assembling video...",
+        response.total_frames
+    );
+    assemble_job_frames(args, response.frames, audio, output, bundle).await
+}
+
+/// Re-downloads a finished job's stored frames page by page and assembles
+/// them, without rerunning inference.
+pub async fn fetch_job_frames(
+    args: &Args,
+    job_id: &str,
+    bundle: Option<&SharedBundle>,
+) -> Result<()> {
+    let (audio, output) = job_output_paths(args)?;
+
+    let client = MuseTalkClient::from_args(args, bundle)?;
+    let frames = client
+        .fetch_job_frames(job_id, DEFAULT_FRAME_PAGE_SIZE)
+        .await
+        .context("Frame fetch failed")?;
+    println!(
+        "Downloaded {} stored frames for job {job_id}, assembling video...",
+        frames.len()
+    );
+    assemble_job_frames(args, frames, audio, output, bundle).await
+}
+
+/// Validates the `--audio` and `--output` a fetched job is assembled with.
+fn job_output_paths(args: &Args) -> Result<(&Path, &Path)> {
+    let audio = crate::stages::required_path(&args.audio, "--audio")?;
+    let output = crate::stages::required_path(&args.output, "--output")?;
+    validate_audio_path(audio).context("Input validation failed")?;
+    validate_output_path(output).context("Input validation failed")?;
+    check_ffmpeg(&args.ffmpeg).context("FFmpeg check failed")?;
+    Ok((audio, output))
+}
+
+/// Assembles a job's frames with `audio` into `output`.
+async fn assemble_job_frames(
+    args: &Args,
+    frames: Vec<Frame>,
+    audio: &Path,
+    output: &Path,
+    bundle: Option<&SharedBundle>,
+) -> Result<()> {
+    let frames: Vec<String> = frames.into_iter().map(|f| f.data).collect();
+    let frames = crate::stages::upscale_frames(args, frames, bundle).await?;
+    let audio_secs = load_audio_with(audio, &crate::stages::audio_options(args))
+        .context("Failed to load audio")?
+        .duration_secs;
+    VideoAssembler::from_args(args, args.fps, audio_secs, bundle)?
+        .assemble_from_frames(&frames, audio, output)
+        .context("Failed to assemble video")?;
+    println!("Output video created: {}", output.display());
+    Ok(())
+}
+
+/// Checks the full server path with a tiny synthetic request.
+pub async fn smoke_test(args: &Args, bundle: Option<&SharedBundle>) -> Result<()> {
+    let client = MuseTalkClient::from_args(args, bundle)?;
+    let options = InferenceOptions {
+        model: args.model.clone(),
+        ..InferenceOptions::new(args.fps)
+    };
+    let report = smoke::smoke_test(&client, &options)
+        .await
+        .context("Smoke test failed")?;
+    println!("{report}");
+    Ok(())
+}
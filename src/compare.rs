@@ -0,0 +1,172 @@
+//! Regression comparison of a rendered video against a golden file.
+//!
+//! Both videos are decoded by FFmpeg into small RGB frames and compared by
+//! mean absolute pixel difference, normalized to `0.0` (identical) through
+//! `1.0` (maximally different).
+
+use crate::error::{CliError, Result, decode_base64};
+use crate::ffmpeg::FfmpegConfig;
+use std::path::Path;
+
+/// Default maximum mean difference accepted by `--compare-threshold`.
+pub const DEFAULT_COMPARE_THRESHOLD: f64 = 0.02;
+
+/// Side length frames are scaled to before comparing.
+const COMPARE_SIZE: usize = 64;
+
+/// Bytes in one decoded RGB comparison frame.
+const FRAME_BYTES: usize = COMPARE_SIZE * COMPARE_SIZE * 3;
+
+/// Builds FFmpeg arguments that decode `path` to raw scaled RGB on stdout.
+fn decode_args(path: &Path) -> Vec<String> {
+    let scale = format!("scale={COMPARE_SIZE}:{COMPARE_SIZE}");
+    let mut args: Vec<String> = ["-v", "error", "-i"].map(String::from).to_vec();
+    args.push(path.to_string_lossy().into_owned());
+    args.extend(["-vf", &scale, "-f", "rawvideo", "-pix_fmt", "rgb24", "-"].map(String::from));
+    args
+}
+
+/// Decodes every frame of a video into comparison-sized RGB buffers.
+pub fn decode_frames(ffmpeg: &FfmpegConfig, path: &Path) -> Result<Vec<Vec<u8>>> {
+    let output = ffmpeg
+        .ffmpeg()
+        .args(decode_args(path))
+        .output()
+        .map_err(|e| CliError::Video(format!("Failed to run ffmpeg: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CliError::Video(format!(
+            "Failed to decode {}: {stderr}",
+            path.display()
+        )));
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(FRAME_BYTES)
+        .map(<[u8]>::to_vec)
+        .collect())
+}
+
+/// Decodes base64 PNG frames into comparison-sized RGB buffers.
+pub fn decode_png_frames(frames: &[String]) -> Result<Vec<Vec<u8>>> {
+    let size = COMPARE_SIZE as u32;
+    frames
+        .iter()
+        .map(|frame| {
+            let png = decode_base64(frame, || "comparison frame".to_string())?;
+            let img = image::load_from_memory(&png)
+                .map_err(|e| CliError::ImageLoad(format!("Invalid frame image: {e}")))?;
+            Ok(img
+                .resize_exact(size, size, image::imageops::FilterType::Triangle)
+                .to_rgb8()
+                .into_raw())
+        })
+        .collect()
+}
+
+/// Mean absolute difference between two equally sized frames, in `0.0..=1.0`.
+pub fn frame_difference(a: &[u8], b: &[u8]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    let total: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| u64::from(x.abs_diff(*y)))
+        .sum();
+    total as f64 / (a.len() as f64 * 255.0)
+}
+
+/// Mean per-frame difference between two frame sequences.
+///
+/// Frames present in only one sequence count as maximally different, so a
+/// truncated render fails the comparison.
+pub fn mean_difference(a: &[Vec<u8>], b: &[Vec<u8>]) -> f64 {
+    let count = a.len().max(b.len());
+    if count == 0 {
+        return 0.0;
+    }
+    let matched: f64 = a.iter().zip(b).map(|(x, y)| frame_difference(x, y)).sum();
+    let missing = (count - a.len().min(b.len())) as f64;
+    (matched + missing) / count as f64
+}
+
+/// Compares `output` against `golden`, returning the mean difference.
+pub fn compare_videos(ffmpeg: &FfmpegConfig, output: &Path, golden: &Path) -> Result<f64> {
+    let rendered = decode_frames(ffmpeg, output)?;
+    let expected = decode_frames(ffmpeg, golden)?;
+    tracing::debug!(
+        "Comparing {} rendered frames to {} golden frames",
+        rendered.len(),
+        expected.len()
+    );
+    Ok(mean_difference(&rendered, &expected))
+}
+
+/// Fails when `difference` exceeds `threshold`.
+pub fn check_difference(difference: f64, threshold: f64) -> Result<()> {
+    if difference > threshold {
+        return Err(CliError::CompareMismatch(format!(
+            "mean difference {difference:.4} exceeds threshold {threshold:.4}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_frames(count: usize, offset: u8) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|f| {
+                (0..FRAME_BYTES)
+                    .map(|i| ((i + f) % 200) as u8 + offset)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_frames_have_zero_difference() {
+        let frames = gradient_frames(5, 0);
+        let difference = mean_difference(&frames, &frames.clone());
+        assert!(difference.abs() < 1e-9);
+        assert!(check_difference(difference, DEFAULT_COMPARE_THRESHOLD).is_ok());
+    }
+
+    #[test]
+    fn test_shifted_frames_exceed_threshold() {
+        let difference = mean_difference(&gradient_frames(5, 0), &gradient_frames(5, 51));
+        assert!((difference - 0.2).abs() < 1e-9);
+        assert!(matches!(
+            check_difference(difference, DEFAULT_COMPARE_THRESHOLD),
+            Err(CliError::CompareMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_frames_count_as_different() {
+        let frames = gradient_frames(4, 0);
+        let difference = mean_difference(&frames[..2], &frames);
+        assert!((difference - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_png_frames_decode_to_compare_size() {
+        let frames = decode_png_frames(&[crate::test_support::tiny_png_base64()]).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), FRAME_BYTES);
+        assert!(decode_png_frames(&["not png".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_decode_args() {
+        let args = decode_args(Path::new("golden.mp4"));
+        assert_eq!(args[2..4], ["-i", "golden.mp4"]);
+        assert!(args.contains(&"scale=64:64".to_string()));
+        assert_eq!(args.last().unwrap(), "-");
+    }
+}
@@ -0,0 +1,200 @@
+//! Compatibility checks against a server's advertised capabilities.
+//!
+//! Missing capabilities mean "unknown", so checks pass rather than fail.
+
+use crate::assembler::Container;
+use crate::cli::Args;
+use crate::client::{MuseTalkClient, ServerCapabilities};
+use crate::error::{CliError, Result};
+use crate::loader::AudioData;
+use crate::probe;
+use crate::validation::{
+    ChannelCheck, ReferenceType, check_audio_channels, check_fps, check_reference_codec,
+    validate_model,
+};
+use std::path::Path;
+
+/// Checks the reference, model, and audio against the server's capabilities.
+pub async fn check_server_compatibility(
+    args: &Args,
+    client: &MuseTalkClient,
+    ref_type: ReferenceType,
+    reference: &Path,
+    audio_data: &mut AudioData,
+) -> Result<Option<ServerCapabilities>> {
+    let caps = fetch_capabilities(client).await;
+    if args.audio_url.is_some() && !caps.as_ref().is_some_and(|c| c.supports_audio_url) {
+        // Unlike the checks below, an unknown answer fails: a server that
+        // ignores `audio_url` would render without any audio
+        return Err(CliError::AudioLoad(
+            "server does not advertise audio URL support; pass --audio instead".to_string(),
+        ));
+    }
+    if ref_type == ReferenceType::Video {
+        check_reference_compatibility(args, reference, caps.as_ref())?;
+    }
+    check_fps(args.fps, caps.as_ref().and_then(|c| c.max_fps))?;
+    if let Some(model) = &args.model {
+        validate_model(model, caps.as_ref().map_or(&[][..], |c| &c.models))?;
+    }
+    match check_audio_channels(
+        audio_data.channels,
+        caps.as_ref().is_some_and(|c| c.requires_mono),
+    ) {
+        ChannelCheck::Mono => {}
+        ChannelCheck::Warn(msg) => tracing::warn!("{msg}"),
+        ChannelCheck::Downmix(msg) => {
+            println!("{msg}");
+            *audio_data = audio_data.to_mono()?;
+        }
+    }
+    Ok(caps)
+}
+
+/// Fetches server capabilities, treating any failure as "unknown".
+pub async fn fetch_capabilities(client: &MuseTalkClient) -> Option<ServerCapabilities> {
+    match client.capabilities().await {
+        Ok(caps) => caps,
+        Err(e) => {
+            tracing::debug!("Capabilities unavailable: {e}");
+            None
+        }
+    }
+}
+
+/// Warns (or errors under `--strict`) if the server can't decode the reference codec.
+pub fn check_reference_compatibility(
+    args: &Args,
+    reference: &Path,
+    caps: Option<&ServerCapabilities>,
+) -> Result<()> {
+    let Some(caps) = caps else {
+        return Ok(());
+    };
+    if !probe::ffprobe_available(&args.ffmpeg) {
+        tracing::debug!("ffprobe not available, skipping codec check");
+        return Ok(());
+    }
+    let codec = probe::video_codec(&args.ffmpeg, reference)?;
+    tracing::debug!("Reference codec: {codec}");
+
+    match check_reference_codec(&codec, &caps.supported_formats) {
+        Err(e) if args.strict => Err(e),
+        Err(e) => {
+            tracing::warn!("{e}");
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Picks the format to request for `--server-assemble`, or `None` to
+/// assemble locally.
+///
+/// The server must advertise the output's container; unknown capabilities
+/// fall back to local assembly.
+pub fn server_assembly_format(caps: Option<&ServerCapabilities>, output: &Path) -> Option<String> {
+    let format = Container::for_output(output).extension();
+    if caps.is_some_and(|c| c.assembles.iter().any(|f| f.eq_ignore_ascii_case(format))) {
+        return Some(format.to_string());
+    }
+    tracing::warn!("Server does not advertise {format} assembly; assembling locally");
+    None
+}
+
+/// Fails under `--strict` if the server reported warnings for the render.
+pub fn check_server_warnings(warnings: &[String], strict: bool) -> Result<()> {
+    if strict && !warnings.is_empty() {
+        return Err(CliError::ServerWarning(warnings.join("; ")));
+    }
+    Ok(())
+}
+
+/// Lists server warnings for display after a successful render.
+pub fn format_server_warnings(warnings: &[String]) -> String {
+    warnings
+        .iter()
+        .map(|w| format!("Server warning: {w}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{InferenceOptions, ReferenceInput};
+    use crate::test_support::{MockResponse, MockServer, test_audio, test_image, tiny_png_base64};
+
+    #[tokio::test]
+    async fn test_stereo_downmixed_when_server_requires_mono() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/capabilities" => MockResponse::json(serde_json::json!({"requires_mono": true})),
+            _ => MockResponse::status(404),
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+        let args = Args::try_parse_from_args([
+            "musetalk-cli",
+            "-r",
+            "a.png",
+            "-a",
+            "a.wav",
+            "-o",
+            "o.mp4",
+        ])
+        .unwrap();
+        let mut audio = AudioData {
+            sample_rate: 16000,
+            channels: 2,
+            duration_secs: 0.0,
+            samples: vec![0.5, -0.5, 0.25, 0.25],
+            base64_wav: String::new(),
+        };
+
+        let caps = check_server_compatibility(
+            &args,
+            &client,
+            ReferenceType::Image,
+            Path::new("a.png"),
+            &mut audio,
+        )
+        .await
+        .unwrap();
+
+        assert!(caps.unwrap().requires_mono);
+        assert_eq!(audio.channels, 1);
+        assert_eq!(audio.samples, [0.0, 0.25]);
+    }
+
+    #[tokio::test]
+    async fn test_server_warnings_shown_and_strict_promotes_them() {
+        let server = MockServer::with_infer(|_| {
+            MockResponse::json(serde_json::json!({
+                "status": "success",
+                "total_frames": 1,
+                "frames": [{"index": 0, "data": tiny_png_base64()}],
+                "warnings": ["face partially occluded"],
+            }))
+        })
+        .await;
+        let client = MuseTalkClient::new(server.url());
+        let response = client
+            .infer(
+                ReferenceInput::Image(&test_image()),
+                &test_audio(),
+                &InferenceOptions::new(25),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            format_server_warnings(&response.warnings),
+            "Server warning: face partially occluded\n"
+        );
+        assert!(check_server_warnings(&response.warnings, false).is_ok());
+        assert!(matches!(
+            check_server_warnings(&response.warnings, true),
+            Err(CliError::ServerWarning(_))
+        ));
+        assert!(check_server_warnings(&[], true).is_ok());
+    }
+}
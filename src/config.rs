@@ -0,0 +1,485 @@
+//! Configuration file support.
+//!
+//! Defaults for common options are read from `--config <PATH>`, or else from
+//! `$XDG_CONFIG_HOME/musetalk/config.toml` (`~/.config/...`) when it exists.
+//! Options given on the command line or through the environment win.
+
+use crate::cli::Args;
+use crate::error::{CliError, Result};
+use crate::schema;
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Default path written by `--init-config`.
+///
+/// This is in the working directory, not [`default_config_path`], so trying
+/// out a template never changes what later runs pick up on their own.
+pub const DEFAULT_CONFIG_FILE: &str = "musetalk.toml";
+
+/// Options that can be supplied from a TOML config file.
+///
+/// Every field is optional; command-line flags take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub server: Option<String>,
+    pub model: Option<String>,
+    pub resolution: Option<String>,
+    pub fps: Option<u32>,
+    pub max_frames: Option<u32>,
+    pub min_audio_duration: Option<f64>,
+    pub png_compression: Option<u8>,
+    pub headers: Option<Vec<String>>,
+    pub frame_pattern: Option<String>,
+    pub frame_start: Option<u32>,
+    pub connect_timeout: Option<u64>,
+    pub read_timeout: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub max_request_size: Option<f64>,
+    pub retry_on_empty: Option<bool>,
+    pub frame_safety_factor: Option<f64>,
+    pub strict: Option<bool>,
+}
+
+/// Template documentation for each config key: (key, description, example).
+///
+/// The example is only used for keys without a default value.
+const FIELD_DOCS: &[(&str, &str, &str)] = &[
+    ("server", "MuseTalk server URL", ""),
+    (
+        "model",
+        "Model variant to request (server default when unset)",
+        "\"musetalk-v15\"",
+    ),
+    ("resolution", "Output resolution (WxH)", ""),
+    ("fps", "Frame rate", ""),
+    (
+        "max_frames",
+        "Cap total frames; lowers fps to fit the audio duration",
+        "3000",
+    ),
+    (
+        "min_audio_duration",
+        "Reject audio shorter than this many seconds",
+        "",
+    ),
+    (
+        "png_compression",
+        "PNG compression level (0-9) for re-encoded images",
+        "6",
+    ),
+    (
+        "headers",
+        "Extra HTTP headers sent to the server",
+        "[\"X-Tenant-Id: acme\"]",
+    ),
+    (
+        "frame_pattern",
+        "Frame filename template with one {index} placeholder",
+        "",
+    ),
+    ("frame_start", "Number of the first frame file", ""),
+    (
+        "connect_timeout",
+        "Seconds to wait for a connection to the server",
+        "",
+    ),
+    (
+        "read_timeout",
+        "Seconds to wait for inference to finish once connected",
+        "",
+    ),
+    (
+        "max_retries",
+        "Retries when the server is busy (429 with Retry-After) or returns no frames",
+        "",
+    ),
+    (
+        "retry_on_empty",
+        "Retry responses with no (or far too few) frames",
+        "",
+    ),
+    (
+        "frame_safety_factor",
+        "Reject responses reporting more than this multiple of the expected frames",
+        "",
+    ),
+    (
+        "max_request_size",
+        "Largest inference request to send, in megabytes",
+        "",
+    ),
+    (
+        "strict",
+        "Treat compatibility, audio quality, and server warnings as errors",
+        "",
+    ),
+];
+
+impl Config {
+    /// Builds a config holding the values of the parsed arguments.
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            server: Some(args.server.clone()),
+            model: args.model.clone(),
+            resolution: Some(args.resolution.to_string()),
+            fps: Some(args.fps),
+            max_frames: args.max_frames,
+            min_audio_duration: Some(args.min_audio_duration),
+            png_compression: args.png_compression,
+            headers: Some(
+                args.headers
+                    .iter()
+                    .map(|h| format!("{}: {}", h.name, h.value))
+                    .collect(),
+            ),
+            frame_pattern: Some(args.frame_pattern.to_string()),
+            frame_start: Some(args.frame_start),
+            connect_timeout: Some(args.connect_timeout),
+            read_timeout: Some(args.read_timeout),
+            max_retries: Some(args.max_retries),
+            max_request_size: Some(args.max_request_size),
+            retry_on_empty: Some(args.retry_on_empty),
+            frame_safety_factor: Some(args.frame_safety_factor),
+            strict: Some(args.strict),
+        }
+    }
+
+    /// Parses a config from TOML text.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| CliError::Config(schema::explain(&e.to_string())))
+    }
+
+    /// Sets every option in `args` that this config holds, unless `matches`
+    /// shows it was given on the command line or through the environment.
+    pub fn apply(&self, args: &mut Args, matches: &ArgMatches) -> Result<()> {
+        let given = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        let config = self.clone();
+        fill(&mut args.server, config.server, given("server"));
+        fill(&mut args.model, config.model.map(Some), given("model"));
+        let resolution = config.resolution.map(|r| r.parse()).transpose()?;
+        fill(&mut args.resolution, resolution, given("resolution"));
+        fill(&mut args.fps, config.fps, given("fps"));
+        fill(
+            &mut args.max_frames,
+            config.max_frames.map(Some),
+            given("max_frames"),
+        );
+        fill(
+            &mut args.min_audio_duration,
+            config.min_audio_duration,
+            given("min_audio_duration"),
+        );
+        fill(
+            &mut args.png_compression,
+            config.png_compression.map(Some),
+            given("png_compression"),
+        );
+        let headers = config
+            .headers
+            .map(|headers| headers.iter().map(|h| h.parse()).collect::<Result<_>>())
+            .transpose()?;
+        fill(&mut args.headers, headers, given("headers"));
+        let frame_pattern = config.frame_pattern.map(|p| p.parse()).transpose()?;
+        fill(
+            &mut args.frame_pattern,
+            frame_pattern,
+            given("frame_pattern"),
+        );
+        fill(
+            &mut args.frame_start,
+            config.frame_start,
+            given("frame_start"),
+        );
+        fill(
+            &mut args.connect_timeout,
+            config.connect_timeout,
+            given("connect_timeout"),
+        );
+        fill(
+            &mut args.read_timeout,
+            config.read_timeout,
+            given("read_timeout"),
+        );
+        fill(
+            &mut args.max_retries,
+            config.max_retries,
+            given("max_retries"),
+        );
+        fill(
+            &mut args.max_request_size,
+            config.max_request_size,
+            given("max_request_size"),
+        );
+        fill(
+            &mut args.retry_on_empty,
+            config.retry_on_empty,
+            given("retry_on_empty") || given("no_retry_on_empty"),
+        );
+        fill(
+            &mut args.frame_safety_factor,
+            config.frame_safety_factor,
+            given("frame_safety_factor"),
+        );
+        fill(
+            &mut args.strict,
+            config.strict,
+            given("strict") || given("no_strict"),
+        );
+        Ok(())
+    }
+}
+
+/// Replaces `target` with `value`, if any, unless the user gave it.
+fn fill<T>(target: &mut T, value: Option<T>, given: bool) {
+    if let Some(value) = value
+        && !given
+    {
+        *target = value;
+    }
+}
+
+/// `$XDG_CONFIG_HOME/musetalk/config.toml`, or `~/.config/...` without it.
+pub fn default_config_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("musetalk").join("config.toml"))
+}
+
+/// Loads the config file at `path`.
+///
+/// Returns `None` if the file doesn't exist; a file that can't be read or
+/// parsed is an error naming the path.
+pub fn load_config(path: &Path) -> Result<Option<Config>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(CliError::Config(format!(
+                "Failed to read {}: {e}",
+                path.display()
+            )));
+        }
+    };
+    Config::from_toml(&text).map(Some).map_err(|e| match e {
+        CliError::Config(message) => CliError::Config(format!("{}: {message}", path.display())),
+        e => e,
+    })
+}
+
+/// Applies `--config`, or the default config file if present, to `args`.
+///
+/// A missing default file is ignored, but a missing `--config` is an error.
+pub fn apply_config(args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    let Some(path) = args.config.clone().or_else(default_config_path) else {
+        return Ok(());
+    };
+    match load_config(&path)? {
+        Some(config) => config.apply(args, matches),
+        None if args.config.is_some() => {
+            Err(CliError::Config(format!("{} not found", path.display())))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Returns the config defaults, taken from the CLI's own defaults.
+fn default_config() -> Config {
+    let args = Args::try_parse_from_args(["musetalk-cli", "--init-config"])
+        .expect("default arguments must parse");
+    Config::from_args(&args)
+}
+
+/// Renders a commented TOML template listing every option and its default.
+pub fn config_template() -> String {
+    let defaults = toml::Table::try_from(default_config()).unwrap_or_default();
+
+    let mut out = String::from(
+        "# MuseTalk CLI configuration\n#\n# Command-line flags override values in this file.\n",
+    );
+    for (key, doc, example) in FIELD_DOCS {
+        out.push_str(&format!("\n# {doc}\n"));
+        match defaults.get(*key) {
+            Some(value) => out.push_str(&format!("{key} = {value}\n")),
+            None => out.push_str(&format!("# {key} = {example}\n")),
+        }
+    }
+    out
+}
+
+/// Writes the config template to `path`.
+///
+/// Refuses to replace an existing file unless `overwrite` is set.
+pub fn write_config_template(path: &Path, overwrite: bool) -> Result<()> {
+    if path.exists() && !overwrite {
+        return Err(CliError::Config(format!(
+            "{} already exists (use --overwrite to replace it)",
+            path.display()
+        )));
+    }
+    std::fs::write(path, config_template())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_template_parses_back_into_config() {
+        let parsed = Config::from_toml(&config_template()).unwrap();
+        let defaults = default_config();
+
+        assert_eq!(parsed.server, defaults.server);
+        assert_eq!(parsed.fps, Some(30));
+        assert_eq!(parsed.frame_pattern.as_deref(), Some("frame_{index:5}.png"));
+        assert_eq!(parsed, defaults);
+    }
+
+    #[test]
+    fn test_template_documents_every_field() {
+        // Fields without defaults are absent unless set
+        let config = Config {
+            max_frames: Some(0),
+            model: Some(String::new()),
+            png_compression: Some(0),
+            ..default_config()
+        };
+        let all = toml::Table::try_from(config).unwrap();
+        let documented: Vec<_> = FIELD_DOCS.iter().map(|(k, _, _)| *k).collect();
+        for key in all.keys() {
+            assert!(documented.contains(&key.as_str()), "undocumented: {key}");
+        }
+        assert_eq!(documented.len(), all.len());
+    }
+
+    #[test]
+    fn test_every_key_matches_an_argument() {
+        use clap::CommandFactory;
+
+        // `apply` looks up each key's value source by argument id
+        let config = Config {
+            max_frames: Some(0),
+            model: Some(String::new()),
+            png_compression: Some(0),
+            ..default_config()
+        };
+        let command = Args::command();
+        let ids: Vec<_> = command
+            .get_arguments()
+            .map(|arg| arg.get_id().as_str())
+            .collect();
+        for key in toml::Table::try_from(config).unwrap().keys() {
+            assert!(ids.contains(&key.as_str()), "no argument for key: {key}");
+        }
+    }
+
+    #[test]
+    fn test_misspelled_key_suggests_correction() {
+        let err = Config::from_toml("srever = \"http://gpu:3015\"\nfps = 25\n").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("did you mean `server`?"), "{message}");
+        assert!(message.contains("line 1"), "{message}");
+    }
+
+    #[test]
+    fn test_config_file_merged_under_cli_flags() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "server = \"http://gpu:3015\"\nfps = 25\nresolution = \"256x256\"\nmax_retries = 7\n",
+        )
+        .unwrap();
+
+        let args = Args::try_parse_with_config([
+            "musetalk-cli",
+            "--config",
+            path.to_str().unwrap(),
+            "-r",
+            "avatar.png",
+            "-a",
+            "audio.wav",
+            "-o",
+            "out.mp4",
+            "--fps",
+            "60",
+        ])
+        .unwrap();
+
+        assert_eq!(args.server, "http://gpu:3015");
+        assert_eq!(args.resolution.to_string(), "256x256");
+        assert_eq!(args.max_retries, 7);
+        // The command line wins over the file
+        assert_eq!(args.fps, 60);
+    }
+
+    #[test]
+    fn test_negated_flags_override_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "strict = true\nretry_on_empty = true\n").unwrap();
+        let parse = |extra: &[&str]| {
+            let mut argv = vec!["musetalk-cli", "--config", path.to_str().unwrap()];
+            argv.extend(["-r", "avatar.png", "-a", "audio.wav", "-o", "out.mp4"]);
+            argv.extend(extra);
+            Args::try_parse_with_config(argv).unwrap()
+        };
+
+        let args = parse(&[]);
+        assert!(args.strict && args.retry_on_empty);
+        let args = parse(&["--no-strict", "--no-retry-on-empty"]);
+        assert!(!args.strict && !args.retry_on_empty);
+    }
+
+    #[test]
+    fn test_missing_config_ignored_and_malformed_rejected() {
+        let dir = tempdir().unwrap();
+        assert!(
+            load_config(&dir.path().join("missing.toml"))
+                .unwrap()
+                .is_none()
+        );
+
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "fps = \"fast\"\n").unwrap();
+        let err = load_config(&path).unwrap_err();
+        assert!(
+            matches!(&err, CliError::Config(m) if m.contains("config.toml")),
+            "{err}"
+        );
+
+        std::fs::write(&path, "resolution = \"512\"\n").unwrap();
+        let parsed = Args::try_parse_with_config([
+            "musetalk-cli",
+            "--config",
+            path.to_str().unwrap(),
+            "--init-config",
+        ]);
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_write_template_refuses_overwrite() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_CONFIG_FILE);
+
+        write_config_template(&path, false).unwrap();
+        assert!(matches!(
+            write_config_template(&path, false),
+            Err(CliError::Config(_))
+        ));
+        assert!(write_config_template(&path, true).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+//! Debug bundle collection for support tickets.
+//!
+//! Gathers the (truncated) request, response metadata, FFmpeg invocations,
+//! stage timings, and versions into a single zip archive.
+
+use crate::client::headers::is_sensitive_header;
+use crate::error::{CliError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zip::write::SimpleFileOptions;
+
+/// Strings longer than this are truncated in the captured request.
+const MAX_STRING_LEN: usize = 128;
+
+/// A debug bundle shared between the client, assembler, and entry point.
+pub type SharedBundle = Arc<Mutex<DebugBundle>>;
+
+/// Response metadata captured from the server.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseMeta {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub frame_count: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// A single FFmpeg invocation.
+#[derive(Debug, Clone, Serialize)]
+pub struct FfmpegRun {
+    pub command: Vec<String>,
+    pub stderr: String,
+    pub success: bool,
+}
+
+/// Collected diagnostics for one run.
+#[derive(Debug, Default, Serialize)]
+pub struct DebugBundle {
+    pub request: Option<Value>,
+    pub request_headers: Vec<(String, String)>,
+    pub response: Option<ResponseMeta>,
+    pub ffmpeg: Vec<FfmpegRun>,
+    pub timings: Vec<(String, f64)>,
+    pub error: Option<String>,
+}
+
+impl DebugBundle {
+    /// Creates an empty bundle wrapped for sharing.
+    pub fn shared() -> SharedBundle {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    /// Records the request payload with long strings truncated.
+    pub fn record_request<T: Serialize>(&mut self, request: &T, headers: &[(String, String)]) {
+        self.request = serde_json::to_value(request).ok().map(truncate_strings);
+        self.request_headers = redact_headers(headers);
+    }
+
+    /// Records the server response metadata.
+    pub fn record_response(&mut self, mut meta: ResponseMeta) {
+        meta.headers = redact_headers(&meta.headers);
+        self.response = Some(meta);
+    }
+
+    /// Records an FFmpeg invocation and its stderr.
+    pub fn record_ffmpeg(&mut self, command: Vec<String>, stderr: String, success: bool) {
+        self.ffmpeg.push(FfmpegRun {
+            command,
+            stderr,
+            success,
+        });
+    }
+
+    /// Records how long a named stage took.
+    pub fn record_timing(&mut self, stage: &str, elapsed: Duration) {
+        self.timings
+            .push((stage.to_string(), elapsed.as_secs_f64()));
+    }
+
+    /// Writes the bundle as a zip archive.
+    pub fn write(&self, path: &Path, ffmpeg_version: Option<&str>) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+
+        let versions = serde_json::json!({
+            "musetalk-cli": env!("CARGO_PKG_VERSION"),
+            "ffmpeg": ffmpeg_version,
+        });
+        let request = serde_json::json!({
+            "headers": self.request_headers,
+            "body": self.request,
+        });
+
+        let entries = [
+            ("request.json", to_pretty(&request)),
+            ("response.json", to_pretty(&self.response)),
+            ("ffmpeg.json", to_pretty(&self.ffmpeg)),
+            ("timings.json", to_pretty(&self.timings)),
+            ("versions.json", to_pretty(&versions)),
+            ("error.txt", self.error.clone().unwrap_or_default()),
+        ];
+
+        for (name, contents) in entries {
+            zip.start_file(name, options)
+                .map_err(|e| CliError::DebugBundle(e.to_string()))?;
+            zip.write_all(contents.as_bytes())?;
+        }
+
+        zip.finish()
+            .map_err(|e| CliError::DebugBundle(e.to_string()))?;
+        Ok(())
+    }
+}
+
+fn to_pretty<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn redact_headers(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive_header(name) {
+                "<redacted>".to_string()
+            } else {
+                value.clone()
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+fn truncate_strings(value: Value) -> Value {
+    match value {
+        Value::String(s) if s.len() > MAX_STRING_LEN => {
+            let prefix: String = s.chars().take(MAX_STRING_LEN).collect();
+            Value::String(format!("{prefix}...({} chars)", s.len()))
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(truncate_strings).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, truncate_strings(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bundle_contains_expected_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+
+        let mut bundle = DebugBundle::default();
+        bundle.record_request(
+            &serde_json::json!({"audio": "A".repeat(1000), "fps": 30}),
+            &[
+                ("Authorization".to_string(), "Bearer secret".to_string()),
+                ("X-Trace-Id".to_string(), "abc".to_string()),
+            ],
+        );
+        bundle.record_response(ResponseMeta {
+            status: 200,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            frame_count: Some(12),
+            warnings: Vec::new(),
+        });
+        bundle.record_ffmpeg(vec!["ffmpeg".into(), "-y".into()], "ok".into(), true);
+        bundle.record_timing("inference", Duration::from_millis(1500));
+        bundle.write(&path, Some("ffmpeg version 6.0")).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut names: Vec<_> = archive
+            .file_names()
+            .map(|n| n.unwrap().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "error.txt",
+                "ffmpeg.json",
+                "request.json",
+                "response.json",
+                "timings.json",
+                "versions.json"
+            ]
+        );
+
+        let mut request = String::new();
+        archive
+            .by_name("request.json")
+            .unwrap()
+            .read_to_string(&mut request)
+            .unwrap();
+        assert!(!request.contains("Bearer secret"));
+        assert!(request.contains("<redacted>"));
+        assert!(request.contains("(1000 chars)"));
+        assert!(request.contains("abc"));
+    }
+
+    #[test]
+    fn test_truncate_strings_leaves_short_values() {
+        let value = serde_json::json!({"fps": 30, "image": "short"});
+        assert_eq!(truncate_strings(value.clone()), value);
+    }
+}
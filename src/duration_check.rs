@@ -0,0 +1,141 @@
+//! Post-render duration assertion (`--assert-duration`).
+//!
+//! Automated QA can pin the expected output length, e.g. `12.5+-0.2`, so a
+//! truncated or overrunning render fails the run instead of slipping through.
+//! The output is measured with ffprobe.
+
+use crate::error::{CliError, Result};
+use crate::ffmpeg::FfmpegConfig;
+use crate::probe;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Tolerance used when none is given, in seconds.
+pub const DEFAULT_TOLERANCE_SECS: f64 = 0.1;
+
+/// Separators accepted between the duration and its tolerance.
+const SEPARATORS: &[&str] = &["\u{b1}", "+/-", "+-"];
+
+/// An expected duration and how far the output may stray from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DurationAssertion {
+    pub expected_secs: f64,
+    pub tolerance_secs: f64,
+}
+
+impl FromStr for DurationAssertion {
+    type Err = CliError;
+
+    /// Parses `SECONDS`, `SECONDS+-TOL`, `SECONDS+/-TOL`, or `SECONDS` and
+    /// `TOL` joined by a plus-minus sign.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            CliError::OutputDuration(format!(
+                "invalid assertion '{s}': expected SECONDS or SECONDS+-TOLERANCE"
+            ))
+        };
+        let (expected, tolerance) = SEPARATORS
+            .iter()
+            .find_map(|sep| s.split_once(sep))
+            .map_or((s, None), |(e, t)| (e, Some(t)));
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+        };
+        Ok(Self {
+            expected_secs: parse(expected).ok_or_else(invalid)?,
+            tolerance_secs: match tolerance {
+                Some(t) => parse(t).ok_or_else(invalid)?,
+                None => DEFAULT_TOLERANCE_SECS,
+            },
+        })
+    }
+}
+
+impl fmt::Display for DurationAssertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.2}s +/- {:.2}s",
+            self.expected_secs, self.tolerance_secs
+        )
+    }
+}
+
+impl DurationAssertion {
+    /// Fails if `actual_secs` is outside the tolerance.
+    pub fn check(&self, actual_secs: f64) -> Result<()> {
+        if (actual_secs - self.expected_secs).abs() <= self.tolerance_secs {
+            return Ok(());
+        }
+        Err(CliError::OutputDuration(format!(
+            "output is {actual_secs:.2}s, expected {self}"
+        )))
+    }
+
+    /// Probes `output` and checks its duration, returning the measured length.
+    pub fn verify(&self, ffmpeg: &FfmpegConfig, output: &Path) -> Result<f64> {
+        let actual = probe::media_duration(ffmpeg, output)?;
+        self.check(actual).map_err(|e| match e {
+            CliError::OutputDuration(msg) => {
+                CliError::OutputDuration(format!("{}: {msg}", output.display()))
+            }
+            other => other,
+        })?;
+        Ok(actual)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assertion() {
+        let parsed = |s: &str| s.parse::<DurationAssertion>().unwrap();
+        assert_eq!(
+            parsed("12.5+-0.2"),
+            DurationAssertion {
+                expected_secs: 12.5,
+                tolerance_secs: 0.2
+            }
+        );
+        assert_eq!(parsed("12.5\u{b1}0.2"), parsed("12.5 +/- 0.2"));
+        assert_eq!(parsed("3").tolerance_secs, DEFAULT_TOLERANCE_SECS);
+        assert!("abc".parse::<DurationAssertion>().is_err());
+        assert!("5+-x".parse::<DurationAssertion>().is_err());
+        assert!("-5".parse::<DurationAssertion>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_probed_mismatch_fails() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let ffmpeg = dir.path().join("ffmpeg");
+        let ffprobe = dir.path().join("ffprobe");
+        std::fs::write(&ffmpeg, "#!/bin/sh\n").unwrap();
+        std::fs::write(&ffprobe, "#!/bin/sh\necho 4.200000\n").unwrap();
+        for tool in [&ffmpeg, &ffprobe] {
+            std::fs::set_permissions(tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let config = FfmpegConfig::from_path(&ffmpeg).unwrap();
+        let output = Path::new("out.mp4");
+
+        let ok: DurationAssertion = "4+-0.5".parse().unwrap();
+        assert_eq!(ok.verify(&config, output).unwrap(), 4.2);
+
+        let strict: DurationAssertion = "5+-0.5".parse().unwrap();
+        let err = strict.verify(&config, output).unwrap_err();
+        assert!(matches!(err, CliError::OutputDuration(_)));
+        assert!(
+            err.to_string()
+                .contains("out.mp4: output is 4.20s, expected 5.00s"),
+            "{err}"
+        );
+    }
+}
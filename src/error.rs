@@ -1,5 +1,6 @@
 //! Error types for the MuseTalk CLI.
 
+use base64::Engine;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -15,21 +16,73 @@ pub enum CliError {
     AudioNotFound(PathBuf),
 
     /// Unsupported reference format.
-    #[error("Unsupported reference format: {0}. Supported formats: PNG, JPEG, MP4")]
+    #[error("Unsupported reference format: {0}. Supported formats: PNG, JPEG, WebP, BMP, MP4")]
     UnsupportedReferenceFormat(String),
 
     /// Unsupported audio format.
     #[error("Unsupported audio format: {0}. Supported formats: WAV, MP3, FLAC")]
     UnsupportedAudioFormat(String),
 
+    /// Frame data that does not match its server-provided checksum.
+    #[error("Frame integrity check failed: {0}")]
+    FrameIntegrity(String),
+
+    /// Malformed `--audio-url`.
+    #[error("Invalid audio URL: {0}")]
+    InvalidAudioUrl(String),
+
+    /// Reference codec not supported by the server.
+    #[error("Unsupported reference codec: {0}")]
+    UnsupportedCodec(String),
+
+    /// Audio sample rate outside the rates MuseTalk handles well.
+    #[error("Unusual audio sample rate: {0}")]
+    UnusualSampleRate(String),
+
+    /// Frame rate above what the server can render.
+    #[error("Unsupported frame rate: {0}")]
+    UnsupportedFps(String),
+
+    /// `--resolution` that isn't `WxH` with positive dimensions.
+    #[error("Invalid resolution: {0}")]
+    InvalidResolution(String),
+
+    /// Model not offered by the server.
+    #[error("Unknown model: {0}")]
+    UnknownModel(String),
+
+    /// Server warnings promoted to an error by `--strict`.
+    #[error("Server reported warnings: {0}")]
+    ServerWarning(String),
+
+    /// Face center that can't be parsed or lies outside the reference.
+    #[error("Face selection error: {0}")]
+    FaceSelection(String),
+
     /// Invalid output path.
     #[error("Invalid output path: {0}")]
-    InvalidOutputPath(PathBuf),
+    InvalidOutputPath(String),
 
     /// Server connection error.
     #[error("Failed to connect to server: {0}")]
     ServerConnection(String),
 
+    /// Response that broke off before all of its frames arrived.
+    #[error("Response ended early: {0}")]
+    ResponseTruncated(String),
+
+    /// Request payload larger than the configured limit.
+    #[error("Request payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    /// Malformed custom HTTP header.
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    /// Malformed frame filename pattern.
+    #[error("Invalid frame pattern {0}")]
+    InvalidFramePattern(String),
+
     /// Image loading/processing error.
     #[error("Image loading error: {0}")]
     ImageLoad(String),
@@ -42,10 +95,50 @@ pub enum CliError {
     #[error("Audio loading error: {0}")]
     AudioLoad(String),
 
+    /// Media probing error.
+    #[error("Probe error: {0}")]
+    Probe(String),
+
     /// Video encoding error.
     #[error("Video encoding error: {0}")]
     Video(String),
 
+    /// Rendered video differs from the golden file.
+    #[error("Output does not match golden video: {0}")]
+    CompareMismatch(String),
+
+    /// Batch manifest or checkpoint error.
+    #[error("Batch error: {0}")]
+    Batch(String),
+
+    /// Config file error.
+    #[error("Config error: {0}")]
+    Config(String),
+
+    /// Profile output error.
+    #[error("Profile error: {0}")]
+    Profile(String),
+
+    /// Debug bundle could not be written.
+    #[error("Debug bundle error: {0}")]
+    DebugBundle(String),
+
+    /// Output duration outside `--assert-duration`.
+    #[error("Output duration check failed: {0}")]
+    OutputDuration(String),
+
+    /// `--post-hook` command missing or failed after a successful render.
+    #[error("Post-hook error: {0}")]
+    PostHook(String),
+
+    /// Base64 payload (frame, video, audio) that doesn't decode.
+    #[error("Invalid base64 in {context}: {source}")]
+    Base64Decode {
+        /// What was being decoded, e.g. "frame 12".
+        context: String,
+        source: base64::DecodeError,
+    },
+
     /// General I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -53,3 +146,35 @@ pub enum CliError {
 
 /// Result type alias using CliError.
 pub type Result<T> = std::result::Result<T, CliError>;
+
+/// Decodes standard base64, naming what was decoded on failure.
+///
+/// `context` is only evaluated when decoding fails.
+pub fn decode_base64(data: impl AsRef<[u8]>, context: impl FnOnce() -> String) -> Result<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|source| CliError::Base64Decode {
+            context: context(),
+            source,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_base64_names_context() {
+        let err = decode_base64("not base64!", || "frame 7".to_string()).unwrap_err();
+        assert!(
+            matches!(&err, CliError::Base64Decode { context, .. } if context == "frame 7"),
+            "{err:?}"
+        );
+        assert!(
+            err.to_string().starts_with("Invalid base64 in frame 7: "),
+            "{err}"
+        );
+
+        assert_eq!(decode_base64("aGk=", || unreachable!()).unwrap(), b"hi");
+    }
+}
@@ -0,0 +1,164 @@
+//! Machine-readable lifecycle events for integrations such as GUIs.
+//!
+//! With `--events` (stderr) or `--events-fd <N>`, each event is written as
+//! one JSON object per line, independent of the human-readable logs.
+
+use crate::cli::Args;
+use crate::error::{CliError, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// One step of a render, tagged by `"event"` in the JSON output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Validated {
+        reference: PathBuf,
+        audio: PathBuf,
+    },
+    AudioLoaded {
+        duration_secs: f32,
+        sample_rate: u32,
+        channels: u16,
+    },
+    /// Emitted only when processing left samples at full scale.
+    AudioClipped {
+        samples: usize,
+    },
+    ServerConnected {
+        server: String,
+        version: Option<String>,
+    },
+    InferenceStarted,
+    FrameReceived {
+        count: usize,
+    },
+    Encoding {
+        frames: usize,
+    },
+    /// The output was written, but `--post-hook` failed on it.
+    PostHookFailed {
+        output: PathBuf,
+        message: String,
+    },
+    Done {
+        outputs: Vec<PathBuf>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+type Writer = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Destination for [`Event`]s; a disabled stream drops them.
+#[derive(Clone, Default)]
+pub struct EventStream {
+    writer: Option<Writer>,
+}
+
+impl EventStream {
+    /// Writes events as JSON lines to `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Some(Arc::new(Mutex::new(Box::new(writer)))),
+        }
+    }
+
+    /// Opens the stream selected by `--events` or `--events-fd`, if any.
+    pub fn from_args(args: &Args) -> Result<Self> {
+        if let Some(fd) = args.events_fd {
+            return Ok(Self::new(open_fd(fd)?));
+        }
+        if args.events {
+            return Ok(Self::new(std::io::stderr()));
+        }
+        Ok(Self::default())
+    }
+
+    /// Returns true if events are being written anywhere.
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Writes one event; failures are logged rather than aborting the render.
+    pub fn emit(&self, event: Event) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let mut line = match serde_json::to_vec(&event) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("Failed to encode event: {e}");
+                return;
+            }
+        };
+        line.push(b'\n');
+        let mut writer = writer.lock().unwrap();
+        if let Err(e) = writer.write_all(&line).and_then(|()| writer.flush()) {
+            tracing::warn!("Failed to write event: {e}");
+        }
+    }
+}
+
+/// Opens an inherited file descriptor for writing.
+#[cfg(unix)]
+fn open_fd(fd: u32) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(format!("/dev/fd/{fd}"))
+        .map_err(|e| CliError::Config(format!("Cannot write events to fd {fd}: {e}")))
+}
+
+#[cfg(not(unix))]
+fn open_fd(fd: u32) -> Result<std::fs::File> {
+    Err(CliError::Config(format!(
+        "--events-fd {fd} is only supported on Unix; use --events"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shared buffer standing in for the event file descriptor.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_written_as_tagged_json_lines() {
+        let buffer = Buffer::default();
+        let events = EventStream::new(buffer.clone());
+        events.emit(Event::InferenceStarted);
+        events.emit(Event::FrameReceived { count: 12 });
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"event":"inference_started"}"#,
+                r#"{"event":"frame_received","count":12}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disabled_stream_drops_events() {
+        let events = EventStream::default();
+        assert!(!events.is_enabled());
+        events.emit(Event::InferenceStarted);
+    }
+}
@@ -0,0 +1,131 @@
+//! Face position forwarded to the server.
+//!
+//! The server finds the face itself unless told where it is. `--face-center`
+//! pins the center point.
+
+use crate::error::{CliError, Result};
+use crate::loader::ImageData;
+use std::fmt;
+use std::str::FromStr;
+
+/// A `--face-center` point in reference pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaceCenter {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl FaceCenter {
+    /// The point as `[x, y]`, the form the server expects.
+    pub fn to_array(self) -> [u32; 2] {
+        [self.x, self.y]
+    }
+
+    /// Checks that the point lies inside a `width` x `height` reference.
+    pub fn check_within(self, width: u32, height: u32) -> Result<()> {
+        if self.x >= width || self.y >= height {
+            return Err(CliError::FaceSelection(format!(
+                "face center {self} is outside the {width}x{height} reference"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The point in `image`, whose source may have been scaled or padded.
+    ///
+    /// The point is in the source's pixels, so it is checked against those
+    /// and then moved to where that pixel landed.
+    pub fn in_image(self, image: &ImageData) -> Result<[u32; 2]> {
+        let (width, height) = image.source_size;
+        self.check_within(width, height)?;
+        let (x, y) = image.from_source(self.x, self.y);
+        Ok([x, y])
+    }
+}
+
+impl FromStr for FaceCenter {
+    type Err = CliError;
+
+    /// Parses `X,Y` in pixels.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || {
+            CliError::FaceSelection(format!(
+                "invalid face center '{value}' (expected X,Y in pixels)"
+            ))
+        };
+        let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+        let x = x.trim().parse().map_err(|_| invalid())?;
+        let y = y.trim().parse().map_err(|_| invalid())?;
+        Ok(Self { x, y })
+    }
+}
+
+impl fmt::Display for FaceCenter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_face_center_parsed() {
+        let center: FaceCenter = "256, 300".parse().unwrap();
+        assert_eq!(center, FaceCenter { x: 256, y: 300 });
+        assert_eq!(center.to_array(), [256, 300]);
+        assert_eq!(center.to_string(), "256,300");
+        assert!("256".parse::<FaceCenter>().is_err());
+        assert!("a,b".parse::<FaceCenter>().is_err());
+        assert!("-1,5".parse::<FaceCenter>().is_err());
+    }
+
+    #[test]
+    fn test_face_center_outside_reference_rejected() {
+        let center = FaceCenter { x: 256, y: 300 };
+        assert!(center.check_within(512, 512).is_ok());
+
+        let err = center.check_within(256, 512).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("face center 256,300 is outside the 256x512 reference"),
+            "{err}"
+        );
+        assert!(center.check_within(512, 300).is_err());
+    }
+
+    #[test]
+    fn test_face_center_follows_resolution_resize() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("avatar.png");
+        image::RgbImage::new(1000, 500).save(&path).unwrap();
+        let args = crate::Args::try_parse_from_args([
+            "musetalk-cli",
+            "-r",
+            "avatar.png",
+            "-a",
+            "audio.wav",
+            "-o",
+            "out.mp4",
+            "--resolution",
+            "512x512",
+            "--face-center",
+            "900,250",
+        ])
+        .unwrap();
+        let resolution = args.resolution;
+        let options = crate::loader::ImageLoadOptions {
+            resize_to: Some((resolution.width, resolution.height)),
+            ..Default::default()
+        };
+        let image = crate::loader::load_image_with(&path, &options).unwrap();
+        let center = args.face_center.unwrap();
+
+        // 1000x500 fits as 512x256, 128 rows below the top of the frame
+        assert_eq!(center.in_image(&image).unwrap(), [460, 256]);
+        // Checked against the source, not the 512x512 it was fitted to
+        let err = FaceCenter { x: 600, y: 520 }.in_image(&image).unwrap_err();
+        assert!(err.to_string().contains("outside the 1000x500"), "{err}");
+    }
+}
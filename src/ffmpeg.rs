@@ -0,0 +1,122 @@
+//! Locations of the FFmpeg and ffprobe binaries.
+
+use crate::error::{CliError, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// Paths to the FFmpeg tools, defaulting to the binaries on `PATH`.
+///
+/// ffprobe is taken from the same directory as a configured FFmpeg binary
+/// when one exists there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegConfig {
+    ffmpeg: PathBuf,
+    ffprobe: PathBuf,
+}
+
+impl Default for FfmpegConfig {
+    fn default() -> Self {
+        Self {
+            ffmpeg: PathBuf::from("ffmpeg"),
+            ffprobe: PathBuf::from("ffprobe"),
+        }
+    }
+}
+
+impl FfmpegConfig {
+    /// Uses the FFmpeg binary at `path`, checking it is an executable file.
+    ///
+    /// A bare name such as `ffmpeg` is looked up on `PATH` when run.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        if path.components().count() == 1 && !path.is_absolute() {
+            return Ok(Self {
+                ffmpeg: path.to_path_buf(),
+                ..Self::default()
+            });
+        }
+        check_executable(path)?;
+        let sibling = path.with_file_name(format!("ffprobe{}", std::env::consts::EXE_SUFFIX));
+        let ffprobe = if sibling.is_file() {
+            sibling
+        } else {
+            PathBuf::from("ffprobe")
+        };
+        Ok(Self {
+            ffmpeg: path.to_path_buf(),
+            ffprobe,
+        })
+    }
+
+    /// Path of the FFmpeg binary.
+    pub fn ffmpeg_path(&self) -> &Path {
+        &self.ffmpeg
+    }
+
+    /// A command that runs FFmpeg.
+    pub fn ffmpeg(&self) -> Command {
+        Command::new(&self.ffmpeg)
+    }
+
+    /// A command that runs ffprobe.
+    pub fn ffprobe(&self) -> Command {
+        Command::new(&self.ffprobe)
+    }
+}
+
+impl FromStr for FfmpegConfig {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_path(Path::new(s))
+    }
+}
+
+/// Fails unless `path` is a file the current user may execute.
+fn check_executable(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| CliError::Video(format!("FFmpeg not found at {}: {e}", path.display())))?;
+    if !metadata.is_file() {
+        return Err(CliError::Video(format!(
+            "FFmpeg path is not a file: {}",
+            path.display()
+        )));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(CliError::Video(format!(
+                "FFmpeg is not executable: {}",
+                path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bare_name_uses_path_lookup() {
+        let config: FfmpegConfig = "ffmpeg".parse().unwrap();
+        assert_eq!(config, FfmpegConfig::default());
+    }
+
+    #[test]
+    fn test_missing_or_non_executable_path_rejected() {
+        let dir = tempdir().unwrap();
+        assert!(FfmpegConfig::from_path(&dir.path().join("ffmpeg")).is_err());
+        assert!(FfmpegConfig::from_path(dir.path()).is_err());
+
+        #[cfg(unix)]
+        {
+            let path = dir.path().join("ffmpeg");
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            assert!(FfmpegConfig::from_path(&path).is_err());
+        }
+    }
+}
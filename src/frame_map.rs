@@ -0,0 +1,98 @@
+//! Correlation of generated frames with the audio that produced them.
+//!
+//! Written as a JSON sidecar by `--frame-manifest` to help diagnose lip-sync
+//! drift: each inference request (chunk) lists the frame indices it returned
+//! and the audio time range it covered.
+
+use crate::error::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// Frames and audio covered by one inference request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChunkMapping {
+    pub chunk: usize,
+    /// First frame index (inclusive).
+    pub first_frame: usize,
+    /// End frame index (exclusive).
+    pub end_frame: usize,
+    pub audio_start_secs: f64,
+    pub audio_end_secs: f64,
+}
+
+/// Chunk-by-chunk mapping for a whole render.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameManifest {
+    pub fps: u32,
+    pub chunks: Vec<ChunkMapping>,
+}
+
+impl FrameManifest {
+    /// Builds the mapping for consecutive requests, given each request's
+    /// audio length in seconds and the number of frames it returned.
+    ///
+    /// A render sent as one request is a single chunk.
+    pub fn from_chunks(fps: u32, chunks: &[(f64, usize)]) -> Self {
+        let mut frame = 0;
+        let mut secs = 0.0;
+        let chunks = chunks
+            .iter()
+            .enumerate()
+            .map(|(chunk, &(audio_secs, frame_count))| {
+                let mapping = ChunkMapping {
+                    chunk,
+                    first_frame: frame,
+                    end_frame: frame + frame_count,
+                    audio_start_secs: secs,
+                    audio_end_secs: secs + audio_secs,
+                };
+                frame = mapping.end_frame;
+                secs = mapping.audio_end_secs;
+                mapping
+            })
+            .collect();
+        Self { fps, chunks }
+    }
+
+    /// Writes the manifest as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).unwrap_or_default();
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_is_contiguous_and_covers_duration() {
+        let manifest = FrameManifest::from_chunks(25, &[(4.0, 100), (4.0, 101), (1.5, 37)]);
+
+        assert_eq!(manifest.chunks.len(), 3);
+        assert_eq!(manifest.chunks[0].first_frame, 0);
+        assert_eq!(manifest.chunks[0].audio_start_secs, 0.0);
+        for pair in manifest.chunks.windows(2) {
+            assert_eq!(pair[0].end_frame, pair[1].first_frame);
+            assert_eq!(pair[0].audio_end_secs, pair[1].audio_start_secs);
+        }
+        let last = manifest.chunks.last().unwrap();
+        assert_eq!(last.end_frame, 238);
+        assert_eq!(last.audio_end_secs, 9.5);
+    }
+
+    #[test]
+    fn test_manifest_written_as_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frames.json");
+        FrameManifest::from_chunks(30, &[(2.0, 60)])
+            .write(&path)
+            .unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["chunks"][0]["end_frame"], 60);
+        assert_eq!(json["chunks"][0]["audio_end_secs"], 2.0);
+    }
+}
@@ -0,0 +1,170 @@
+//! User commands run on the finished video (`--post-hook`).
+//!
+//! The hook receives the output path as its last argument, with the path,
+//! duration, and size also exported as `MUSETALK_OUTPUT`,
+//! `MUSETALK_DURATION_SECS`, and `MUSETALK_SIZE_BYTES`.
+//!
+//! A failing hook doesn't undo the render: the outputs are kept and
+//! reported, and the process exits with [`HOOK_FAILED_EXIT_CODE`] rather
+//! than the 1 of a failed render.
+
+use crate::error::{CliError, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// Exit status when the outputs were rendered but `--post-hook` failed.
+pub const HOOK_FAILED_EXIT_CODE: i32 = 3;
+
+/// The outputs were rendered, but `--post-hook` failed on `failures` of them.
+#[derive(Debug, thiserror::Error)]
+#[error("Render succeeded, but the post-hook failed for {failures} output(s)")]
+pub struct HookFailed {
+    pub failures: usize,
+}
+
+/// A validated `--post-hook` command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostHook {
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl PostHook {
+    /// Runs the hook for `output`, failing if it exits unsuccessfully.
+    pub fn run(&self, output: &Path, duration_secs: f32) -> Result<()> {
+        let size = std::fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+        let status = Command::new(&self.program)
+            .args(&self.args)
+            .arg(output)
+            .env("MUSETALK_OUTPUT", output)
+            .env("MUSETALK_DURATION_SECS", format!("{duration_secs:.3}"))
+            .env("MUSETALK_SIZE_BYTES", size.to_string())
+            .status()
+            .map_err(|e| {
+                CliError::PostHook(format!("could not run {}: {e}", self.program.display()))
+            })?;
+        if !status.success() {
+            return Err(CliError::PostHook(format!(
+                "{} exited with {status}",
+                self.program.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PostHook {
+    type Err = CliError;
+
+    /// Splits `s` into words the way a POSIX shell would (so quoted paths
+    /// with spaces stay whole) and checks the program can be found.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = shlex::split(s)
+            .ok_or_else(|| CliError::PostHook(format!("unbalanced quotes in {s:?}")))?
+            .into_iter();
+        let program = words
+            .next()
+            .map(PathBuf::from)
+            .ok_or_else(|| CliError::PostHook("empty command".to_string()))?;
+        let program = resolve_program(&program)?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+        })
+    }
+}
+
+/// Finds `program` on `PATH` when it is a bare name, else checks it exists.
+fn resolve_program(program: &Path) -> Result<PathBuf> {
+    if program.components().count() > 1 || program.is_absolute() {
+        return if program.is_file() {
+            Ok(program.to_path_buf())
+        } else {
+            Err(CliError::PostHook(format!(
+                "command not found: {}",
+                program.display()
+            )))
+        };
+    }
+    let mut exe = program.as_os_str().to_owned();
+    exe.push(std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .flat_map(|dir| [dir.join(program), dir.join(&exe)])
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| {
+            CliError::PostHook(format!("command not found on PATH: {}", program.display()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_command_rejected() {
+        assert!("".parse::<PostHook>().is_err());
+        assert!(
+            "definitely-not-a-musetalk-hook"
+                .parse::<PostHook>()
+                .is_err()
+        );
+        assert!("/no/such/hook.sh".parse::<PostHook>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_receives_output_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("hook.sh");
+        let log = dir.path().join("hook.log");
+        std::fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$1 $2 $MUSETALK_SIZE_BYTES $MUSETALK_DURATION_SECS\" > {}\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let output = dir.path().join("out.mp4");
+        std::fs::write(&output, [0u8; 42]).unwrap();
+
+        let hook: PostHook = format!("{} --upload", script.display()).parse().unwrap();
+        hook.run(&output, 1.5).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap().trim(),
+            format!("--upload {} 42 1.500", output.display())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_quoted_words_kept_whole() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("my hooks").join("upload.sh");
+        std::fs::create_dir_all(script.parent().unwrap()).unwrap();
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+
+        let hook: PostHook = format!("'{}' --dest \"shared drive\"", script.display())
+            .parse()
+            .unwrap();
+        assert_eq!(hook.program, script);
+        assert_eq!(hook.args, ["--dest", "shared drive"]);
+        assert!("sh -c 'unterminated".parse::<PostHook>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_hook_reported() {
+        let hook: PostHook = "false".parse().unwrap();
+        let err = hook.run(Path::new("out.mp4"), 1.0).unwrap_err();
+        assert!(matches!(err, CliError::PostHook(_)));
+    }
+}
@@ -0,0 +1,117 @@
+//! Per-frame face data returned by the server, saved for compositing.
+//!
+//! Servers may attach the crop box they used and facial landmarks to each
+//! frame. `--landmarks-out` writes them as a JSON sidecar with one entry per
+//! output frame, so downstream tools can line them up by frame number.
+
+use crate::client::Frame;
+use crate::error::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// Face data for one output frame; fields are `null` when not provided.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameLandmarks {
+    /// Position of the frame in the output video.
+    pub frame: usize,
+    /// Crop box `[x, y, width, height]` in reference pixels.
+    pub bbox: Option<[u32; 4]>,
+    /// Landmark points `[x, y]` in reference pixels.
+    pub landmarks: Option<Vec<[f32; 2]>>,
+}
+
+/// Face data for a whole render.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LandmarkSidecar {
+    pub frames: Vec<FrameLandmarks>,
+}
+
+impl LandmarkSidecar {
+    /// Collects face data from `frames` in output order.
+    ///
+    /// Empty when no frame carries a bounding box or landmarks.
+    pub fn from_frames(frames: &[Frame]) -> Self {
+        if frames
+            .iter()
+            .all(|f| f.bbox.is_none() && f.landmarks.is_none())
+        {
+            return Self::default();
+        }
+        let frames = frames
+            .iter()
+            .enumerate()
+            .map(|(frame, f)| FrameLandmarks {
+                frame,
+                bbox: f.bbox,
+                landmarks: f.landmarks.clone(),
+            })
+            .collect();
+        Self { frames }
+    }
+
+    /// Returns true if the server sent no face data.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Writes the sidecar as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).unwrap_or_default();
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(index: usize, bbox: Option<[u32; 4]>, landmarks: Option<Vec<[f32; 2]>>) -> Frame {
+        Frame {
+            index,
+            data: String::new(),
+            sha256: None,
+            bbox,
+            landmarks,
+        }
+    }
+
+    #[test]
+    fn test_landmarks_serialized_by_frame() {
+        let frames = [
+            frame(
+                0,
+                Some([10, 20, 64, 64]),
+                Some(vec![[12.5, 30.0], [40.0, 31.5]]),
+            ),
+            frame(1, None, None),
+        ];
+        let sidecar = LandmarkSidecar::from_frames(&frames);
+
+        let json = serde_json::to_value(&sidecar).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"frames": [
+                {"frame": 0, "bbox": [10, 20, 64, 64], "landmarks": [[12.5, 30.0], [40.0, 31.5]]},
+                {"frame": 1, "bbox": null, "landmarks": null},
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_frames_without_face_data_give_empty_sidecar() {
+        let sidecar = LandmarkSidecar::from_frames(&[frame(0, None, None)]);
+        assert!(sidecar.is_empty());
+        assert_eq!(serde_json::to_string(&sidecar).unwrap(), r#"{"frames":[]}"#);
+    }
+
+    #[test]
+    fn test_frame_fields_optional_in_response() {
+        let frame: Frame = serde_json::from_str(r#"{"index": 3, "data": ""}"#).unwrap();
+        assert!(frame.bbox.is_none() && frame.landmarks.is_none());
+
+        let frame: Frame =
+            serde_json::from_str(r#"{"index": 3, "data": "", "bbox": [1, 2, 3, 4]}"#).unwrap();
+        assert_eq!(frame.bbox, Some([1, 2, 3, 4]));
+    }
+}
@@ -0,0 +1,319 @@
+//! Last successful render per set of inputs (`--stale-ok`).
+//!
+//! A video reference can't fall back to a static render when the server is
+//! down. With `--stale-ok`, each successful render is copied into a cache keyed
+//! by a hash of the reference, the audio, and the settings that shape the
+//! render; a later run with the same inputs and no server serves that copy
+//! instead of failing.
+
+use crate::assembler::VideoCodec;
+use crate::cli::Args;
+use crate::error::Result;
+use crate::resolution::Resolution;
+use ring::digest::{Context, SHA256};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Settings besides the input files that change what is rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySettings {
+    /// The rate rendered at, after `--snap-fps` and `--max-frames`.
+    pub fps: u32,
+    pub resolution: Resolution,
+    /// The rendered part of the audio, in seconds (`--start-time`/`--end-time`).
+    pub window: Option<(f64, f64)>,
+    /// Milliseconds of silence prepended (`--lead-in`).
+    pub lead_in: Option<u32>,
+    /// Gain applied in dB (`--audio-gain`).
+    pub audio_gain: Option<f32>,
+    /// Audio scaled to a fixed RMS (`--two-pass-audio-analysis`).
+    pub two_pass_audio_analysis: bool,
+    /// Audio converted with `--preprocess-audio`.
+    pub preprocess_audio: bool,
+    /// Rate the audio was resampled to (`--target-sample-rate`).
+    pub target_sample_rate: u32,
+    pub model: Option<String>,
+    pub codec: Option<VideoCodec>,
+    /// `--face-center` as `[x, y]`.
+    pub face_center: Option<[u32; 2]>,
+}
+
+impl KeySettings {
+    /// The settings `args` render with at `fps` over the audio `window`.
+    pub fn from_args(args: &Args, fps: u32, window: Option<(f64, f64)>) -> Self {
+        Self {
+            fps,
+            resolution: args.resolution,
+            window,
+            lead_in: args.lead_in,
+            audio_gain: args.audio_gain,
+            two_pass_audio_analysis: args.two_pass_audio_analysis,
+            preprocess_audio: args.preprocess_audio,
+            target_sample_rate: args.target_sample_rate,
+            model: args.model.clone(),
+            codec: args.codec,
+            face_center: args.face_center.map(|c| c.to_array()),
+        }
+    }
+}
+
+/// Cached renders, one file per input key and container.
+#[derive(Debug, Clone)]
+pub struct LastGoodCache {
+    dir: PathBuf,
+}
+
+impl LastGoodCache {
+    /// A cache stored in `dir` (created on first store).
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `$XDG_CACHE_HOME/musetalk-cli/last-good`, falling back to
+    /// `~/.cache` and then the system temp dir.
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("musetalk-cli")
+            .join("last-good")
+    }
+
+    /// SHA-256 over the reference and audio contents and `settings`.
+    pub fn input_key(reference: &Path, audio: &Path, settings: &KeySettings) -> Result<String> {
+        let mut context = Context::new(&SHA256);
+        for path in [reference, audio] {
+            let bytes = std::fs::read(path)?;
+            context.update(&(bytes.len() as u64).to_le_bytes());
+            context.update(&bytes);
+        }
+        let KeySettings {
+            fps,
+            resolution,
+            window,
+            lead_in,
+            audio_gain,
+            two_pass_audio_analysis,
+            preprocess_audio,
+            target_sample_rate,
+            model,
+            codec,
+            face_center,
+        } = settings;
+        context.update(
+            format!(
+                "fps={fps};resolution={resolution};window={window:?};\
+                 lead_in={lead_in:?};audio_gain={audio_gain:?};\
+                 two_pass={two_pass_audio_analysis};preprocess={preprocess_audio};\
+                 sample_rate={target_sample_rate};model={model:?};codec={codec:?};\
+                 face_center={face_center:?}"
+            )
+            .as_bytes(),
+        );
+        Ok(context
+            .finish()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect())
+    }
+
+    /// Where the render for `key` with `output`'s extension is kept.
+    fn entry(&self, key: &str, output: &Path) -> PathBuf {
+        let ext = output.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+        self.dir.join(format!("{key}.{ext}"))
+    }
+
+    /// Records `output` as the last good render for `key`.
+    ///
+    /// Written to a temporary name and renamed, so an interrupted copy never
+    /// replaces a good entry. `output` must be a regular file; a named pipe
+    /// has nothing left to copy once its reader is done.
+    pub fn store(&self, key: &str, output: &Path) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let entry = self.entry(key, output);
+        let partial = entry.with_extension("partial");
+        std::fs::copy(output, &partial)?;
+        std::fs::rename(&partial, &entry)?;
+        Ok(())
+    }
+
+    /// Copies the last good render for `key` to `output`.
+    ///
+    /// The bytes are streamed into `output` rather than copied as a file, so
+    /// a named pipe output keeps its type and permissions. Returns false when
+    /// nothing is cached for these inputs.
+    pub fn restore(&self, key: &str, output: &Path) -> Result<bool> {
+        let entry = self.entry(key, output);
+        if !entry.is_file() {
+            return Ok(false);
+        }
+        let mut target = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(output)?;
+        std::io::copy(&mut File::open(&entry)?, &mut target)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn settings(fps: u32) -> KeySettings {
+        KeySettings {
+            fps,
+            resolution: Resolution::new(512, 512).unwrap(),
+            window: None,
+            lead_in: None,
+            audio_gain: None,
+            two_pass_audio_analysis: false,
+            preprocess_audio: false,
+            target_sample_rate: 16000,
+            model: None,
+            codec: None,
+            face_center: None,
+        }
+    }
+
+    fn args(extra: &[&str]) -> Args {
+        let base = [
+            "musetalk-cli",
+            "-r",
+            "ref.mp4",
+            "-a",
+            "speech.wav",
+            "-o",
+            "out.mp4",
+        ];
+        Args::try_parse_from_args(base.iter().chain(extra)).unwrap()
+    }
+
+    #[test]
+    fn test_cached_render_served_when_server_down() {
+        let dir = tempdir().unwrap();
+        let reference = dir.path().join("ref.mp4");
+        let audio = dir.path().join("speech.wav");
+        std::fs::write(&reference, b"reference video").unwrap();
+        std::fs::write(&audio, b"speech").unwrap();
+        let cache = LastGoodCache::new(dir.path().join("cache"));
+        let key = LastGoodCache::input_key(&reference, &audio, &settings(25)).unwrap();
+
+        // Nothing cached yet: the caller has to fail as before
+        let rerun = dir.path().join("rerun.mp4");
+        assert!(!cache.restore(&key, &rerun).unwrap());
+
+        // A successful render is remembered...
+        let first = dir.path().join("first.mp4");
+        std::fs::write(&first, b"lip-synced video").unwrap();
+        cache.store(&key, &first).unwrap();
+
+        // ...and served for the same inputs while the server is down
+        assert!(cache.restore(&key, &rerun).unwrap());
+        assert_eq!(std::fs::read(&rerun).unwrap(), b"lip-synced video");
+    }
+
+    /// `settings(25)` with each other setting changed in turn.
+    fn changed_settings() -> Vec<KeySettings> {
+        vec![
+            KeySettings {
+                resolution: Resolution::new(512, 288).unwrap(),
+                ..settings(25)
+            },
+            KeySettings {
+                window: Some((0.5, 2.0)),
+                ..settings(25)
+            },
+            KeySettings {
+                lead_in: Some(100),
+                ..settings(25)
+            },
+            KeySettings {
+                audio_gain: Some(6.0),
+                ..settings(25)
+            },
+            KeySettings {
+                two_pass_audio_analysis: true,
+                ..settings(25)
+            },
+            KeySettings {
+                model: Some("musetalk-v15".to_string()),
+                ..settings(25)
+            },
+            KeySettings {
+                codec: Some(VideoCodec::H265),
+                ..settings(25)
+            },
+            KeySettings {
+                face_center: Some([256, 300]),
+                ..settings(25)
+            },
+        ]
+    }
+
+    #[test]
+    fn test_key_depends_on_inputs_and_settings() {
+        let dir = tempdir().unwrap();
+        let reference = dir.path().join("ref.mp4");
+        let audio = dir.path().join("speech.wav");
+        std::fs::write(&reference, b"reference video").unwrap();
+        std::fs::write(&audio, b"speech").unwrap();
+        let key = LastGoodCache::input_key(&reference, &audio, &settings(25)).unwrap();
+
+        assert_eq!(
+            key,
+            LastGoodCache::input_key(&reference, &audio, &settings(25)).unwrap()
+        );
+        assert_ne!(
+            key,
+            LastGoodCache::input_key(&reference, &audio, &settings(30)).unwrap()
+        );
+        for changed in changed_settings() {
+            assert_ne!(
+                key,
+                LastGoodCache::input_key(&reference, &audio, &changed).unwrap()
+            );
+        }
+        std::fs::write(&audio, b"other speech").unwrap();
+        assert_ne!(
+            key,
+            LastGoodCache::input_key(&reference, &audio, &settings(25)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_max_frames_and_two_pass_analysis_miss_the_cache() {
+        let dir = tempdir().unwrap();
+        let reference = dir.path().join("ref.mp4");
+        let audio = dir.path().join("speech.wav");
+        std::fs::write(&reference, b"reference video").unwrap();
+        std::fs::write(&audio, b"speech").unwrap();
+        let cache = LastGoodCache::new(dir.path().join("cache"));
+        let key = |args: &Args, fps| {
+            let settings = KeySettings::from_args(args, fps, None);
+            LastGoodCache::input_key(&reference, &audio, &settings).unwrap()
+        };
+        let plain = args(&[]);
+        let first = dir.path().join("first.mp4");
+        std::fs::write(&first, b"lip-synced video").unwrap();
+        cache.store(&key(&plain, plain.fps), &first).unwrap();
+
+        let rerun = dir.path().join("rerun.mp4");
+        assert!(cache.restore(&key(&plain, plain.fps), &rerun).unwrap());
+        let two_pass = args(&["--two-pass-audio-analysis"]);
+        assert!(
+            !cache
+                .restore(&key(&two_pass, two_pass.fps), &rerun)
+                .unwrap()
+        );
+        // 40 frames over 2 s renders at 20 fps, not the requested 30
+        let capped = args(&["--max-frames", "40"]);
+        let fps = crate::validation::fps_for_frame_budget(capped.fps, 2.0, 40);
+        assert!(!cache.restore(&key(&capped, fps), &rerun).unwrap());
+    }
+}
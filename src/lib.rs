@@ -4,12 +4,41 @@
 //! avatar videos using the MuseTalk inference server.
 
 pub mod assembler;
+pub mod batch;
+pub mod benchmark;
 pub mod cli;
 pub mod client;
+pub mod compare;
+pub mod compat;
+pub mod config;
+pub mod debug_bundle;
+pub mod duration_check;
 pub mod error;
+pub mod events;
+pub mod face;
+pub mod ffmpeg;
+pub mod frame_map;
+pub mod hook;
+pub mod landmarks;
+pub mod last_good;
 pub mod loader;
+pub mod matrix;
+pub mod preview;
+pub mod probe;
+pub mod profile;
+pub mod progress;
+pub mod resolution;
+pub mod schema;
+pub mod smoke;
+pub mod throttle;
+#[cfg(feature = "tui")]
+pub mod tui;
 pub mod validation;
 
+#[cfg(test)]
+#[allow(dead_code)]
+pub(crate) mod test_support;
+
 pub use cli::Args;
 pub use error::{CliError, Result};
-pub use validation::{ReferenceType, validate_inputs};
+pub use validation::{ReferenceType, fps_for_frame_budget, validate_inputs};
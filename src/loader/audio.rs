@@ -1,9 +1,21 @@
 //! Audio loading and preprocessing.
 
-use crate::error::{CliError, Result};
+use super::compressed::decode_compressed;
+use super::levels;
+use super::loudness::{self, Loudness};
+use super::wav_chunks::canonicalize_wav;
+use super::wav_repair::{WavFormat, repair_wav};
+use crate::error::{CliError, Result, decode_base64};
 use base64::Engine;
 use hound::WavReader;
 use std::path::Path;
+use tempfile::TempPath;
+
+/// Sample rate MuseTalk's audio encoder expects.
+pub const MUSETALK_SAMPLE_RATE: u32 = 16000;
+
+/// Peak level audio is normalized to, leaving a little headroom.
+const NORMALIZED_PEAK: f32 = 0.95;
 
 /// Loaded audio data ready for processing.
 #[derive(Debug, Clone)]
@@ -20,8 +32,200 @@ pub struct AudioData {
     pub base64_wav: String,
 }
 
-/// Loads a WAV audio file from the given path.
+impl AudioData {
+    /// Builds audio from interleaved normalized samples held in memory.
+    ///
+    /// The base64 WAV is encoded as 16-bit PCM.
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Result<Self> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(CliError::AudioLoad(format!(
+                "Invalid audio format: {sample_rate} Hz, {channels} channels"
+            )));
+        }
+        Ok(Self {
+            sample_rate,
+            channels,
+            duration_secs: samples.len() as f32 / channels as f32 / sample_rate as f32,
+            base64_wav: encode_wav_base64(&samples, sample_rate, channels)?,
+            samples,
+        })
+    }
+
+    /// Returns a mono copy, averaging interleaved channels.
+    ///
+    /// The base64 WAV is regenerated as 16-bit PCM.
+    pub fn to_mono(&self) -> Result<AudioData> {
+        if self.channels <= 1 {
+            return Ok(self.clone());
+        }
+        let channels = self.channels as usize;
+        let samples: Vec<f32> = self
+            .samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+
+        Self::from_samples(samples, self.sample_rate, 1)
+    }
+
+    /// Returns a copy resampled to `sample_rate` by linear interpolation.
+    pub fn resample(&self, sample_rate: u32) -> Result<AudioData> {
+        if sample_rate == self.sample_rate || self.samples.is_empty() {
+            return Self::from_samples(self.samples.clone(), sample_rate, self.channels);
+        }
+        let channels = self.channels as usize;
+        let frames = self.samples.len() / channels;
+        let ratio = f64::from(self.sample_rate) / f64::from(sample_rate);
+        let out_frames = (frames as f64 / ratio).round() as usize;
+
+        let mut samples = Vec::with_capacity(out_frames * channels);
+        for i in 0..out_frames {
+            let pos = i as f64 * ratio;
+            let left = (pos as usize).min(frames - 1);
+            let right = (left + 1).min(frames - 1);
+            let t = (pos - left as f64) as f32;
+            for c in 0..channels {
+                let a = self.samples[left * channels + c];
+                let b = self.samples[right * channels + c];
+                samples.push(a + (b - a) * t);
+            }
+        }
+        Self::from_samples(samples, sample_rate, self.channels)
+    }
+
+    /// Returns a mono copy at `target_rate`, downmixing multi-channel audio.
+    ///
+    /// MuseTalk's feature extractor expects 16 kHz mono; audio already in the
+    /// target format is returned unchanged.
+    pub fn resample_to(&self, target_rate: u32) -> Result<AudioData> {
+        if self.channels <= 1 && self.sample_rate == target_rate {
+            return Ok(self.clone());
+        }
+        self.to_mono()?.resample(target_rate)
+    }
+
+    /// Returns a copy scaled so the loudest sample reaches a fixed peak.
+    ///
+    /// Silent audio is returned unchanged.
+    pub fn normalize_peak(&self) -> Result<AudioData> {
+        let peak = self.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        if peak == 0.0 {
+            return Ok(self.clone());
+        }
+        let gain = NORMALIZED_PEAK / peak;
+        let samples = self.samples.iter().map(|s| s * gain).collect();
+        Self::from_samples(samples, self.sample_rate, self.channels)
+    }
+
+    /// Number of samples at or beyond full scale (+/-1.0).
+    pub fn clip_count(&self) -> usize {
+        self.samples.iter().filter(|s| s.abs() >= 1.0).count()
+    }
+
+    /// Returns a copy whose RMS over the whole clip reaches `target_dbfs`.
+    pub fn normalize_rms(&self, target_dbfs: f32) -> Result<AudioData> {
+        levels::normalize_rms(self, target_dbfs)
+    }
+
+    /// Measures integrated loudness (LUFS) and true peak (dBTP).
+    pub fn loudness(&self) -> Loudness {
+        loudness::measure(self)
+    }
+
+    /// Applies MuseTalk's recommended input chain: mono, 16 kHz, peak-normalized.
+    pub fn preprocess_for_musetalk(&self) -> Result<AudioData> {
+        self.resample_to(MUSETALK_SAMPLE_RATE)?.normalize_peak()
+    }
+
+    /// Returns the audio between `start_secs` and `end_secs`.
+    ///
+    /// Bounds are clamped to the clip and rounded down to whole sample frames.
+    pub fn slice(&self, start_secs: f64, end_secs: f64) -> Result<AudioData> {
+        let channels = self.channels as usize;
+        let frame_at = |secs: f64| {
+            let frame = (secs.max(0.0) * f64::from(self.sample_rate)) as usize;
+            (frame * channels).min(self.samples.len())
+        };
+        let start = frame_at(start_secs);
+        let end = frame_at(end_secs).max(start);
+        let samples = self.samples[start..end].to_vec();
+        Self::from_samples(samples, self.sample_rate, self.channels)
+    }
+
+    /// Returns a copy with a fixed gain of `db` decibels applied.
+    ///
+    /// Samples pushed past full scale are clamped rather than wrapped.
+    pub fn with_gain(&self, db: f32) -> Result<AudioData> {
+        if !db.is_finite() {
+            return Err(CliError::AudioLoad(format!(
+                "Invalid audio gain {db} dB: must be a finite number"
+            )));
+        }
+        let gain = 10f32.powf(db / 20.0);
+        let samples = self
+            .samples
+            .iter()
+            .map(|s| (s * gain).clamp(-1.0, 1.0))
+            .collect();
+        Self::from_samples(samples, self.sample_rate, self.channels)
+    }
+
+    /// Returns a copy with `millis` of silence prepended.
+    pub fn with_lead_in(&self, millis: u32) -> Result<AudioData> {
+        let frames = u64::from(self.sample_rate) * u64::from(millis) / 1000;
+        let mut samples = vec![0.0; frames as usize * self.channels as usize];
+        samples.extend_from_slice(&self.samples);
+        Self::from_samples(samples, self.sample_rate, self.channels)
+    }
+
+    /// Writes the encoded WAV to a temp file, returning its path.
+    pub fn to_temp_wav(&self) -> Result<TempPath> {
+        let bytes = decode_base64(&self.base64_wav, || "encoded audio".to_string())?;
+        let file = tempfile::Builder::new().suffix(".wav").tempfile()?;
+        std::fs::write(file.path(), bytes)?;
+        Ok(file.into_temp_path())
+    }
+}
+
+/// Encodes normalized samples as a base64 16-bit PCM WAV.
+pub fn encode_wav_base64(samples: &[f32], sample_rate: u32, channels: u16) -> Result<String> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut bytes = Vec::new();
+    {
+        let mut writer = hound::WavWriter::new(std::io::Cursor::new(&mut bytes), spec)
+            .map_err(|e| CliError::AudioLoad(format!("Failed to encode WAV: {e}")))?;
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer
+                .write_sample(value)
+                .map_err(|e| CliError::AudioLoad(format!("Failed to encode WAV: {e}")))?;
+        }
+        writer
+            .finalize()
+            .map_err(|e| CliError::AudioLoad(format!("Failed to encode WAV: {e}")))?;
+    }
+    Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
+}
+
+/// Options controlling how an audio file is read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioLoadOptions {
+    /// Rebuild the header with this format when a WAV file fails to parse.
+    pub repair: Option<WavFormat>,
+}
+
+/// Loads a WAV, MP3, or FLAC audio file from the given path.
 pub fn load_audio(path: &Path) -> Result<AudioData> {
+    load_audio_with(path, &AudioLoadOptions::default())
+}
+
+/// Loads an audio file with the given options.
+pub fn load_audio_with(path: &Path, options: &AudioLoadOptions) -> Result<AudioData> {
     tracing::debug!("Loading audio from: {}", path.display());
 
     let ext = path
@@ -31,17 +235,25 @@ pub fn load_audio(path: &Path) -> Result<AudioData> {
         .unwrap_or_default();
 
     match ext.as_str() {
-        "wav" => load_wav(path),
-        "mp3" | "flac" => Err(CliError::AudioLoad(format!(
-            "{} format not yet implemented, please convert to WAV",
-            ext.to_uppercase()
-        ))),
+        "wav" => {
+            let bytes = std::fs::read(path).map_err(CliError::Io)?;
+            match (load_wav(&bytes), options.repair) {
+                (Err(e), Some(format)) => {
+                    tracing::warn!("{e}; rebuilding the WAV header as {format}");
+                    load_wav(&repair_wav(&bytes, format))
+                }
+                (result, _) => result,
+            }
+        }
+        "mp3" | "flac" => decode_compressed(std::fs::read(path)?, &ext),
         _ => Err(CliError::UnsupportedAudioFormat(ext)),
     }
 }
 
-fn load_wav(path: &Path) -> Result<AudioData> {
-    let reader = WavReader::open(path).map_err(|e| CliError::AudioLoad(e.to_string()))?;
+fn load_wav(wav_bytes: &[u8]) -> Result<AudioData> {
+    let canonical = canonicalize_wav(wav_bytes);
+    let wav_bytes = canonical.as_deref().unwrap_or(wav_bytes);
+    let reader = WavReader::new(wav_bytes).map_err(|e| CliError::AudioLoad(e.to_string()))?;
 
     let spec = reader.spec();
     let sample_rate = spec.sample_rate;
@@ -74,9 +286,7 @@ fn load_wav(path: &Path) -> Result<AudioData> {
     let num_samples = samples.len();
     let duration_secs = num_samples as f32 / (sample_rate as f32 * channels as f32);
 
-    // Read raw file bytes for base64 encoding
-    let wav_bytes = std::fs::read(path).map_err(CliError::Io)?;
-    let base64_wav = base64::engine::general_purpose::STANDARD.encode(&wav_bytes);
+    let base64_wav = base64::engine::general_purpose::STANDARD.encode(wav_bytes);
 
     tracing::info!(
         "Loaded audio: {:.2}s, {} Hz, {} ch, {} samples (base64: {} chars)",
@@ -103,8 +313,12 @@ mod tests {
     use tempfile::tempdir;
 
     fn create_test_wav(path: &Path, sample_rate: u32, duration_secs: f32) {
+        create_test_wav_channels(path, sample_rate, duration_secs, 1);
+    }
+
+    fn create_test_wav_channels(path: &Path, sample_rate: u32, duration_secs: f32, channels: u16) {
         let spec = WavSpec {
-            channels: 1,
+            channels,
             sample_rate,
             bits_per_sample: 16,
             sample_format: hound::SampleFormat::Int,
@@ -117,11 +331,35 @@ mod tests {
             let t = i as f32 / sample_rate as f32;
             let sample = (t * 440.0 * 2.0 * std::f32::consts::PI).sin();
             let sample_i16 = (sample * 32767.0) as i16;
-            writer.write_sample(sample_i16).unwrap();
+            for _ in 0..channels {
+                writer.write_sample(sample_i16).unwrap();
+            }
         }
         writer.finalize().unwrap();
     }
 
+    #[test]
+    fn test_corrupted_header_repaired_with_assumed_format() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tts.wav");
+        create_test_wav(&path, 16000, 1.0);
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8..16].copy_from_slice(b"garbage!");
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(load_audio(&path).is_err());
+        let options = AudioLoadOptions {
+            repair: Some(WavFormat::default()),
+        };
+        let data = load_audio_with(&path, &options).unwrap();
+        assert_eq!(data.samples.len(), 16000);
+        assert_eq!((data.sample_rate, data.channels), (16000, 1));
+        assert!(
+            (data.samples[4] - (4.0f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin()).abs()
+                < 1e-3
+        );
+    }
+
     #[test]
     fn test_load_wav() {
         let dir = tempdir().unwrap();
@@ -136,6 +374,107 @@ mod tests {
         assert!(!data.base64_wav.is_empty());
     }
 
+    #[test]
+    fn test_to_mono_downmixes_stereo() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stereo.wav");
+        create_test_wav_channels(&path, 16000, 1.0, 2);
+
+        let stereo = load_audio(&path).unwrap();
+        assert_eq!(stereo.channels, 2);
+
+        let mono = stereo.to_mono().unwrap();
+        assert_eq!(mono.channels, 1);
+        assert_eq!(mono.samples.len(), stereo.samples.len() / 2);
+        assert!((mono.duration_secs - stereo.duration_secs).abs() < 0.01);
+        assert_ne!(mono.base64_wav, stereo.base64_wav);
+    }
+
+    #[test]
+    fn test_resample_to_downmixes_and_scales_length() {
+        let samples = (0..44100 * 2).map(|i| (i / 2) as f32 / 44100.0).collect();
+        let input = AudioData::from_samples(samples, 44100, 2).unwrap();
+
+        let output = input.resample_to(16000).unwrap();
+        assert_eq!((output.sample_rate, output.channels), (16000, 1));
+        assert_eq!(output.samples.len(), 16000);
+        assert!((output.samples[8000] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_preprocess_for_musetalk() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stereo48k.wav");
+        create_test_wav_channels(&path, 48000, 1.0, 2);
+        let mut input = load_audio(&path).unwrap();
+        input.samples.iter_mut().for_each(|s| *s *= 0.25);
+
+        let output = input.preprocess_for_musetalk().unwrap();
+        assert_eq!(output.sample_rate, MUSETALK_SAMPLE_RATE);
+        assert_eq!(output.channels, 1);
+        assert_eq!(output.samples.len(), 16000);
+        assert!((output.duration_secs - 1.0).abs() < 1e-3);
+        let peak = output.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - NORMALIZED_PEAK).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_slice_window() {
+        let samples: Vec<f32> = (0..32000).map(|i| i as f32 / 32000.0).collect();
+        let audio = AudioData::from_samples(samples, 16000, 2).unwrap();
+        assert!((audio.duration_secs - 1.0).abs() < 1e-6);
+
+        let slice = audio.slice(0.25, 0.75).unwrap();
+        assert_eq!(slice.samples.len(), 16000);
+        assert!((slice.duration_secs - 0.5).abs() < 1e-6);
+        assert_eq!(slice.samples[0], audio.samples[8000]);
+
+        let path = slice.to_temp_wav().unwrap();
+        let reloaded = load_audio(&path).unwrap();
+        assert_eq!(reloaded.channels, 2);
+        assert!((reloaded.duration_secs - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_gain_scales_and_clamps() {
+        let audio = AudioData::from_samples(vec![0.25, -0.25, 0.8, 0.0], 16000, 1).unwrap();
+        let louder = audio.with_gain(6.0).unwrap();
+
+        assert!((louder.samples[0] - 0.5).abs() < 0.01);
+        assert!((louder.samples[1] + 0.5).abs() < 0.01);
+        assert_eq!(louder.samples[2], 1.0);
+        assert!((audio.with_gain(-6.0).unwrap().samples[0] - 0.125).abs() < 0.01);
+
+        let silence = AudioData::from_samples(vec![0.0; 4], 16000, 1).unwrap();
+        assert!(
+            silence
+                .with_gain(-120.0)
+                .unwrap()
+                .samples
+                .iter()
+                .all(|s| *s == 0.0)
+        );
+        assert!(audio.with_gain(f32::NEG_INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_over_gained_audio_reports_clipping() {
+        let audio = AudioData::from_samples(vec![0.1, -0.6, 0.7, 0.3, -0.2], 16000, 1).unwrap();
+        assert_eq!(audio.clip_count(), 0);
+        // +6 dB doubles: |-0.6| and 0.7 go past full scale
+        assert_eq!(audio.with_gain(6.0).unwrap().clip_count(), 2);
+    }
+
+    #[test]
+    fn test_lead_in_prepends_silence() {
+        let audio = AudioData::from_samples(vec![0.5; 16000], 16000, 1).unwrap();
+        let padded = audio.with_lead_in(100).unwrap();
+
+        assert!((padded.duration_secs - 1.1).abs() < 1e-6);
+        assert!(padded.samples[..1600].iter().all(|s| *s == 0.0));
+        assert_eq!(padded.samples[1600], 0.5);
+    }
+
     #[test]
     fn test_load_nonexistent_audio() {
         let result = load_audio(Path::new("nonexistent.wav"));
@@ -0,0 +1,78 @@
+//! ICC color management for reference images.
+//!
+//! Images tagged with a wide-gamut profile (Display P3, Adobe RGB) look
+//! washed out when their pixels are treated as sRGB. Converting through the
+//! embedded profile keeps colors matching the artist's editor.
+
+use crate::error::{CliError, Result};
+use image::RgbImage;
+use lcms2::{Intent, PixelFormat, Profile, Transform};
+
+/// Converts `img` in place from the embedded `icc` profile to sRGB.
+pub fn convert_to_srgb(img: &mut RgbImage, icc: &[u8]) -> Result<()> {
+    let source = Profile::new_icc(icc)
+        .map_err(|e| CliError::ImageLoad(format!("Invalid ICC profile: {e}")))?;
+    let transform: Transform<u8, u8> = Transform::new(
+        &source,
+        PixelFormat::RGB_8,
+        &Profile::new_srgb(),
+        PixelFormat::RGB_8,
+        Intent::Perceptual,
+    )
+    .map_err(|e| CliError::ImageLoad(format!("Unsupported ICC profile: {e}")))?;
+
+    transform.transform_in_place(img);
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use lcms2::{CIExyY, CIExyYTRIPLE, ToneCurve};
+
+    /// An ICC profile with Adobe RGB (1998) primaries.
+    pub(crate) fn adobe_rgb_icc() -> Vec<u8> {
+        let white = CIExyY {
+            x: 0.3127,
+            y: 0.3290,
+            Y: 1.0,
+        };
+        let primaries = CIExyYTRIPLE {
+            Red: CIExyY {
+                x: 0.64,
+                y: 0.33,
+                Y: 1.0,
+            },
+            Green: CIExyY {
+                x: 0.21,
+                y: 0.71,
+                Y: 1.0,
+            },
+            Blue: CIExyY {
+                x: 0.15,
+                y: 0.06,
+                Y: 1.0,
+            },
+        };
+        let curve = ToneCurve::new(2.2);
+        Profile::new_rgb(&white, &primaries, &[&curve, &curve, &curve])
+            .unwrap()
+            .icc()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wide_gamut_green_converted() {
+        let mut img = RgbImage::from_pixel(2, 2, image::Rgb([0, 200, 0]));
+        convert_to_srgb(&mut img, &adobe_rgb_icc()).unwrap();
+
+        // Adobe RGB green lies outside sRGB, so red is pulled in to compensate
+        assert_ne!(img.get_pixel(0, 0).0, [0, 200, 0]);
+    }
+
+    #[test]
+    fn test_invalid_profile_rejected() {
+        let mut img = RgbImage::new(1, 1);
+        assert!(convert_to_srgb(&mut img, b"not a profile").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! MP3 and FLAC decoding.
+//!
+//! Compressed files are decoded to normalized samples (integer PCM of any
+//! bit depth is scaled by `1 << (bits - 1)`) and re-encoded as a 16-bit PCM
+//! WAV, so the server always receives WAV whatever the input format.
+
+use super::audio::AudioData;
+use crate::error::{CliError, Result};
+use std::io::{Cursor, ErrorKind};
+use symphonia::core::audio::{SampleBuffer, SignalSpec};
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Decodes an MP3 or FLAC file held in memory; `extension` picks the format.
+///
+/// Corrupt frames are skipped rather than failing the whole file.
+pub fn decode_compressed(bytes: Vec<u8>, extension: &str) -> Result<AudioData> {
+    let name = extension.to_uppercase();
+    let invalid = |e: DecodeError| CliError::AudioLoad(format!("Invalid {name}: {e}"));
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(invalid)?
+        .format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| CliError::AudioLoad(format!("{name} has no audio track")))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(invalid)?;
+
+    let mut samples = Vec::new();
+    let mut spec: Option<SignalSpec> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(invalid(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let decoded_spec = *decoded.spec();
+                let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, decoded_spec);
+                buffer.copy_interleaved_ref(decoded);
+                samples.extend_from_slice(buffer.samples());
+                spec.get_or_insert(decoded_spec);
+            }
+            Err(DecodeError::DecodeError(e)) => tracing::debug!("Skipping bad {name} frame: {e}"),
+            Err(e) => return Err(invalid(e)),
+        }
+    }
+
+    let spec =
+        spec.ok_or_else(|| CliError::AudioLoad(format!("{name} contains no audio frames")))?;
+    tracing::debug!(
+        "{name}: {} Hz, {} channels, {} samples",
+        spec.rate,
+        spec.channels.count(),
+        samples.len()
+    );
+    AudioData::from_samples(samples, spec.rate, spec.channels.count() as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes in one 128 kbps, 44.1 kHz MPEG-1 Layer III frame.
+    const FRAME_LEN: usize = 417;
+
+    /// `frames` silent mono MP3 frames (zeroed side info decodes to silence).
+    fn silent_mp3(frames: usize) -> Vec<u8> {
+        let mut frame = vec![0u8; FRAME_LEN];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC0]);
+        frame.repeat(frames)
+    }
+
+    #[test]
+    fn test_generated_mp3_decodes_to_wav_audio() {
+        let audio = decode_compressed(silent_mp3(40), "mp3").unwrap();
+
+        assert_eq!(audio.sample_rate, 44100);
+        assert_eq!(audio.channels, 1);
+        // 40 frames of 1152 samples, give or take the decoder's priming frame
+        let expected = 40.0 * 1152.0 / 44100.0;
+        assert!(
+            (audio.duration_secs - expected).abs() < 0.05,
+            "{}",
+            audio.duration_secs
+        );
+        assert!(audio.samples.iter().all(|s| s.abs() < 1e-3));
+        assert!(audio.base64_wav.starts_with("UklGR"));
+    }
+
+    /// CRC-8 (poly 0x07) used by FLAC frame headers.
+    fn crc8(bytes: &[u8]) -> u8 {
+        bytes.iter().fold(0u8, |mut crc, &b| {
+            crc ^= b;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    (crc << 1) ^ 0x07
+                } else {
+                    crc << 1
+                };
+            }
+            crc
+        })
+    }
+
+    /// CRC-16 (poly 0x8005) used by FLAC frame footers.
+    fn crc16(bytes: &[u8]) -> u16 {
+        bytes.iter().fold(0u16, |mut crc, &b| {
+            crc ^= u16::from(b) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 {
+                    (crc << 1) ^ 0x8005
+                } else {
+                    crc << 1
+                };
+            }
+            crc
+        })
+    }
+
+    /// Mono 24-bit FLAC of `samples` at `rate`, stored in verbatim subframes.
+    fn flac_24bit(samples: &[i32], rate: u32) -> Vec<u8> {
+        const BLOCK: usize = 4096;
+        let mut out = b"fLaC".to_vec();
+        // Last-metadata-block flag, STREAMINFO type, 34-byte length
+        out.extend_from_slice(&[0x80, 0, 0, 34]);
+        out.extend_from_slice(&(BLOCK as u16).to_be_bytes());
+        out.extend_from_slice(&(BLOCK as u16).to_be_bytes());
+        out.extend_from_slice(&[0; 6]);
+        // 20-bit rate, 3-bit channels-1, 5-bit bits-1, 36-bit total samples
+        let packed = (u64::from(rate) << 44) | (23 << 36) | samples.len() as u64;
+        out.extend_from_slice(&packed.to_be_bytes());
+        out.extend_from_slice(&[0; 16]);
+
+        for (number, block) in samples.chunks(BLOCK).enumerate() {
+            let start = out.len();
+            // Fixed blocking; 16-bit block size and rate/depth from STREAMINFO
+            out.extend_from_slice(&[0xFF, 0xF8, 0x70, 0x00, number as u8]);
+            out.extend_from_slice(&(block.len() as u16 - 1).to_be_bytes());
+            out.push(crc8(&out[start..]));
+            out.push(0x02); // verbatim subframe
+            for sample in block {
+                out.extend_from_slice(&sample.to_be_bytes()[1..]);
+            }
+            let crc = crc16(&out[start..]);
+            out.extend_from_slice(&crc.to_be_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_24bit_flac_round_trip() {
+        let mut samples = vec![0i32; 16000];
+        samples[0] = 1 << 22;
+        samples[1] = -(1 << 23);
+        samples[5000] = (1 << 23) - 1;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("speech.flac");
+        std::fs::write(&path, flac_24bit(&samples, 16000)).unwrap();
+
+        let audio = crate::loader::load_audio(&path).unwrap();
+
+        assert_eq!((audio.sample_rate, audio.channels), (16000, 1));
+        assert_eq!(audio.samples.len(), 16000);
+        assert!((audio.duration_secs - 1.0).abs() < 1e-6);
+        assert!((audio.samples[0] - 0.5).abs() < 1e-6);
+        assert!((audio.samples[1] + 1.0).abs() < 1e-6);
+        assert!((audio.samples[5000] - 1.0).abs() < 1e-6);
+        assert!(audio.base64_wav.starts_with("UklGR"));
+    }
+
+    #[test]
+    fn test_undecodable_input_rejected() {
+        assert!(decode_compressed(b"definitely not audio".to_vec(), "mp3").is_err());
+        assert!(decode_compressed(b"definitely not audio".to_vec(), "flac").is_err());
+    }
+}
@@ -1,8 +1,10 @@
 //! Image loading and preprocessing.
 
+use super::{color, tonemap};
 use crate::error::{CliError, Result};
 use base64::Engine;
-use image::GenericImageView;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageDecoder, ImageEncoder};
 use std::path::Path;
 
 /// Loaded image data ready for processing.
@@ -16,46 +18,210 @@ pub struct ImageData {
     pub rgb_data: Vec<u8>,
     /// Base64-encoded PNG for API transmission.
     pub base64_png: String,
+    /// Size of the decoded source as `(width, height)`, before `--resolution`
+    /// or the size budget scaled it.
+    pub source_size: (u32, u32),
 }
 
 /// Loads an image from the given path.
 ///
 /// Converts to RGB format and prepares for API transmission.
+///
+/// The transmitted image is re-encoded from raw pixels, so EXIF and other
+/// metadata (GPS, camera, timestamps) in the source file never reach the
+/// server. Any future JPEG transmission path must likewise encode from
+/// pixels rather than copying source bytes, so no APP1/EXIF segment is kept.
 pub fn load_image(path: &Path) -> Result<ImageData> {
+    load_image_with(path, &ImageLoadOptions::default())
+}
+
+/// Options controlling how a reference image is prepared.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImageLoadOptions {
+    /// PNG compression level (0-9); `None` keeps the encoder's fast default.
+    pub png_compression: Option<u8>,
+    /// Convert from an embedded ICC profile to sRGB.
+    ///
+    /// Images without a profile are assumed to be sRGB already.
+    pub color_manage: bool,
+    /// Downscale until the base64 PNG is at most this many bytes.
+    pub max_encoded_bytes: Option<usize>,
+    /// Tone-map 16-bit or HDR-profiled images to 8-bit SDR instead of clipping.
+    pub tonemap: bool,
+    /// Fit and pad to exactly this `(width, height)` (`--resolution`).
+    pub resize_to: Option<(u32, u32)>,
+}
+
+/// Loads an image with the given preparation options.
+pub fn load_image_with(path: &Path, options: &ImageLoadOptions) -> Result<ImageData> {
     tracing::debug!("Loading image from: {}", path.display());
 
-    let img = image::open(path).map_err(|e| CliError::ImageLoad(e.to_string()))?;
+    // Sniff the format from content so extension aliases like .jpe decode
+    let mut decoder = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| CliError::ImageLoad(e.to_string()))?
+        .into_decoder()
+        .map_err(|e| CliError::ImageLoad(e.to_string()))?;
+    let icc = if options.color_manage || options.tonemap {
+        decoder.icc_profile().ok().flatten()
+    } else {
+        None
+    };
+    let hdr_profile = icc.as_deref().is_some_and(tonemap::is_hdr_profile);
+    let img = image::DynamicImage::from_decoder(decoder)
+        .map_err(|e| CliError::ImageLoad(e.to_string()))?;
 
-    let (width, height) = img.dimensions();
-    tracing::debug!("Image dimensions: {width}x{height}");
+    let mut rgb_img = if options.tonemap && (hdr_profile || tonemap::is_high_bit_depth(&img)) {
+        tracing::info!("Tone-mapping {:?} reference to 8-bit SDR", img.color());
+        tonemap::reinhard(&img)
+    } else {
+        img.to_rgb8()
+    };
+    // The tone-map already replaces an HDR transfer curve with SDR gamma
+    if let Some(icc) = icc.filter(|_| options.color_manage && !hdr_profile) {
+        tracing::debug!(
+            "Converting embedded ICC profile ({} bytes) to sRGB",
+            icc.len()
+        );
+        color::convert_to_srgb(&mut rgb_img, &icc)?;
+    }
+    let source_size = rgb_img.dimensions();
+    if let Some((width, height)) = options.resize_to {
+        rgb_img = super::resize::resize_to_fit(&rgb_img, width, height);
+    }
+    if let Some(budget) = options.max_encoded_bytes {
+        rgb_img = fit_to_budget(rgb_img, options.png_compression, budget)?;
+    }
+    Ok(ImageData {
+        source_size,
+        ..ImageData::from_rgb(rgb_img, options.png_compression)?
+    })
+}
 
-    // Convert to RGB8
-    let rgb_img = img.to_rgb8();
-    let rgb_data = rgb_img.as_raw().clone();
+/// Resizes an image to `width` pixels wide, keeping its aspect ratio.
+pub fn resize_to_width(img: &image::RgbImage, width: u32) -> image::RgbImage {
+    let height = (u64::from(img.height()) * u64::from(width) / u64::from(img.width())).max(1);
+    image::imageops::resize(
+        img,
+        width,
+        height as u32,
+        image::imageops::FilterType::Triangle,
+    )
+}
 
-    // Encode as PNG for transmission
-    let mut png_bytes = Vec::new();
-    let mut cursor = std::io::Cursor::new(&mut png_bytes);
-    rgb_img
-        .write_to(&mut cursor, image::ImageFormat::Png)
-        .map_err(|e| CliError::ImageLoad(format!("Failed to encode PNG: {e}")))?;
+/// Length of the base64 encoding of `len` bytes.
+fn base64_len(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+/// Shrinks `img` to the largest width whose base64 PNG fits in `budget` bytes.
+///
+/// Images already within budget are returned unchanged.
+fn fit_to_budget(
+    img: image::RgbImage,
+    level: Option<u8>,
+    budget: usize,
+) -> Result<image::RgbImage> {
+    let fits = |img: &image::RgbImage| -> Result<bool> {
+        Ok(base64_len(encode_png(img, level)?.len()) <= budget)
+    };
+    if fits(&img)? {
+        return Ok(img);
+    }
 
-    let base64_png = base64::engine::general_purpose::STANDARD.encode(&png_bytes);
+    // Binary search the width; PNG size grows with the pixel count
+    let (mut lo, mut hi) = (1, img.width() - 1);
+    let mut best = None;
+    while lo <= hi {
+        let width = lo + (hi - lo) / 2;
+        let candidate = resize_to_width(&img, width);
+        if fits(&candidate)? {
+            best = Some(candidate);
+            lo = width + 1;
+        } else {
+            hi = width - 1;
+        }
+    }
 
+    let best = best.ok_or_else(|| {
+        CliError::ImageLoad(format!(
+            "reference cannot be downscaled to fit {budget} bytes"
+        ))
+    })?;
     tracing::info!(
-        "Loaded image: {}x{}, {} bytes (base64: {} chars)",
-        width,
-        height,
-        rgb_data.len(),
-        base64_png.len()
+        "Downscaled reference from {}x{} to {}x{} to fit {budget} bytes",
+        img.width(),
+        img.height(),
+        best.width(),
+        best.height()
     );
+    Ok(best)
+}
 
-    Ok(ImageData {
-        width,
-        height,
-        rgb_data,
-        base64_png,
-    })
+/// Encodes an RGB image as PNG at the given compression level (0-9).
+///
+/// Higher levels trade CPU time for smaller output.
+pub fn encode_png(img: &image::RgbImage, level: Option<u8>) -> Result<Vec<u8>> {
+    let compression = match level {
+        None => CompressionType::default(),
+        Some(0) => CompressionType::Uncompressed,
+        Some(level) => CompressionType::Level(level.min(9)),
+    };
+    let mut png_bytes = Vec::new();
+    PngEncoder::new_with_quality(&mut png_bytes, compression, FilterType::default())
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| CliError::ImageLoad(format!("Failed to encode PNG: {e}")))?;
+    Ok(png_bytes)
+}
+
+impl ImageData {
+    /// Decodes an in-memory PNG or JPEG, detecting the format from its magic bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let format = image::guess_format(bytes)
+            .map_err(|_| CliError::ImageLoad("Unrecognized image data".to_string()))?;
+        if !matches!(format, image::ImageFormat::Png | image::ImageFormat::Jpeg) {
+            return Err(CliError::ImageLoad(format!(
+                "Unsupported image format {format:?}, expected PNG or JPEG"
+            )));
+        }
+        let img = image::load_from_memory_with_format(bytes, format)
+            .map_err(|e| CliError::ImageLoad(e.to_string()))?;
+        Self::from_rgb(img.to_rgb8(), None)
+    }
+
+    /// Prepares RGB pixels for API transmission.
+    fn from_rgb(rgb_img: image::RgbImage, png_level: Option<u8>) -> Result<Self> {
+        let (width, height) = rgb_img.dimensions();
+        tracing::debug!("Image dimensions: {width}x{height}");
+
+        let rgb_data = rgb_img.as_raw().clone();
+
+        // Encode as PNG for transmission
+        let png_bytes = encode_png(&rgb_img, png_level)?;
+
+        let base64_png = base64::engine::general_purpose::STANDARD.encode(&png_bytes);
+
+        tracing::info!(
+            "Loaded image: {}x{}, {} bytes (base64: {} chars)",
+            width,
+            height,
+            rgb_data.len(),
+            base64_png.len()
+        );
+
+        Ok(Self {
+            width,
+            height,
+            rgb_data,
+            base64_png,
+            source_size: (width, height),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -93,6 +259,224 @@ mod tests {
         assert_eq!(data.height, 4);
     }
 
+    #[test]
+    fn test_image_from_png_bytes() {
+        let img = image::RgbImage::from_fn(3, 2, |_, _| image::Rgb([0, 255, 0]));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let data = ImageData::from_bytes(&png).unwrap();
+        assert_eq!((data.width, data.height), (3, 2));
+        assert_eq!(&data.rgb_data[..3], [0, 255, 0]);
+    }
+
+    #[test]
+    fn test_higher_png_compression_is_smaller() {
+        let img = image::RgbImage::from_fn(64, 64, |x, y| image::Rgb([x as u8, y as u8, 128]));
+
+        let stored = encode_png(&img, Some(0)).unwrap();
+        let best = encode_png(&img, Some(9)).unwrap();
+        assert!(best.len() < stored.len());
+
+        let decoded = image::load_from_memory(&best).unwrap().to_rgb8();
+        assert_eq!(decoded, img);
+    }
+
+    #[test]
+    fn test_color_manage_converts_tagged_image() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("wide.png");
+        let img = image::RgbImage::from_pixel(2, 2, image::Rgb([0, 200, 0]));
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        encoder
+            .set_icc_profile(super::color::tests::adobe_rgb_icc())
+            .unwrap();
+        encoder
+            .write_image(img.as_raw(), 2, 2, image::ExtendedColorType::Rgb8)
+            .unwrap();
+        std::fs::write(&path, &png).unwrap();
+
+        let naive = load_image(&path).unwrap();
+        let managed = load_image_with(
+            &path,
+            &ImageLoadOptions {
+                color_manage: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(naive.rgb_data[..3], [0, 200, 0]);
+        assert_ne!(managed.rgb_data, naive.rgb_data);
+    }
+
+    #[test]
+    fn test_color_manage_untagged_image_unchanged() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.png");
+        image::RgbImage::from_pixel(2, 2, image::Rgb([0, 200, 0]))
+            .save(&path)
+            .unwrap();
+
+        let options = ImageLoadOptions {
+            color_manage: true,
+            ..Default::default()
+        };
+        let managed = load_image_with(&path, &options).unwrap();
+        assert_eq!(managed.rgb_data, load_image(&path).unwrap().rgb_data);
+    }
+
+    #[test]
+    fn test_tonemap_16_bit_png_to_8_bit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("hdr.png");
+        let img: image::ImageBuffer<image::Rgb<u16>, Vec<u16>> =
+            image::ImageBuffer::from_fn(64, 2, |x, _| {
+                let v = 2000 + x as u16 * 1000;
+                image::Rgb([v, v, v])
+            });
+        img.save(&path).unwrap();
+        let options = ImageLoadOptions {
+            tonemap: true,
+            ..Default::default()
+        };
+
+        let data = load_image_with(&path, &options).unwrap();
+        assert_eq!(data.rgb_data.len(), 64 * 2 * 3);
+        let clipped = data.rgb_data.iter().filter(|&&v| v == 255).count();
+        assert!(clipped <= 6, "{clipped} channels clipped");
+
+        // 8-bit SDR images are left alone
+        let sdr = dir.path().join("sdr.png");
+        image::RgbImage::from_pixel(2, 2, image::Rgb([0, 200, 0]))
+            .save(&sdr)
+            .unwrap();
+        assert_eq!(
+            load_image_with(&sdr, &options).unwrap().rgb_data,
+            load_image(&sdr).unwrap().rgb_data
+        );
+    }
+
+    #[test]
+    fn test_image_from_unrecognized_bytes() {
+        assert!(matches!(
+            ImageData::from_bytes(b"not an image"),
+            Err(CliError::ImageLoad(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_jpeg_extension_aliases() {
+        let dir = tempdir().unwrap();
+        let img = image::RgbImage::from_fn(4, 4, |_, _| image::Rgb([0, 0, 255]));
+        let mut jpeg = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut jpeg),
+            image::ImageFormat::Jpeg,
+        )
+        .unwrap();
+
+        for ext in ["jfif", "jpe"] {
+            let path = dir.path().join(format!("test.{ext}"));
+            std::fs::write(&path, &jpeg).unwrap();
+            let data = load_image(&path).unwrap();
+            assert_eq!((data.width, data.height), (4, 4));
+        }
+    }
+
+    #[test]
+    fn test_webp_and_bmp_round_trip() {
+        let dir = tempdir().unwrap();
+        let img =
+            image::RgbImage::from_fn(3, 2, |x, y| image::Rgb([x as u8 * 80, y as u8 * 120, 7]));
+
+        // Both encoders are lossless, so the pixels come back unchanged
+        for ext in ["webp", "bmp"] {
+            let path = dir.path().join(format!("test.{ext}"));
+            img.save(&path).unwrap();
+            let data = load_image(&path).unwrap();
+            assert_eq!((data.width, data.height), (3, 2), "{ext}");
+            assert_eq!(data.rgb_data, img.as_raw().as_slice(), "{ext}");
+        }
+    }
+
+    /// Inserts an APP1 EXIF segment with GPS tags after the JPEG SOI marker.
+    fn with_gps_exif(jpeg: &[u8]) -> Vec<u8> {
+        let mut payload = b"Exif\0\0MM\0*\0\0\0\x08".to_vec();
+        payload.extend_from_slice(b"GPSLatitude=37.7749;GPSLongitude=-122.4194");
+        let len = (payload.len() + 2) as u16;
+
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(&payload);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn test_exif_stripped_from_transmitted_image() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("photo.jpg");
+
+        let img =
+            image::RgbImage::from_fn(8, 8, |x, y| image::Rgb([x as u8 * 30, y as u8 * 30, 0]));
+        let mut jpeg = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut jpeg),
+            image::ImageFormat::Jpeg,
+        )
+        .unwrap();
+        let tagged = with_gps_exif(&jpeg);
+        assert!(contains(&tagged, b"Exif"));
+        std::fs::write(&path, &tagged).unwrap();
+
+        let data = load_image(&path).unwrap();
+        let sent = base64::engine::general_purpose::STANDARD
+            .decode(&data.base64_png)
+            .unwrap();
+        assert!(!contains(&sent, b"Exif"));
+        assert!(!contains(&sent, b"eXIf"));
+        assert!(!contains(&sent, b"GPS"));
+    }
+
+    #[test]
+    fn test_oversized_image_downscaled_to_budget() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("big.png");
+        image::RgbImage::from_fn(256, 128, |x, y| {
+            image::Rgb([(x * 7 % 256) as u8, (y * 13 % 256) as u8, (x ^ y) as u8])
+        })
+        .save(&path)
+        .unwrap();
+
+        let full = load_image(&path).unwrap();
+        let budget = full.base64_png.len() / 4;
+        let options = ImageLoadOptions {
+            max_encoded_bytes: Some(budget),
+            ..Default::default()
+        };
+        let data = load_image_with(&path, &options).unwrap();
+
+        assert!(data.base64_png.len() <= budget);
+        assert!(data.width < 256 && data.width > 1);
+        // Aspect ratio is kept
+        assert_eq!(data.height, data.width / 2);
+
+        // References within budget are untouched
+        let options = ImageLoadOptions {
+            max_encoded_bytes: Some(full.base64_png.len()),
+            ..Default::default()
+        };
+        assert_eq!(load_image_with(&path, &options).unwrap().width, 256);
+    }
+
     #[test]
     fn test_load_nonexistent_image() {
         let result = load_image(Path::new("nonexistent.png"));
@@ -0,0 +1,100 @@
+//! Two-pass RMS normalization (`--two-pass-audio-analysis`).
+//!
+//! The first pass measures RMS and peak over the whole clip; the second
+//! applies the single gain that brings the RMS to a fixed target. Unlike
+//! peak normalization, one loud transient doesn't leave the rest of the clip
+//! quiet, so a batch of clips comes out at a similar perceived loudness.
+
+use super::audio::AudioData;
+use crate::error::Result;
+use std::fmt;
+
+/// RMS level clips are normalized to, in dBFS.
+pub const TARGET_RMS_DBFS: f32 = -20.0;
+
+/// Whole-clip level statistics from the analysis pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Levels {
+    /// Root mean square of all samples (linear, 0.0 to 1.0).
+    pub rms: f32,
+    /// Largest absolute sample.
+    pub peak: f32,
+}
+
+impl Levels {
+    /// RMS in dBFS (negative infinity for silence).
+    pub fn rms_dbfs(&self) -> f32 {
+        20.0 * self.rms.log10()
+    }
+}
+
+impl fmt::Display for Levels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RMS {:.1} dBFS, peak {:.1} dBFS",
+            self.rms_dbfs(),
+            20.0 * self.peak.log10()
+        )
+    }
+}
+
+/// First pass: measures RMS and peak across every sample.
+pub fn analyze(samples: &[f32]) -> Levels {
+    let (sum, peak) = samples.iter().fold((0.0f64, 0.0f32), |(sum, peak), s| {
+        (sum + f64::from(*s) * f64::from(*s), peak.max(s.abs()))
+    });
+    let rms = if samples.is_empty() {
+        0.0
+    } else {
+        (sum / samples.len() as f64).sqrt() as f32
+    };
+    Levels { rms, peak }
+}
+
+/// Second pass: scales `audio` so its RMS reaches `target_dbfs`.
+///
+/// Silent audio is returned unchanged; samples pushed past full scale are
+/// clamped.
+pub fn normalize_rms(audio: &AudioData, target_dbfs: f32) -> Result<AudioData> {
+    let levels = analyze(&audio.samples);
+    if levels.rms == 0.0 {
+        return Ok(audio.clone());
+    }
+    let gain_db = target_dbfs - levels.rms_dbfs();
+    tracing::debug!("Audio levels: {levels}; applying {gain_db:+.1} dB");
+    audio.with_gain(gain_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(amplitude: f32) -> AudioData {
+        let samples = (0..16000)
+            .map(|i| (i as f32 * 0.05).sin() * amplitude)
+            .collect();
+        AudioData::from_samples(samples, 16000, 1).unwrap()
+    }
+
+    #[test]
+    fn test_different_clips_reach_same_rms() {
+        let quiet = tone(0.05);
+        let loud = tone(0.6);
+        assert!(analyze(&loud.samples).rms > 10.0 * analyze(&quiet.samples).rms);
+
+        for clip in [quiet, loud] {
+            let normalized = clip.normalize_rms(TARGET_RMS_DBFS).unwrap();
+            let rms_dbfs = analyze(&normalized.samples).rms_dbfs();
+            assert!((rms_dbfs - TARGET_RMS_DBFS).abs() < 0.05, "{rms_dbfs}");
+        }
+    }
+
+    #[test]
+    fn test_silence_left_unchanged() {
+        let silence = AudioData::from_samples(vec![0.0; 100], 16000, 1).unwrap();
+        let normalized = silence.normalize_rms(TARGET_RMS_DBFS).unwrap();
+        assert!(normalized.samples.iter().all(|s| *s == 0.0));
+        assert_eq!(analyze(&[]).rms, 0.0);
+    }
+}
@@ -0,0 +1,246 @@
+//! Loudness and true-peak measurement (ITU-R BS.1770 / EBU R128).
+//!
+//! Integrated loudness K-weights each channel, averages energy over 400 ms
+//! blocks with 75% overlap, and gates out blocks below -70 LUFS and then
+//! blocks more than 10 LU below the ungated mean. True peak is the largest
+//! absolute sample after 4x oversampling.
+
+use super::audio::AudioData;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// Blocks quieter than this are ignored entirely.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// Blocks this far below the absolutely-gated mean are ignored.
+const RELATIVE_GATE_LU: f64 = 10.0;
+
+/// Oversampling factor for true-peak detection.
+const OVERSAMPLING: usize = 4;
+
+/// Interpolation filter taps per oversampled phase.
+const TAPS_PER_PHASE: usize = 12;
+
+/// Integrated loudness and true peak of a clip.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS; `None` if the clip is too short or silent.
+    pub integrated_lufs: Option<f64>,
+    /// True peak in dBTP (negative infinity for silence).
+    pub true_peak_dbtp: f64,
+}
+
+impl fmt::Display for Loudness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.integrated_lufs {
+            Some(lufs) => writeln!(f, "  Integrated loudness: {lufs:.1} LUFS")?,
+            None => writeln!(f, "  Integrated loudness: n/a (too short or silent)")?,
+        }
+        write!(f, "  True peak: {:.1} dBTP", self.true_peak_dbtp)
+    }
+}
+
+/// Measures integrated loudness and true peak of `audio`.
+pub fn measure(audio: &AudioData) -> Loudness {
+    let channels = usize::from(audio.channels.max(1));
+    Loudness {
+        integrated_lufs: integrated_loudness(&audio.samples, audio.sample_rate, channels),
+        true_peak_dbtp: 20.0 * true_peak(&audio.samples, channels).log10(),
+    }
+}
+
+/// A direct-form I biquad filter.
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn apply(&self, input: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        input
+            .iter()
+            .map(|&x| {
+                let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2
+                    - self.a[0] * y1
+                    - self.a[1] * y2;
+                (x2, x1, y2, y1) = (x1, x, y1, y);
+                y
+            })
+            .collect()
+    }
+}
+
+/// The two K-weighting stages (high shelf, then high pass) for `rate` Hz.
+fn k_weighting(rate: u32) -> [Biquad; 2] {
+    let fs = f64::from(rate);
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Gated integrated loudness of interleaved `samples`.
+fn integrated_loudness(samples: &[f32], rate: u32, channels: usize) -> Option<f64> {
+    let frames = samples.len() / channels;
+    let block = (rate as usize * 4) / 10;
+    let step = block / 4;
+    if rate == 0 || frames < block {
+        return None;
+    }
+
+    // Squared K-weighted samples per channel
+    let [shelf, high_pass] = k_weighting(rate);
+    let weighted: Vec<Vec<f64>> = (0..channels)
+        .map(|c| {
+            let channel: Vec<f64> = samples
+                .iter()
+                .skip(c)
+                .step_by(channels)
+                .map(|&s| f64::from(s))
+                .collect();
+            high_pass
+                .apply(&shelf.apply(&channel))
+                .into_iter()
+                .map(|s| s * s)
+                .collect()
+        })
+        .collect();
+
+    let powers: Vec<f64> = (0..=(frames - block) / step)
+        .map(|i| {
+            let range = i * step..i * step + block;
+            weighted
+                .iter()
+                .map(|ch| ch[range.clone()].iter().sum::<f64>() / block as f64)
+                .sum()
+        })
+        .collect();
+
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<_> = powers
+            .iter()
+            .copied()
+            .filter(|&p| lufs(p) > threshold)
+            .collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+    let relative_gate = lufs(gated_mean(ABSOLUTE_GATE_LUFS)?) - RELATIVE_GATE_LU;
+    gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS)).map(lufs)
+}
+
+/// Loudness of a mean-square block power.
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Largest absolute value of the 4x oversampled signal.
+fn true_peak(samples: &[f32], channels: usize) -> f64 {
+    let filter = interpolation_filter();
+    let half = TAPS_PER_PHASE / 2;
+    let mut peak = 0.0f64;
+    for c in 0..channels {
+        let channel: Vec<f64> = samples
+            .iter()
+            .skip(c)
+            .step_by(channels)
+            .map(|&s| f64::from(s))
+            .collect();
+        for n in 0..channel.len() {
+            peak = peak.max(channel[n].abs());
+            for taps in &filter[1..] {
+                let value: f64 = taps
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(t, tap)| {
+                        let index = (n + t + 1).checked_sub(half)?;
+                        Some(channel.get(index)? * tap)
+                    })
+                    .sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+    peak
+}
+
+/// Hann-windowed sinc coefficients for each oversampled phase.
+fn interpolation_filter() -> Vec<Vec<f64>> {
+    let half = TAPS_PER_PHASE as f64 / 2.0;
+    (0..OVERSAMPLING)
+        .map(|phase| {
+            let offset = phase as f64 / OVERSAMPLING as f64;
+            (0..TAPS_PER_PHASE)
+                .map(|t| {
+                    // Distance from the interpolated point to tap `t`
+                    let x = t as f64 + 1.0 - half - offset;
+                    let sinc = if x == 0.0 {
+                        1.0
+                    } else {
+                        (PI * x).sin() / (PI * x)
+                    };
+                    let window = 0.5 + 0.5 * (PI * x / (half + 1.0)).cos();
+                    sinc * window
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, freq: f32, rate: u32, secs: f32) -> AudioData {
+        let samples = (0..(rate as f32 * secs) as usize)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin())
+            .collect();
+        AudioData::from_samples(samples, rate, 1).unwrap()
+    }
+
+    #[test]
+    fn test_sine_loudness_matches_reference() {
+        // A full-scale 997 Hz sine on one channel measures -3.01 LUFS,
+        // so amplitude 0.1 (-20 dB) measures about -23 LUFS.
+        let loudness = measure(&sine(0.1, 997.0, 48000, 3.0));
+        let lufs = loudness.integrated_lufs.unwrap();
+        assert!((lufs - -23.01).abs() < 0.3, "{lufs}");
+
+        let resampled = measure(&sine(0.1, 997.0, 16000, 3.0));
+        assert!((resampled.integrated_lufs.unwrap() - -23.01).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_true_peak_of_sine() {
+        let loudness = measure(&sine(0.5, 997.0, 48000, 1.0));
+        assert!((loudness.true_peak_dbtp - -6.02).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_silence_and_short_clips_have_no_loudness() {
+        let silence = AudioData::from_samples(vec![0.0; 48000], 48000, 1).unwrap();
+        assert_eq!(measure(&silence).integrated_lufs, None);
+        assert_eq!(measure(&sine(0.5, 997.0, 48000, 0.2)).integrated_lufs, None);
+    }
+}
@@ -1,9 +1,28 @@
 //! Input loading modules for images, audio, and video.
 
 pub mod audio;
+pub mod color;
+pub mod compressed;
 pub mod image;
+pub mod levels;
+pub mod loudness;
+pub mod npy;
+pub mod reference_video;
+pub mod remote_audio;
+pub mod resize;
+pub mod tonemap;
 pub mod video;
+pub mod wav_chunks;
+pub mod wav_repair;
+pub mod window;
 
-pub use audio::{AudioData, load_audio};
-pub use image::{ImageData, load_image};
+pub use audio::{
+    AudioData, AudioLoadOptions, MUSETALK_SAMPLE_RATE, encode_wav_base64, load_audio,
+    load_audio_with,
+};
+pub use image::{ImageData, ImageLoadOptions, encode_png, load_image, load_image_with};
+pub use reference_video::{ReferenceSpec, load_video_reference};
+pub use remote_audio::{AudioUrl, probe_remote_audio};
 pub use video::{VideoData, load_video};
+pub use wav_repair::WavFormat;
+pub use window::TimeWindow;
@@ -0,0 +1,101 @@
+//! NumPy `.npy` export of decoded audio samples (`--dump-samples`).
+//!
+//! Writes format version 1.0: the magic string, a little-endian header
+//! length, a Python dict literal describing the array, then the raw
+//! little-endian `f32` data. `numpy.load` reads the result directly.
+
+use super::AudioData;
+use crate::error::{CliError, Result};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+/// Encodes `samples` as a C-order `float32` array of the given shape.
+pub fn encode_npy(samples: &[f32], shape: &[usize]) -> Vec<u8> {
+    let dims = match shape {
+        [n] => format!("({n},)"),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {dims}, }}");
+    // Pad with spaces so the data starts on a 64-byte boundary
+    let unpadded = MAGIC.len() + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(MAGIC.len() + 2 + header.len() + samples.len() * 4);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for sample in samples {
+        out.extend_from_slice(&sample.to_le_bytes());
+    }
+    out
+}
+
+impl AudioData {
+    /// Writes the samples to `path` as a `(frames, channels)` array.
+    pub fn write_npy(&self, path: &Path) -> Result<()> {
+        let channels = usize::from(self.channels);
+        let npy = encode_npy(&self.samples, &[self.samples.len() / channels, channels]);
+        std::fs::write(path, npy).map_err(|e| {
+            CliError::AudioLoad(format!(
+                "Failed to write samples to {}: {e}",
+                path.display()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a version 1.0 `<f4` file back into its shape and values.
+    fn read_npy(bytes: &[u8]) -> (String, Vec<f32>) {
+        assert_eq!(&bytes[..8], MAGIC);
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.contains("'descr': '<f4'"), "{header}");
+        assert!(header.ends_with('\n'));
+        assert_eq!((10 + header_len) % 64, 0);
+        let shape = header
+            .split("'shape': ")
+            .nth(1)
+            .and_then(|s| s.split(')').next())
+            .map(|s| format!("{s})"))
+            .unwrap();
+        let values = bytes[10 + header_len..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        (shape, values)
+    }
+
+    #[test]
+    fn test_npy_round_trips_samples() {
+        let samples = vec![0.0, -1.0, 0.25, 1.0, -0.5, 0.125];
+        let audio = AudioData::from_samples(samples.clone(), 16000, 2).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.npy");
+
+        audio.write_npy(&path).unwrap();
+        let (shape, values) = read_npy(&std::fs::read(&path).unwrap());
+
+        assert_eq!(shape, "(3, 2)");
+        assert_eq!(values, samples);
+    }
+
+    #[test]
+    fn test_one_dimensional_shape_has_trailing_comma() {
+        let (shape, values) = read_npy(&encode_npy(&[0.5], &[1]));
+        assert_eq!(shape, "(1,)");
+        assert_eq!(values, [0.5]);
+    }
+}
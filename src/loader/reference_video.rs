@@ -0,0 +1,284 @@
+//! Video reference preprocessing: normalizing, looping, and trimming.
+//!
+//! The server drives lip-sync from the reference frames, so a reference
+//! shorter than the audio can run out of frames mid-clip, and a reference
+//! for a partial render must start at the same moment as the audio.
+//! Unusual codecs, sizes, or frame rates can also be normalized to a
+//! canonical spec before sending.
+
+use crate::error::{CliError, Result};
+use crate::ffmpeg::FfmpegConfig;
+use crate::loader::{VideoData, load_video};
+use crate::probe::{VideoStream, media_duration, video_stream};
+use crate::resolution::Resolution;
+use std::path::Path;
+use tempfile::TempPath;
+
+/// Number of extra repeats (`-stream_loop`) needed to cover `audio_secs`.
+///
+/// Returns 0 when the reference is already long enough.
+pub fn loop_count(reference_secs: f64, audio_secs: f64) -> u32 {
+    if reference_secs <= 0.0 || reference_secs >= audio_secs {
+        return 0;
+    }
+    ((audio_secs / reference_secs).ceil() as u32).saturating_sub(1)
+}
+
+/// Builds FFmpeg arguments that repeat `input` `loops` extra times.
+fn loop_args(input: &Path, loops: u32, output: &Path) -> Vec<String> {
+    vec![
+        "-y".to_string(),
+        "-stream_loop".to_string(),
+        loops.to_string(),
+        "-i".to_string(),
+        input.to_string_lossy().into_owned(),
+        "-c".to_string(),
+        "copy".to_string(),
+        output.to_string_lossy().into_owned(),
+    ]
+}
+
+/// Canonical format for `--normalize-reference`: H.264, yuv420p, at a fixed
+/// size and frame rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferenceSpec {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+}
+
+impl ReferenceSpec {
+    /// Builds a spec from a resolution and frame rate.
+    pub fn new(resolution: Resolution, fps: u32) -> Result<Self> {
+        let Resolution { width, height } = resolution;
+        if fps == 0 {
+            return Err(CliError::VideoLoad(
+                "frame rate must be positive".to_string(),
+            ));
+        }
+        Ok(Self { width, height, fps })
+    }
+
+    /// Returns true if `stream` already has this spec's format.
+    pub fn matches(&self, stream: &VideoStream) -> bool {
+        stream.codec == "h264"
+            && stream.pix_fmt == "yuv420p"
+            && (stream.width, stream.height) == (self.width, self.height)
+            && (stream.frame_rate == self.fps.to_string()
+                || stream.frame_rate == format!("{}/1", self.fps))
+    }
+}
+
+/// Builds FFmpeg arguments transcoding `input` to `spec`.
+///
+/// The picture is scaled to fit and padded, so the aspect ratio is kept.
+fn normalize_args(input: &Path, spec: &ReferenceSpec, output: &Path) -> Vec<String> {
+    let ReferenceSpec { width, height, fps } = *spec;
+    let filter = format!(
+        "scale={width}:{height}:force_original_aspect_ratio=decrease,\
+         pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,fps={fps}"
+    );
+    let mut args = vec![
+        "-y".to_string(),
+        "-i".to_string(),
+        input.to_string_lossy().into_owned(),
+    ];
+    args.extend(["-vf".to_string(), filter]);
+    args.extend(
+        [
+            "-c:v", "libx264", "-crf", "18", "-pix_fmt", "yuv420p", "-an",
+        ]
+        .map(String::from),
+    );
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+/// Transcodes the video reference to `spec` in a temp MP4.
+///
+/// Returns `None` when the reference already matches.
+pub fn normalize_reference(
+    ffmpeg: &FfmpegConfig,
+    path: &Path,
+    spec: &ReferenceSpec,
+) -> Result<Option<TempPath>> {
+    let stream = video_stream(ffmpeg, path)?;
+    if spec.matches(&stream) {
+        tracing::debug!("Reference already matches {spec:?}");
+        return Ok(None);
+    }
+    tracing::info!(
+        "Normalizing {} {}x{} @ {} reference to H.264 {}x{} @ {}",
+        stream.codec,
+        stream.width,
+        stream.height,
+        stream.frame_rate,
+        spec.width,
+        spec.height,
+        spec.fps
+    );
+    run_to_temp(ffmpeg, "normalize", |output| {
+        normalize_args(path, spec, output)
+    })
+    .map(Some)
+}
+
+/// Loads a video reference, first normalizing it to `normalize`, looping it
+/// to `loop_to_secs`, and cutting it to the `(start, end)` window when given.
+///
+/// Intermediate files are removed once the video is in memory.
+pub fn load_video_reference(
+    ffmpeg: &FfmpegConfig,
+    path: &Path,
+    normalize: Option<&ReferenceSpec>,
+    loop_to_secs: Option<f32>,
+    window: Option<(f64, f64)>,
+) -> Result<VideoData> {
+    let normalized = match normalize {
+        Some(spec) => normalize_reference(ffmpeg, path, spec)?,
+        None => None,
+    };
+    let path = normalized.as_deref().unwrap_or(path);
+    let looped = match loop_to_secs {
+        Some(secs) => loop_reference(ffmpeg, path, secs)?,
+        None => None,
+    };
+    let source = looped.as_deref().unwrap_or(path);
+    let trimmed = window
+        .map(|(start, end)| trim_reference(ffmpeg, source, start, end - start))
+        .transpose()?;
+    load_video(trimmed.as_deref().unwrap_or(source))
+}
+
+/// Loops the video reference into a temp MP4 lasting at least `audio_secs`.
+///
+/// Returns `None` when the reference is already long enough.
+pub fn loop_reference(
+    ffmpeg: &FfmpegConfig,
+    path: &Path,
+    audio_secs: f32,
+) -> Result<Option<TempPath>> {
+    let reference_secs = media_duration(ffmpeg, path)?;
+    let loops = loop_count(reference_secs, f64::from(audio_secs));
+    if loops == 0 {
+        return Ok(None);
+    }
+
+    tracing::info!("Looping {reference_secs:.2}s reference {loops} extra time(s) to cover audio");
+    run_to_temp(ffmpeg, "loop", |output| loop_args(path, loops, output)).map(Some)
+}
+
+/// Builds FFmpeg arguments that cut `duration` seconds of `input` from `start`.
+///
+/// The video is re-encoded so the cut lands on the exact frame.
+fn trim_args(input: &Path, start: f64, duration: f64, output: &Path) -> Vec<String> {
+    let mut args = vec!["-y".to_string(), "-ss".to_string(), format!("{start:.3}")];
+    args.extend(["-i".to_string(), input.to_string_lossy().into_owned()]);
+    args.extend(["-t".to_string(), format!("{duration:.3}")]);
+    args.extend(["-c:v", "libx264", "-crf", "18", "-an"].map(String::from));
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+/// Cuts the video reference to `duration` seconds starting at `start`.
+pub fn trim_reference(
+    ffmpeg: &FfmpegConfig,
+    path: &Path,
+    start: f64,
+    duration: f64,
+) -> Result<TempPath> {
+    tracing::info!("Trimming reference to {duration:.2}s from {start:.2}s");
+    run_to_temp(ffmpeg, "trim", |output| {
+        trim_args(path, start, duration, output)
+    })
+}
+
+/// Runs FFmpeg with arguments writing to a fresh temp MP4, returning its path.
+fn run_to_temp(
+    ffmpeg: &FfmpegConfig,
+    action: &str,
+    args: impl FnOnce(&Path) -> Vec<String>,
+) -> Result<TempPath> {
+    let output = tempfile::Builder::new()
+        .suffix(".mp4")
+        .tempfile()
+        .map_err(|e| CliError::VideoLoad(format!("Failed to create temp file: {e}")))?
+        .into_temp_path();
+
+    let result = ffmpeg
+        .ffmpeg()
+        .args(args(&output))
+        .output()
+        .map_err(|e| CliError::VideoLoad(format!("Failed to run ffmpeg: {e}")))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(CliError::VideoLoad(format!(
+            "Failed to {action} reference: {stderr}"
+        )));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_count() {
+        assert_eq!(loop_count(2.0, 5.0), 2);
+        assert_eq!(loop_count(2.0, 4.0), 1);
+        assert_eq!(loop_count(2.0, 4.1), 2);
+        assert_eq!(loop_count(6.0, 5.0), 0);
+        assert_eq!(loop_count(5.0, 5.0), 0);
+        assert_eq!(loop_count(0.0, 5.0), 0);
+    }
+
+    #[test]
+    fn test_loop_args() {
+        let args = loop_args(Path::new("ref.mp4"), 2, Path::new("out.mp4"));
+        assert_eq!(args[1..5], ["-stream_loop", "2", "-i", "ref.mp4"]);
+        assert_eq!(args.last().unwrap(), "out.mp4");
+    }
+
+    fn square() -> Resolution {
+        Resolution::new(512, 512).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_args() {
+        let spec = ReferenceSpec::new(square(), 25).unwrap();
+        let args = normalize_args(Path::new("ref.mov"), &spec, Path::new("out.mp4"));
+        assert_eq!(args[1..3], ["-i", "ref.mov"]);
+        assert!(args.windows(2).any(|w| w
+            == [
+                "-vf",
+                "scale=512:512:force_original_aspect_ratio=decrease,pad=512:512:(ow-iw)/2:(oh-ih)/2,fps=25"
+            ]));
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libx264"]));
+        assert!(args.windows(2).any(|w| w == ["-pix_fmt", "yuv420p"]));
+        assert_eq!(args.last().unwrap(), "out.mp4");
+    }
+
+    #[test]
+    fn test_matching_reference_skips_normalize() {
+        let spec = ReferenceSpec::new(square(), 30).unwrap();
+        let mut stream = VideoStream {
+            codec: "h264".to_string(),
+            width: 512,
+            height: 512,
+            frame_rate: "30/1".to_string(),
+            pix_fmt: "yuv420p".to_string(),
+        };
+        assert!(spec.matches(&stream));
+        stream.frame_rate = "30000/1001".to_string();
+        assert!(!spec.matches(&stream));
+        assert!(ReferenceSpec::new(square(), 0).is_err());
+    }
+
+    #[test]
+    fn test_trim_args_seek_before_input() {
+        let args = trim_args(Path::new("ref.mp4"), 30.0, 15.0, Path::new("out.mp4"));
+        assert_eq!(args[1..5], ["-ss", "30.000", "-i", "ref.mp4"]);
+        assert!(args.windows(2).any(|w| w == ["-t", "15.000"]));
+    }
+}
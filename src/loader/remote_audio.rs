@@ -0,0 +1,85 @@
+//! Audio the server fetches itself from a URL.
+//!
+//! The bytes are never uploaded; only the duration and format are probed
+//! locally so frame budgets and muxing still work.
+
+use super::audio::AudioData;
+use crate::error::{CliError, Result};
+use crate::ffmpeg::FfmpegConfig;
+use crate::probe;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// An `http` or `https` URL passed with `--audio-url`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioUrl(String);
+
+impl AudioUrl {
+    /// The URL as sent to the server.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The URL as an FFmpeg input, which reads HTTP sources directly.
+    pub fn as_path(&self) -> &Path {
+        Path::new(&self.0)
+    }
+}
+
+impl fmt::Display for AudioUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for AudioUrl {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let url =
+            reqwest::Url::parse(s).map_err(|e| CliError::InvalidAudioUrl(format!("'{s}': {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+            return Err(CliError::InvalidAudioUrl(format!(
+                "'{s}': expected an http:// or https:// URL"
+            )));
+        }
+        Ok(Self(url.into()))
+    }
+}
+
+/// Describes remote audio without downloading it into memory.
+///
+/// The returned audio has no samples or encoded WAV.
+pub fn probe_remote_audio(ffmpeg: &FfmpegConfig, url: &AudioUrl) -> Result<AudioData> {
+    let path = url.as_path();
+    let duration = probe::media_duration(ffmpeg, path)
+        .map_err(|e| CliError::AudioLoad(format!("Cannot probe {url}: {e}")))?;
+    let (sample_rate, channels) = probe::audio_format(ffmpeg, path)
+        .map_err(|e| CliError::AudioLoad(format!("Cannot probe {url}: {e}")))?;
+    Ok(AudioData {
+        sample_rate,
+        channels,
+        duration_secs: duration as f32,
+        samples: Vec::new(),
+        base64_wav: String::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_url_validation() {
+        let url: AudioUrl = "https://bucket.example.com/talk.wav".parse().unwrap();
+        assert_eq!(url.as_str(), "https://bucket.example.com/talk.wav");
+
+        for bad in ["talk.wav", "ftp://host/talk.wav", "http://", "not a url"] {
+            assert!(
+                matches!(bad.parse::<AudioUrl>(), Err(CliError::InvalidAudioUrl(_))),
+                "{bad}"
+            );
+        }
+    }
+}
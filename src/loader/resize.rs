@@ -0,0 +1,125 @@
+//! Resizing reference images to the requested `--resolution`.
+//!
+//! The server renders at the reference's size, so an image reference is
+//! brought to the exact `WxH` before it is sent. The image is scaled to fit
+//! and centered on black padding, so a face is never stretched. Lanczos3
+//! keeps facial detail sharp when downscaling large photos.
+
+use super::image::ImageData;
+use image::imageops::FilterType;
+
+/// Where an image scaled to fit inside a frame lands in that frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fit {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Fit {
+    /// The largest placement of a `source` sized image inside `frame` that
+    /// keeps its aspect ratio, centered.
+    pub fn within(source: (u32, u32), frame: (u32, u32)) -> Self {
+        let scale = (f64::from(frame.0) / f64::from(source.0))
+            .min(f64::from(frame.1) / f64::from(source.1));
+        let scaled = |len: u32, max: u32| ((f64::from(len) * scale).round() as u32).clamp(1, max);
+        let width = scaled(source.0, frame.0);
+        let height = scaled(source.1, frame.1);
+        Self {
+            x: (frame.0 - width) / 2,
+            y: (frame.1 - height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+/// Fits `img` inside `width` x `height` with Lanczos3, padding the rest
+/// with black.
+///
+/// Images already at that size are returned unchanged.
+pub fn resize_to_fit(img: &image::RgbImage, width: u32, height: u32) -> image::RgbImage {
+    if img.dimensions() == (width, height) {
+        return img.clone();
+    }
+    let fit = Fit::within(img.dimensions(), (width, height));
+    tracing::info!(
+        "Resizing reference from {}x{} to {}x{} inside {width}x{height}",
+        img.width(),
+        img.height(),
+        fit.width,
+        fit.height
+    );
+    let scaled = image::imageops::resize(img, fit.width, fit.height, FilterType::Lanczos3);
+    let mut framed = image::RgbImage::new(width, height);
+    image::imageops::replace(&mut framed, &scaled, fit.x.into(), fit.y.into());
+    framed
+}
+
+impl ImageData {
+    /// Where pixel `(x, y)` of the source landed in this image.
+    pub fn from_source(&self, x: u32, y: u32) -> (u32, u32) {
+        let (source_width, source_height) = self.source_size;
+        let fit = Fit::within(self.source_size, (self.width, self.height));
+        let map = |v: u32, len: u32, offset: u32, scaled: u32| {
+            offset + (u64::from(v) * u64::from(scaled) / u64::from(len)) as u32
+        };
+        (
+            map(x, source_width, fit.x, fit.width),
+            map(y, source_height, fit.y, fit.height),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{ImageLoadOptions, load_image_with};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resized_to_requested_dimensions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("avatar.png");
+        image::RgbImage::from_fn(100, 100, |x, y| image::Rgb([x as u8, y as u8, 0]))
+            .save(&path)
+            .unwrap();
+
+        let options = ImageLoadOptions {
+            resize_to: Some((64, 64)),
+            ..Default::default()
+        };
+        let data = load_image_with(&path, &options).unwrap();
+        assert_eq!((data.width, data.height), (64, 64));
+        assert_eq!(data.rgb_data.len(), 64 * 64 * 3);
+    }
+
+    #[test]
+    fn test_fit_keeps_aspect_ratio_and_pads() {
+        let img = image::RgbImage::from_pixel(200, 100, image::Rgb([255, 255, 255]));
+
+        let framed = resize_to_fit(&img, 64, 64);
+        assert_eq!(framed.dimensions(), (64, 64));
+        // 200x100 scales to 64x32, leaving 16 rows of padding above and below
+        assert_eq!(framed.get_pixel(32, 8), &image::Rgb([0, 0, 0]));
+        assert_eq!(framed.get_pixel(32, 32), &image::Rgb([255, 255, 255]));
+        assert_eq!(framed.get_pixel(32, 56), &image::Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_fit_within_centers() {
+        let fit = Fit::within((200, 100), (64, 64));
+        assert_eq!(
+            fit,
+            Fit {
+                x: 0,
+                y: 16,
+                width: 64,
+                height: 32
+            }
+        );
+        let fit = Fit::within((100, 300), (512, 512));
+        assert_eq!((fit.x, fit.width, fit.height), (170, 171, 512));
+    }
+}
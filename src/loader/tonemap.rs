@@ -0,0 +1,102 @@
+//! Tone-mapping of high bit-depth and HDR references down to 8-bit SDR.
+//!
+//! A plain 8-bit conversion clips HDR highlights; a Reinhard curve instead
+//! compresses them so bright detail survives.
+
+use image::{ColorType, DynamicImage, RgbImage};
+
+/// Target average luminance ("key") after exposure scaling.
+const KEY: f32 = 0.18;
+
+/// Display gamma used to linearize and re-encode values.
+const GAMMA: f32 = 2.2;
+
+/// Returns true if the image has more than 8 bits per channel.
+pub fn is_high_bit_depth(img: &DynamicImage) -> bool {
+    !matches!(
+        img.color(),
+        ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8
+    )
+}
+
+/// Returns true if an ICC profile describes an HDR (BT.2100 PQ/HLG) encoding.
+pub fn is_hdr_profile(icc: &[u8]) -> bool {
+    let contains = |needle: &[u8]| icc.windows(needle.len()).any(|w| w == needle);
+    contains(b"2100") || contains(b"HLG") || contains(b"SMPTE ST 2084")
+}
+
+/// Tone-maps an image to 8-bit RGB with the extended Reinhard operator.
+///
+/// Exposure is set from the log-average luminance, and the brightest pixel
+/// is used as the white point, so only it reaches full scale.
+pub fn reinhard(img: &DynamicImage) -> RgbImage {
+    let rgb = img.to_rgb32f();
+    let linear: Vec<[f32; 3]> = rgb
+        .pixels()
+        .map(|p| p.0.map(|c| c.max(0.0).powf(GAMMA)))
+        .collect();
+    let luminances: Vec<f32> = linear.iter().map(luminance).collect();
+
+    let count = luminances.len().max(1) as f32;
+    let log_average = (luminances.iter().map(|l| (l + 1e-4).ln()).sum::<f32>() / count).exp();
+    let exposure = KEY / log_average;
+    let white = luminances.iter().copied().fold(0.0, f32::max) * exposure;
+
+    let mut out = RgbImage::new(rgb.width(), rgb.height());
+    for ((pixel, color), lum) in out.pixels_mut().zip(&linear).zip(&luminances) {
+        let scaled = lum * exposure;
+        if scaled <= 0.0 {
+            continue;
+        }
+        let mapped = scaled * (1.0 + scaled / (white * white)) / (1.0 + scaled);
+        let ratio = mapped / lum;
+        pixel.0 = color.map(|c| {
+            let encoded = (c * ratio).min(1.0).powf(GAMMA.recip());
+            (encoded * 255.0).round() as u8
+        });
+    }
+    out
+}
+
+/// Relative luminance of a linear RGB pixel (BT.709 weights).
+fn luminance(rgb: &[f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    #[test]
+    fn test_16_bit_highlights_keep_detail() {
+        // A horizontal ramp whose right half is packed into the top highlights
+        let img: ImageBuffer<Rgb<u16>, Vec<u16>> = ImageBuffer::from_fn(256, 4, |x, _| {
+            let v = if x < 128 {
+                1000 + x as u16 * 256
+            } else {
+                60000 + (x as u16 - 128) * 40
+            };
+            Rgb([v, v, v])
+        });
+        let img = DynamicImage::ImageRgb16(img);
+        assert!(is_high_bit_depth(&img));
+
+        let mapped = reinhard(&img);
+        let highlights: Vec<u8> = (128..256).map(|x| mapped.get_pixel(x, 0).0[0]).collect();
+        let clipped = highlights.iter().filter(|&&v| v == 255).count();
+        assert!(clipped <= 4, "{clipped} highlight pixels clipped");
+        assert!(highlights.windows(2).all(|w| w[0] <= w[1]));
+        let mut distinct = highlights.clone();
+        distinct.dedup();
+        assert!(distinct.len() >= 16, "{distinct:?}");
+        assert!(mapped.get_pixel(0, 0).0[0] < 40);
+    }
+
+    #[test]
+    fn test_8_bit_and_sdr_profiles_not_hdr() {
+        assert!(!is_high_bit_depth(&DynamicImage::new_rgb8(1, 1)));
+        assert!(!is_hdr_profile(b"sRGB IEC61966-2.1"));
+        assert!(is_hdr_profile(b"desc Rec. ITU-R BT.2100 PQ"));
+    }
+}
@@ -15,26 +15,69 @@ pub struct VideoData {
 
 /// Loads a video from the given path.
 ///
-/// Reads the video file and encodes it as base64 for API transmission.
+/// Encodes the video file as base64 for API transmission. The file is
+/// memory-mapped so only the encoded copy lives on the heap, falling back
+/// to a plain read where mapping fails (e.g. empty files or special
+/// filesystems). The format is trusted from the already-validated file
+/// extension.
 pub fn load_video(path: &Path) -> Result<VideoData> {
     tracing::debug!("Loading video from: {}", path.display());
 
+    match map_file(path) {
+        Ok(map) => Ok(VideoData::encode(&map)),
+        Err(e) => {
+            tracing::debug!("Memory-mapping {} failed ({e}), reading it", path.display());
+            read_video(path)
+        }
+    }
+}
+
+/// Maps a file read-only.
+fn map_file(path: &Path) -> std::io::Result<memmap2::Mmap> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the map is read-only and only lives while the base64 copy is
+    // made; the reference is not expected to change during a render.
+    unsafe { memmap2::Mmap::map(&file) }
+}
+
+/// Loads a video by reading it into memory.
+fn read_video(path: &Path) -> Result<VideoData> {
     let bytes = std::fs::read(path)
         .map_err(|e| CliError::VideoLoad(format!("Failed to read video file: {e}")))?;
+    Ok(VideoData::encode(&bytes))
+}
+
+impl VideoData {
+    /// Wraps in-memory MP4 bytes, checking for the `ftyp` box signature.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if !is_mp4(bytes) {
+            return Err(CliError::VideoLoad(
+                "Unrecognized video data, expected MP4".to_string(),
+            ));
+        }
+        Ok(Self::encode(bytes))
+    }
 
-    let file_size = bytes.len() as u64;
-    let base64_mp4 = base64::engine::general_purpose::STANDARD.encode(&bytes);
+    fn encode(bytes: &[u8]) -> Self {
+        let file_size = bytes.len() as u64;
+        let base64_mp4 = base64::engine::general_purpose::STANDARD.encode(bytes);
 
-    tracing::info!(
-        "Loaded video: {} bytes (base64: {} chars)",
-        file_size,
-        base64_mp4.len()
-    );
+        tracing::info!(
+            "Loaded video: {} bytes (base64: {} chars)",
+            file_size,
+            base64_mp4.len()
+        );
 
-    Ok(VideoData {
-        base64_mp4,
-        file_size,
-    })
+        Self {
+            base64_mp4,
+            file_size,
+        }
+    }
+}
+
+/// Returns true if the bytes start with an ISO base media `ftyp` box.
+fn is_mp4(bytes: &[u8]) -> bool {
+    bytes.get(4..8) == Some(b"ftyp")
 }
 
 #[cfg(test)]
@@ -43,6 +86,31 @@ mod tests {
     use std::io::Write;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_mapped_and_read_paths_encode_identically() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("large.mp4");
+        let bytes: Vec<u8> = (0..3 * 1024 * 1024 + 7)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mapped = VideoData::encode(&map_file(&path).unwrap());
+        let read = read_video(&path).unwrap();
+        assert_eq!(mapped.base64_mp4, read.base64_mp4);
+        assert_eq!(mapped.file_size, bytes.len() as u64);
+    }
+
+    #[test]
+    fn test_empty_file_falls_back_to_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("empty.mp4");
+        std::fs::write(&path, b"").unwrap();
+
+        let data = load_video(&path).unwrap();
+        assert_eq!(data.file_size, 0);
+    }
+
     #[test]
     fn test_load_video_success() {
         let dir = tempdir().unwrap();
@@ -57,6 +125,29 @@ mod tests {
         assert_eq!(data.file_size, 16); // "fake mp4 content" is 16 bytes
     }
 
+    #[test]
+    fn test_video_from_mp4_bytes() {
+        let mut bytes = vec![0, 0, 0, 0x18];
+        bytes.extend_from_slice(b"ftypisom\0\0\x02\0isomiso2");
+
+        let data = VideoData::from_bytes(&bytes).unwrap();
+        assert_eq!(data.file_size, bytes.len() as u64);
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(&data.base64_mp4)
+                .unwrap(),
+            bytes
+        );
+    }
+
+    #[test]
+    fn test_video_from_unrecognized_bytes() {
+        assert!(matches!(
+            VideoData::from_bytes(b"fake mp4 content"),
+            Err(CliError::VideoLoad(_))
+        ));
+    }
+
     #[test]
     fn test_load_nonexistent_video() {
         let result = load_video(Path::new("nonexistent.mp4"));
@@ -0,0 +1,177 @@
+//! RIFF chunk walking for WAV files with extra or split chunks.
+//!
+//! Editors and recorders add `LIST`, `cue `, `bext`, and similar chunks,
+//! and some streaming writers split the samples over several `data` chunks.
+//! Rather than trusting the layout, the chunks are walked explicitly and the
+//! file is rewritten as a plain `fmt ` + `data` WAV.
+
+/// Chunks that are expected in a WAV and dropped without a warning.
+const EXPECTED_CHUNKS: [&[u8; 4]; 3] = [b"fmt ", b"fact", b"data"];
+
+/// One chunk of a RIFF file, with its payload clamped to the file length.
+struct Chunk<'a> {
+    id: [u8; 4],
+    payload: &'a [u8],
+    truncated: bool,
+}
+
+/// Splits the body of a `RIFF`/`WAVE` file into chunks.
+///
+/// Returns `None` when `bytes` isn't a WAVE file.
+fn chunks(bytes: &[u8]) -> Option<Vec<Chunk<'_>>> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id: [u8; 4] = bytes[offset..offset + 4].try_into().unwrap();
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let start = offset + 8;
+        let end = start.saturating_add(size).min(bytes.len());
+        chunks.push(Chunk {
+            id,
+            payload: &bytes[start..end],
+            truncated: end - start < size,
+        });
+        // Chunks are padded to an even length
+        offset = start.saturating_add(size).saturating_add(size & 1);
+    }
+    Some(chunks)
+}
+
+/// Rewrites a WAV whose chunks aren't exactly `fmt ` then one `data`.
+///
+/// Unexpected chunks are logged and dropped, multiple `data` chunks are
+/// joined in file order, and a `data` chunk claiming more bytes than the
+/// file holds is cut to what is there. Returns `None` when the file is
+/// already canonical, or lacks a `fmt ` or `data` chunk (left for the WAV
+/// parser to report).
+pub fn canonicalize_wav(bytes: &[u8]) -> Option<Vec<u8>> {
+    let chunks = chunks(bytes)?;
+    let canonical = matches!(
+        &chunks[..],
+        [fmt, data] if &fmt.id == b"fmt " && &data.id == b"data" && !data.truncated
+    );
+    if canonical {
+        return None;
+    }
+    let fmt = chunks.iter().find(|c| &c.id == b"fmt ")?;
+    let data: Vec<&Chunk> = chunks.iter().filter(|c| &c.id == b"data").collect();
+    if data.is_empty() {
+        return None;
+    }
+
+    let unexpected: Vec<String> = chunks
+        .iter()
+        .filter(|c| !EXPECTED_CHUNKS.contains(&&c.id))
+        .map(|c| format!("'{}'", String::from_utf8_lossy(&c.id)))
+        .collect();
+    if !unexpected.is_empty() {
+        tracing::warn!("Ignoring unexpected WAV chunks: {}", unexpected.join(", "));
+    }
+    if data.len() > 1 {
+        tracing::warn!("WAV has {} data chunks; joining them", data.len());
+    }
+    if data.iter().any(|c| c.truncated) {
+        tracing::warn!("WAV data chunk is shorter than its header claims");
+    }
+
+    let data_len: usize = data.iter().map(|c| c.payload.len()).sum();
+    let mut out = Vec::with_capacity(28 + fmt.payload.len() + data_len);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    push_chunk(&mut out, b"fmt ", &[fmt.payload]);
+    push_chunk(
+        &mut out,
+        b"data",
+        &data.iter().map(|c| c.payload).collect::<Vec<_>>(),
+    );
+    let riff_len = (out.len() - 8) as u32;
+    out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+    Some(out)
+}
+
+/// Appends a chunk made of `parts`, padding it to an even length.
+fn push_chunk(out: &mut Vec<u8>, id: &[u8; 4], parts: &[&[u8]]) {
+    let len: usize = parts.iter().map(|p| p.len()).sum();
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+    for part in parts {
+        out.extend_from_slice(part);
+    }
+    if len % 2 == 1 {
+        out.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_audio;
+    use tempfile::tempdir;
+
+    /// 16 kHz mono 16-bit PCM `fmt ` payload.
+    fn fmt_payload() -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&16000u32.to_le_bytes());
+        fmt.extend_from_slice(&32000u32.to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&16u16.to_le_bytes());
+        fmt
+    }
+
+    fn wav(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (id, payload) in chunks {
+            push_chunk(&mut out, id, &[payload]);
+        }
+        let riff_len = (out.len() - 8) as u32;
+        out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_extra_chunk_before_data_gives_correct_sample_count() {
+        let bytes = wav(&[
+            (b"fmt ", fmt_payload()),
+            (b"LIST", b"INFOISFT\x05\0\0\0tool\0".to_vec()),
+            (b"cue ", vec![0; 5]),
+            (b"data", vec![0; 16000 * 2]),
+        ]);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tagged.wav");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let audio = load_audio(&path).unwrap();
+        assert_eq!(audio.samples.len(), 16000);
+        assert!((audio.duration_secs - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_split_data_chunks_joined() {
+        let bytes = wav(&[
+            (b"fmt ", fmt_payload()),
+            (b"data", vec![1; 200]),
+            (b"LIST", vec![0; 3]),
+            (b"data", vec![2; 100]),
+        ]);
+        let canonical = canonicalize_wav(&bytes).unwrap();
+        let chunks = chunks(&canonical).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(&chunks[1].id, b"data");
+        assert_eq!(chunks[1].payload.len(), 300);
+        assert_eq!(chunks[1].payload[199..201], [1, 2]);
+    }
+
+    #[test]
+    fn test_canonical_or_non_wav_left_alone() {
+        let bytes = wav(&[(b"fmt ", fmt_payload()), (b"data", vec![0; 8])]);
+        assert!(canonicalize_wav(&bytes).is_none());
+        assert!(canonicalize_wav(b"not a wav file").is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Reconstruction of missing or malformed WAV headers (`--repair-wav`).
+//!
+//! Some TTS engines write PCM data behind a truncated or wrong RIFF header,
+//! or no header at all. Given the real format, a fresh 44-byte header is
+//! built around the sample data so the file can be parsed.
+
+use crate::error::{CliError, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Size of a canonical PCM WAV header.
+const HEADER_LEN: usize = 44;
+
+/// How far into a damaged file to look for the `data` chunk tag.
+const DATA_SEARCH_LIMIT: usize = 4096;
+
+/// PCM layout assumed for a file whose header can't be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+}
+
+impl WavFormat {
+    /// Bytes per sample frame (all channels).
+    fn block_align(self) -> usize {
+        usize::from(self.channels) * usize::from(self.bits_per_sample / 8)
+    }
+}
+
+impl Default for WavFormat {
+    /// MuseTalk's native 16 kHz mono 16-bit.
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            channels: 1,
+            bits_per_sample: 16,
+        }
+    }
+}
+
+/// Parses `RATE:CHANNELS:BITS`, e.g. `16000:1:16`.
+impl FromStr for WavFormat {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |why: &str| {
+            CliError::AudioLoad(format!(
+                "Invalid WAV format '{s}': {why} (expected RATE:CHANNELS:BITS, e.g. 16000:1:16)"
+            ))
+        };
+        let parts: Vec<&str> = s.split(':').collect();
+        let [rate, channels, bits] = parts[..] else {
+            return Err(invalid("need three fields"));
+        };
+        let format = Self {
+            sample_rate: rate.parse().map_err(|_| invalid("bad sample rate"))?,
+            channels: channels.parse().map_err(|_| invalid("bad channel count"))?,
+            bits_per_sample: bits.parse().map_err(|_| invalid("bad bit depth"))?,
+        };
+        if format.sample_rate == 0 || format.channels == 0 {
+            return Err(invalid("rate and channels must be positive"));
+        }
+        if ![8, 16, 24, 32].contains(&format.bits_per_sample) {
+            return Err(invalid("bits must be 8, 16, 24, or 32"));
+        }
+        Ok(format)
+    }
+}
+
+impl fmt::Display for WavFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.sample_rate, self.channels, self.bits_per_sample
+        )
+    }
+}
+
+/// Rebuilds a WAV file around the sample data in `bytes`.
+///
+/// The data starts after a `data` chunk tag if one is found, after a
+/// canonical header if the file at least starts with `RIFF`, and at the
+/// first byte otherwise. A trailing partial frame is dropped.
+pub fn repair_wav(bytes: &[u8], format: WavFormat) -> Vec<u8> {
+    let search = &bytes[..bytes.len().min(DATA_SEARCH_LIMIT)];
+    let start = match search.windows(4).position(|w| w == b"data") {
+        Some(pos) => (pos + 8).min(bytes.len()),
+        None if bytes.starts_with(b"RIFF") => HEADER_LEN.min(bytes.len()),
+        None => 0,
+    };
+    let data = &bytes[start..];
+    let data = &data[..data.len() - data.len() % format.block_align()];
+
+    let data_len = data.len() as u32;
+    let block_align = format.block_align() as u16;
+    let mut out = Vec::with_capacity(HEADER_LEN + data.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&format.channels.to_le_bytes());
+    out.extend_from_slice(&format.sample_rate.to_le_bytes());
+    out.extend_from_slice(&(format.sample_rate * u32::from(block_align)).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&format.bits_per_sample.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_parses_and_round_trips() {
+        let format: WavFormat = "22050:2:24".parse().unwrap();
+        assert_eq!(format.sample_rate, 22050);
+        assert_eq!(format.to_string(), "22050:2:24");
+        assert!("16000:1".parse::<WavFormat>().is_err());
+        assert!("16000:1:12".parse::<WavFormat>().is_err());
+    }
+
+    #[test]
+    fn test_headerless_data_gets_header_and_drops_partial_frame() {
+        let format = WavFormat {
+            channels: 2,
+            ..WavFormat::default()
+        };
+        let repaired = repair_wav(&[1u8; 4003], format);
+
+        assert_eq!(&repaired[..4], b"RIFF");
+        assert_eq!(repaired.len(), HEADER_LEN + 4000);
+        assert_eq!(&repaired[40..44], &4000u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,91 @@
+//! Time windows for rendering part of a clip.
+
+use crate::error::{CliError, Result};
+
+/// Slack allowed past the clip end, to absorb rounding in reported durations.
+const END_TOLERANCE_SECS: f64 = 0.01;
+
+/// A `--start-time`/`--end-time` window, either bound optional.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    start: Option<f64>,
+    end: Option<f64>,
+}
+
+impl TimeWindow {
+    /// Returns a window, or `None` when neither bound is set.
+    pub fn new(start: Option<f64>, end: Option<f64>) -> Option<Self> {
+        (start.is_some() || end.is_some()).then_some(Self { start, end })
+    }
+
+    /// Resolves the window against a clip of `clip_secs`, returning `(start, end)`.
+    ///
+    /// Fails unless the window is non-empty and lies within the clip.
+    pub fn resolve(&self, clip_secs: f64) -> Result<(f64, f64)> {
+        let start = self.start.unwrap_or(0.0);
+        let end = self.end.unwrap_or(clip_secs);
+        if start < 0.0 || start >= end {
+            return Err(CliError::AudioLoad(format!(
+                "Invalid time window {start:.2}s-{end:.2}s: start must be before end"
+            )));
+        }
+        if end > clip_secs + END_TOLERANCE_SECS {
+            return Err(CliError::AudioLoad(format!(
+                "Time window {start:.2}s-{end:.2}s is outside the {clip_secs:.2}s clip"
+            )));
+        }
+        Ok((start, end.min(clip_secs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::VideoAssembler;
+    use crate::test_support::tiny_png_base64;
+
+    #[test]
+    fn test_window_resolution() {
+        assert!(TimeWindow::new(None, None).is_none());
+
+        let window = TimeWindow::new(Some(30.0), Some(45.0)).unwrap();
+        assert_eq!(window.resolve(60.0).unwrap(), (30.0, 45.0));
+        assert!(window.resolve(40.0).is_err());
+
+        let open_end = TimeWindow::new(Some(30.0), None).unwrap();
+        assert_eq!(open_end.resolve(60.0).unwrap(), (30.0, 60.0));
+        let open_start = TimeWindow::new(None, Some(10.0)).unwrap();
+        assert_eq!(open_start.resolve(60.0).unwrap(), (0.0, 10.0));
+
+        assert!(
+            TimeWindow::new(Some(45.0), Some(30.0))
+                .unwrap()
+                .resolve(60.0)
+                .is_err()
+        );
+        assert!(
+            TimeWindow::new(Some(-1.0), None)
+                .unwrap()
+                .resolve(60.0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_window_frames_renumbered_from_zero() {
+        // Frames for a window starting at 30s are staged from index 0
+        let assembler = VideoAssembler::new(25).unwrap();
+        let frames = vec![tiny_png_base64(); 3];
+        assembler.stage_frames(&frames).unwrap();
+
+        let mut names: Vec<_> = std::fs::read_dir(assembler.frames_dir())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["frame_00000.png", "frame_00001.png", "frame_00002.png"]
+        );
+    }
+}
@@ -1,11 +1,22 @@
 //! MuseTalk CLI entry point.
 
-use anyhow::{Context, Result};
-use musetalk_cli::assembler::{VideoAssembler, check_ffmpeg};
-use musetalk_cli::client::{MuseTalkClient, ReferenceInput};
-use musetalk_cli::loader::{load_audio, load_image, load_video};
-use musetalk_cli::{Args, ReferenceType, validate_inputs};
+mod commands;
+mod report;
+mod stages;
+
+use anyhow::Result;
+use musetalk_cli::Args;
+use musetalk_cli::assembler::check_ffmpeg;
+use musetalk_cli::client::MuseTalkClient;
+use musetalk_cli::debug_bundle::{DebugBundle, SharedBundle};
+use musetalk_cli::events::{Event, EventStream};
+use musetalk_cli::hook::{HOOK_FAILED_EXIT_CODE, HookFailed};
+use musetalk_cli::profile;
+use stages::{Inference, Render};
+use std::path::Path;
+use std::time::Instant;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -20,158 +31,129 @@ async fn main() -> Result<()> {
         EnvFilter::new("info")
     };
 
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+    let (flame_layer, flame_guard) = match &args.profile {
+        Some(path) => {
+            let (layer, guard) = profile::flame_layer(path)?;
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(filter))
+        .with(flame_layer)
+        .init();
 
     tracing::debug!("Parsed arguments: {args:?}");
 
-    // Validate inputs and determine reference type
-    let ref_type = validate_inputs(&args.reference, &args.audio, &args.output)
-        .context("Input validation failed")?;
+    let bundle = args.debug_bundle.as_ref().map(|_| DebugBundle::shared());
+    let events = EventStream::from_args(&args)?;
+    let result = if let Some(manifest) = &args.batch {
+        commands::batch(&args, manifest, bundle.as_ref(), &events).await
+    } else if let Some(list) = &args.input_list {
+        commands::input_list(&args, list, bundle.as_ref(), &events).await
+    } else if let Some(dir) = &args.pair_dir {
+        commands::pair_dir(&args, dir, bundle.as_ref(), &events).await
+    } else if !args.audio_track.is_empty() {
+        commands::audio_tracks(&args, bundle.as_ref(), &events).await
+    } else {
+        run(&args, bundle.as_ref(), &events).await
+    };
+    // A failed hook was already reported as such; the render itself succeeded
+    if let Err(e) = &result
+        && !e.is::<HookFailed>()
+    {
+        events.emit(Event::Error {
+            message: format!("{e:#}"),
+        });
+    }
 
-    // Check FFmpeg availability
-    check_ffmpeg().context("FFmpeg check failed")?;
+    if let (Some(path), Some(bundle)) = (&args.debug_bundle, &bundle) {
+        write_debug_bundle(&args, path, bundle, &result);
+    }
 
-    // Dry run mode - exit after validation
-    if args.dry_run {
-        println!("Dry run: inputs validated successfully");
-        println!(
-            "  Reference: {} ({})",
-            args.reference.display(),
-            match ref_type {
-                ReferenceType::Image => "image",
-                ReferenceType::Video => "video",
-            }
-        );
-        println!("  Audio: {}", args.audio.display());
-        println!("  Output: {}", args.output.display());
-        println!("  Server: {}", args.server);
-        println!("  Resolution: {}", args.resolution);
-        println!("  FPS: {}", args.fps);
-        println!("  FFmpeg: available");
-        return Ok(());
+    if let (Some(path), Some(guard)) = (&args.profile, flame_guard) {
+        guard.flush()?;
+        drop(guard);
+        profile::print_report(path)?;
     }
 
-    // Load reference and audio
-    let audio_data = load_audio(&args.audio).context("Failed to load audio")?;
-    println!(
-        "Loaded audio: {:.2}s, {} Hz from {}",
-        audio_data.duration_secs,
-        audio_data.sample_rate,
-        args.audio.display()
-    );
-
-    // Load reference based on type
-    let image_data;
-    let video_data;
-    let reference_input = match ref_type {
-        ReferenceType::Image => {
-            image_data = load_image(&args.reference).context("Failed to load image")?;
-            println!(
-                "Loaded image: {}x{} from {}",
-                image_data.width,
-                image_data.height,
-                args.reference.display()
-            );
-            ReferenceInput::Image(&image_data)
-        }
-        ReferenceType::Video => {
-            video_data = load_video(&args.reference).context("Failed to load video")?;
-            println!(
-                "Loaded video: {} bytes from {}",
-                video_data.file_size,
-                args.reference.display()
-            );
-            ReferenceInput::Video(&video_data)
-        }
-    };
+    exit_on_hook_failure(result)
+}
 
-    // Try to connect to MuseTalk server
-    let client = MuseTalkClient::new(&args.server);
-    let server_available = match client.health_check().await {
-        Ok(health) => {
-            println!(
-                "Connected to MuseTalk server: {} (version: {})",
-                health.status,
-                health.version.unwrap_or_else(|| "unknown".to_string())
-            );
-            true
+/// Exits with [`HOOK_FAILED_EXIT_CODE`] when the render succeeded but
+/// `--post-hook` failed, so it isn't mistaken for a failed render.
+fn exit_on_hook_failure(result: Result<()>) -> Result<()> {
+    match result {
+        Err(e) if e.is::<HookFailed>() => {
+            eprintln!("Error: {e:#}");
+            std::process::exit(HOOK_FAILED_EXIT_CODE);
         }
-        Err(e) => {
-            tracing::warn!("Server not available: {e}");
-            println!("MuseTalk server not available at {}", args.server);
-            println!("Falling back to static video mode (no lip-sync)");
-            false
-        }
-    };
+        result => result,
+    }
+}
 
-    // Create video assembler
-    let assembler = VideoAssembler::new(args.fps).context("Failed to create video assembler")?;
-
-    if server_available {
-        // Request inference from server
-        println!("Requesting lip-sync inference...");
-        let response = client
-            .infer(reference_input, &audio_data, args.fps)
-            .await
-            .context("Inference request failed")?;
-
-        println!(
-            "Received {} frames, assembling video...",
-            response.total_frames
-        );
-
-        // Extract frame data
-        let frames: Vec<String> = response.frames.into_iter().map(|f| f.data).collect();
-
-        // Assemble video from frames
-        assembler
-            .assemble_from_frames(&frames, &args.audio, &args.output)
-            .context("Failed to assemble video")?;
-    } else {
-        // Fallback: create static video with image + audio (only works for image reference)
-        match ref_type {
-            ReferenceType::Image => {
-                let image_data = load_image(&args.reference).context("Failed to load image")?;
-                println!("Creating static video...");
-                assembler
-                    .assemble_static(
-                        &image_data,
-                        &audio_data,
-                        &args.reference,
-                        &args.audio,
-                        &args.output,
-                    )
-                    .context("Failed to create static video")?;
-            }
-            ReferenceType::Video => {
-                println!("Warning: Video reference requires server connection.");
-                println!("Cannot create fallback video from video reference.");
-                return Err(anyhow::anyhow!(
-                    "Server unavailable and video reference cannot be used for static fallback"
-                ));
-            }
-        }
+/// Writes `--debug-bundle`, recording the run's error if it failed.
+fn write_debug_bundle(args: &Args, path: &Path, bundle: &SharedBundle, result: &Result<()>) {
+    let mut bundle = bundle.lock().unwrap();
+    if let Err(e) = result {
+        bundle.error = Some(format!("{e:#}"));
+    }
+    let ffmpeg_version = check_ffmpeg(&args.ffmpeg).ok();
+    match bundle.write(path, ffmpeg_version.as_deref()) {
+        Ok(()) => println!("Debug bundle written to {}", path.display()),
+        Err(e) => tracing::warn!("Failed to write debug bundle: {e}"),
+    }
+}
+
+/// Runs the full pipeline for the parsed arguments.
+async fn run(args: &Args, bundle: Option<&SharedBundle>, events: &EventStream) -> Result<()> {
+    if let Some(result) = commands::standalone(args, bundle).await {
+        return result;
+    }
+    let inputs = stages::validate_inputs(args, events)?;
+    if args.dry_run {
+        report::dry_run(args, inputs.reference, inputs.ref_type, inputs.audio);
+        return Ok(());
+    }
+    if args.smoke_test {
+        return commands::smoke_test(args, bundle).await;
     }
 
-    // Report success
-    let output_size = std::fs::metadata(&args.output)
-        .map(|m| m.len())
-        .unwrap_or(0);
-    println!();
-    println!("Output video created successfully!");
-    println!("  File: {}", args.output.display());
-    println!("  Size: {:.2} MB", output_size as f64 / 1_000_000.0);
-    println!("  Duration: {:.2}s", audio_data.duration_secs);
-    println!("  FPS: {}", args.fps);
-
-    if !server_available {
-        println!();
-        println!("Note: This is a static video (no lip-sync).");
-        println!(
-            "Start a MuseTalk server at {} for lip-sync generation.",
-            args.server
-        );
+    let load_start = Instant::now();
+    let load_span = tracing::info_span!("load").entered();
+    let mut audio = stages::prepare_audio(args, inputs.audio, events)?;
+    let fps = stages::render_fps(args, audio.data.duration_secs);
+    let reference = stages::load_reference(args, &inputs, fps, audio.full_secs, audio.window)?;
+    drop(load_span);
+    stages::record_timing(bundle, "load", load_start);
+
+    let client =
+        MuseTalkClient::from_args(args, bundle)?.with_health_cache(stages::health_cache(args));
+    let server = stages::connect(args, &client, &inputs, &mut audio.data, events).await?;
+    let options = stages::inference_options(args, server.caps.as_ref(), fps, &reference)?;
+    let inference = Inference {
+        client: &client,
+        caps: server.caps.as_ref(),
+        reference: reference.input(),
+        options: &options,
+    };
+    if args.queue {
+        anyhow::ensure!(server.available, "--queue requires a reachable server");
+        return commands::queue_job(&inference, &audio.data).await;
     }
 
-    Ok(())
+    let render = Render::new(args, bundle, events, &audio, fps)?;
+    let assembler = render.assembler(inputs.codec)?;
+    let server_warnings = if server.available {
+        stages::render_with_server(&render, &inference, &assembler).await?
+    } else {
+        stages::render_offline(&render, inputs.reference, inputs.ref_type, &assembler)?;
+        Vec::new()
+    };
+    stages::finish(
+        &render,
+        inputs.reference,
+        server.available,
+        &server_warnings,
+    )
 }
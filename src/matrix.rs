@@ -0,0 +1,255 @@
+//! Server/input comparison matrix for validating a pool of servers.
+//!
+//! Every input is rendered on every server, one task per server, timing each
+//! request. Frames for an input are compared against the first server that
+//! rendered it, so a server drifting from the rest of the fleet stands out.
+//! A failed cell is recorded and the rest of the matrix still runs.
+
+use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput};
+use crate::compare::{decode_png_frames, mean_difference};
+use crate::error::Result;
+use crate::loader::{AudioData, ImageData};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A named audio input rendered on every server.
+#[derive(Debug, Clone)]
+pub struct MatrixInput {
+    pub name: String,
+    pub audio: AudioData,
+}
+
+/// Result for one server/input combination.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatrixCell {
+    pub server: String,
+    pub input: String,
+    /// Round-trip latency in seconds.
+    pub latency_secs: Option<f64>,
+    pub frames: Option<usize>,
+    /// `1.0 - mean difference` from the first server that rendered the input.
+    pub similarity: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Every cell of a comparison run, in server then input order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ServerMatrix {
+    pub cells: Vec<MatrixCell>,
+}
+
+impl ServerMatrix {
+    /// Number of cells that failed.
+    pub fn failed(&self) -> usize {
+        self.cells.iter().filter(|c| c.error.is_some()).count()
+    }
+
+    /// Renders the matrix as CSV with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("server,input,latency_secs,frames,similarity,error\n");
+        for cell in &self.cells {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                csv_field(&cell.server),
+                csv_field(&cell.input),
+                cell.latency_secs
+                    .map_or(String::new(), |s| format!("{s:.3}")),
+                cell.frames.map_or(String::new(), |n| n.to_string()),
+                cell.similarity.map_or(String::new(), |s| format!("{s:.4}")),
+                csv_field(cell.error.as_deref().unwrap_or_default()),
+            );
+        }
+        csv
+    }
+
+    /// Writes the matrix to `path`, as JSON for `.json` and CSV otherwise.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let contents = if is_json {
+            serde_json::to_string_pretty(self).expect("matrix serializes")
+        } else {
+            self.to_csv()
+        };
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, quote, or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// What one server produced for one input.
+struct Rendered {
+    latency_secs: f64,
+    frames: Vec<Vec<u8>>,
+}
+
+/// Renders every input on every server, concurrently across servers.
+///
+/// `servers` pairs each client with the label used in the report.
+pub async fn run_matrix(
+    servers: Vec<(String, MuseTalkClient)>,
+    reference: ImageData,
+    inputs: Vec<MatrixInput>,
+    options: InferenceOptions,
+) -> ServerMatrix {
+    let shared = Arc::new((reference, inputs, options));
+    let tasks: Vec<_> = servers
+        .into_iter()
+        .map(|(name, client)| {
+            let shared = Arc::clone(&shared);
+            let task = tokio::spawn(async move {
+                let (reference, inputs, options) = &*shared;
+                let mut results = Vec::new();
+                for input in inputs {
+                    results.push(render_cell(&client, reference, &input.audio, options).await);
+                }
+                results
+            });
+            (name, task)
+        })
+        .collect();
+
+    let mut rendered = Vec::new();
+    for (name, task) in tasks {
+        let results = task.await.unwrap_or_else(|e| {
+            let error = format!("server task failed: {e}");
+            shared.1.iter().map(|_| Err(error.clone())).collect()
+        });
+        rendered.push((name, results));
+    }
+    assemble(rendered, &shared.1)
+}
+
+/// Times one inference request and decodes its frames for comparison.
+async fn render_cell(
+    client: &MuseTalkClient,
+    reference: &ImageData,
+    audio: &AudioData,
+    options: &InferenceOptions,
+) -> std::result::Result<Rendered, String> {
+    let start = Instant::now();
+    let response = client
+        .infer(ReferenceInput::Image(reference), audio, options)
+        .await
+        .map_err(|e| e.to_string())?;
+    let latency_secs = start.elapsed().as_secs_f64();
+    let frames: Vec<String> = response.frames.into_iter().map(|f| f.data).collect();
+    let frames = decode_png_frames(&frames).map_err(|e| e.to_string())?;
+    Ok(Rendered {
+        latency_secs,
+        frames,
+    })
+}
+
+/// Builds report cells, comparing each input against its first good render.
+fn assemble(
+    rendered: Vec<(String, Vec<std::result::Result<Rendered, String>>)>,
+    inputs: &[MatrixInput],
+) -> ServerMatrix {
+    let baselines: Vec<Option<&Rendered>> = (0..inputs.len())
+        .map(|i| rendered.iter().find_map(|(_, r)| r[i].as_ref().ok()))
+        .collect();
+    let mut cells = Vec::new();
+    for (server, results) in &rendered {
+        for ((input, result), baseline) in inputs.iter().zip(results).zip(&baselines) {
+            let mut cell = MatrixCell {
+                server: server.clone(),
+                input: input.name.clone(),
+                latency_secs: None,
+                frames: None,
+                similarity: None,
+                error: None,
+            };
+            match result {
+                Ok(render) => {
+                    cell.latency_secs = Some(render.latency_secs);
+                    cell.frames = Some(render.frames.len());
+                    cell.similarity =
+                        baseline.map(|base| 1.0 - mean_difference(&render.frames, &base.frames));
+                }
+                Err(e) => cell.error = Some(e.clone()),
+            }
+            cells.push(cell);
+        }
+    }
+    ServerMatrix { cells }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        MockServer, frames_response, solid_frames_response, test_audio, test_image,
+    };
+
+    #[tokio::test]
+    async fn test_two_by_two_matrix() {
+        let black = MockServer::with_infer(|_| frames_response(2)).await;
+        let white = MockServer::with_infer(|req| {
+            if req.body.contains("c2Vjb25k") {
+                crate::test_support::MockResponse::status(400)
+            } else {
+                solid_frames_response(2, [255, 255, 255])
+            }
+        })
+        .await;
+        let second = AudioData {
+            base64_wav: "c2Vjb25k".to_string(),
+            ..test_audio()
+        };
+        let inputs = vec![
+            MatrixInput {
+                name: "first.wav".to_string(),
+                audio: test_audio(),
+            },
+            MatrixInput {
+                name: "second.wav".to_string(),
+                audio: second,
+            },
+        ];
+        let servers = vec![
+            ("a".to_string(), MuseTalkClient::new(black.url())),
+            ("b".to_string(), MuseTalkClient::new(white.url())),
+        ];
+
+        let matrix = run_matrix(servers, test_image(), inputs, InferenceOptions::new(25)).await;
+
+        assert_eq!(matrix.cells.len(), 4);
+        let cell = |server: &str, input: &str| {
+            matrix
+                .cells
+                .iter()
+                .find(|c| c.server == server && c.input == input)
+                .unwrap()
+        };
+        assert_eq!(cell("a", "first.wav").similarity, Some(1.0));
+        assert_eq!(cell("a", "second.wav").frames, Some(2));
+        assert!(cell("b", "first.wav").similarity.unwrap() < 0.01);
+        assert!(cell("b", "first.wav").latency_secs.is_some());
+        assert!(cell("b", "second.wav").error.is_some());
+        assert_eq!(matrix.failed(), 1);
+
+        let csv = matrix.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.starts_with("server,input,latency_secs"));
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+}
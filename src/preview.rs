@@ -0,0 +1,130 @@
+//! Fast low-resolution preview renders.
+//!
+//! Servers advertising `supports_preview` accept `preview: true` on an
+//! inference request and answer quickly with low-res frames, so the user can
+//! check the result while the full render runs.
+
+use crate::assembler::VideoAssembler;
+use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput, ServerCapabilities};
+use crate::error::Result;
+use crate::ffmpeg::FfmpegConfig;
+use crate::loader::AudioData;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::Instrument;
+
+/// Inputs for a preview render.
+pub struct PreviewRequest<'a> {
+    pub reference: ReferenceInput<'a>,
+    pub audio: &'a AudioData,
+    pub audio_path: &'a Path,
+    pub options: &'a InferenceOptions,
+    /// FFmpeg to assemble the preview with (`--ffmpeg-path`).
+    pub ffmpeg: &'a FfmpegConfig,
+}
+
+/// Renders a preview next to `output`, returning its path.
+///
+/// Returns `None` when the server doesn't advertise preview support.
+pub async fn render_preview(
+    client: &MuseTalkClient,
+    caps: Option<&ServerCapabilities>,
+    request: PreviewRequest<'_>,
+    output: &Path,
+) -> Result<Option<PathBuf>> {
+    if !caps.is_some_and(|c| c.supports_preview) {
+        return Ok(None);
+    }
+    let options = InferenceOptions {
+        preview: true,
+        ..request.options.clone()
+    };
+    let response = client
+        .infer(request.reference, request.audio, &options)
+        .instrument(tracing::info_span!("preview"))
+        .await?;
+
+    let frames: Vec<String> = response.frames.into_iter().map(|f| f.data).collect();
+    let path = preview_path(output);
+    VideoAssembler::new(options.fps)?
+        .with_ffmpeg(request.ffmpeg.clone())
+        .assemble_from_frames(&frames, request.audio_path, &path)?;
+    Ok(Some(path))
+}
+
+/// Path for a preview render next to `output` (`talk.mp4` -> `talk.preview.mp4`).
+pub fn preview_path(output: &Path) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    output.with_file_name(format!("{stem}.preview.mp4"))
+}
+
+/// Opens a video in the platform's default player without waiting for it.
+pub fn open_in_player(path: &Path) -> std::io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path).spawn().map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_path() {
+        assert_eq!(
+            preview_path(Path::new("out/talk.mp4")),
+            Path::new("out/talk.preview.mp4")
+        );
+        assert_eq!(
+            preview_path(Path::new("talk")),
+            Path::new("talk.preview.mp4")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_preview_uses_configured_ffmpeg() {
+        use crate::test_support::{MockServer, frames_response, test_audio, test_image};
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let stub = dir.path().join("ffmpeg-stub");
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\nfor last; do :; done\necho stub > \"$last\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let server = MockServer::with_infer(|_| frames_response(2)).await;
+        let caps = ServerCapabilities {
+            supports_preview: true,
+            ..Default::default()
+        };
+        let image = test_image();
+        let request = PreviewRequest {
+            reference: ReferenceInput::Image(&image),
+            audio: &test_audio(),
+            audio_path: &dir.path().join("audio.wav"),
+            options: &InferenceOptions::new(25),
+            ffmpeg: &FfmpegConfig::from_path(&stub).unwrap(),
+        };
+
+        let path = render_preview(
+            &MuseTalkClient::new(server.url()),
+            Some(&caps),
+            request,
+            &dir.path().join("talk.mp4"),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "stub\n");
+    }
+}
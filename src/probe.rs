@@ -0,0 +1,155 @@
+//! Media inspection via ffprobe.
+
+use crate::error::{CliError, Result};
+use crate::ffmpeg::FfmpegConfig;
+use std::path::Path;
+
+/// Returns true if ffprobe can be executed.
+pub fn ffprobe_available(ffmpeg: &FfmpegConfig) -> bool {
+    ffmpeg
+        .ffprobe()
+        .arg("-version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Returns the codec name of the first video stream (e.g. `h264`, `hevc`).
+pub fn video_codec(ffmpeg: &FfmpegConfig, path: &Path) -> Result<String> {
+    stream_entry(ffmpeg, path, Some("v:0"), "stream=codec_name")
+}
+
+/// Returns the codec name of the first audio stream (e.g. `aac`, `pcm_s16le`).
+pub fn audio_codec(ffmpeg: &FfmpegConfig, path: &Path) -> Result<String> {
+    stream_entry(ffmpeg, path, Some("a:0"), "stream=codec_name")
+}
+
+/// Format of a video stream as reported by ffprobe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoStream {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    /// Frame rate as a fraction, e.g. `30/1` or `30000/1001`.
+    pub frame_rate: String,
+    pub pix_fmt: String,
+}
+
+impl VideoStream {
+    /// Parses `key=value` lines from `-show_entries stream=...`.
+    fn parse(output: &str) -> Option<Self> {
+        let value = |key: &str| {
+            output
+                .lines()
+                .find_map(|l| l.trim().strip_prefix(key)?.strip_prefix('='))
+                .map(str::to_string)
+        };
+        Some(Self {
+            codec: value("codec_name")?,
+            width: value("width")?.parse().ok()?,
+            height: value("height")?.parse().ok()?,
+            frame_rate: value("r_frame_rate")?,
+            pix_fmt: value("pix_fmt")?,
+        })
+    }
+}
+
+/// Returns the codec, size, frame rate, and pixel format of the first video stream.
+pub fn video_stream(ffmpeg: &FfmpegConfig, path: &Path) -> Result<VideoStream> {
+    let output = ffmpeg
+        .ffprobe()
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args([
+            "-show_entries",
+            "stream=codec_name,width,height,r_frame_rate,pix_fmt",
+        ])
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(path)
+        .output()
+        .map_err(|e| CliError::Probe(format!("Failed to run ffprobe: {e}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CliError::Probe(format!(
+            "ffprobe failed on {}: {stderr}",
+            path.display()
+        )));
+    }
+    VideoStream::parse(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| CliError::Probe(format!("No video stream in {}", path.display())))
+}
+
+/// Returns the container duration in seconds.
+pub fn media_duration(ffmpeg: &FfmpegConfig, path: &Path) -> Result<f64> {
+    let value = stream_entry(ffmpeg, path, None, "format=duration")?;
+    value
+        .parse()
+        .map_err(|_| CliError::Probe(format!("Invalid duration '{value}' in {}", path.display())))
+}
+
+/// Returns the sample rate and channel count of the first audio stream.
+pub fn audio_format(ffmpeg: &FfmpegConfig, path: &Path) -> Result<(u32, u16)> {
+    let invalid = |entry: &str, value: &str| {
+        CliError::Probe(format!("Invalid {entry} '{value}' in {}", path.display()))
+    };
+    let rate = stream_entry(ffmpeg, path, Some("a:0"), "stream=sample_rate")?;
+    let channels = stream_entry(ffmpeg, path, Some("a:0"), "stream=channels")?;
+    Ok((
+        rate.parse().map_err(|_| invalid("sample rate", &rate))?,
+        channels
+            .parse()
+            .map_err(|_| invalid("channel count", &channels))?,
+    ))
+}
+
+/// Runs ffprobe for a single entry, optionally of the selected stream.
+fn stream_entry(
+    ffmpeg: &FfmpegConfig,
+    path: &Path,
+    stream: Option<&str>,
+    entry: &str,
+) -> Result<String> {
+    let mut command = ffmpeg.ffprobe();
+    command.args(["-v", "error"]);
+    if let Some(stream) = stream {
+        command.args(["-select_streams", stream]);
+    }
+    let output = command
+        .args(["-show_entries", entry])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .map_err(|e| CliError::Probe(format!("Failed to run ffprobe: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CliError::Probe(format!(
+            "ffprobe failed on {}: {stderr}",
+            path.display()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .ok_or_else(|| CliError::Probe(format!("No {entry} in {}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_stream() {
+        let output =
+            "codec_name=hevc\nwidth=1920\nheight=1080\npix_fmt=yuv420p10le\nr_frame_rate=25/1\n";
+        let stream = VideoStream::parse(output).unwrap();
+        assert_eq!(stream.codec, "hevc");
+        assert_eq!((stream.width, stream.height), (1920, 1080));
+        assert_eq!(stream.frame_rate, "25/1");
+        assert_eq!(stream.pix_fmt, "yuv420p10le");
+        assert!(VideoStream::parse("codec_name=h264\n").is_none());
+    }
+}
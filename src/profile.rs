@@ -0,0 +1,99 @@
+//! Timing profiles in folded-stack format.
+//!
+//! Spans are recorded by `tracing-flame`; the folded file can be rendered
+//! with `inferno-flamegraph`.
+
+use crate::error::{CliError, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tracing_flame::{FlameLayer, FlushGuard};
+
+/// Buffered writer for the folded output file.
+pub type FoldedWriter = BufWriter<File>;
+
+/// A flame layer and the guard that flushes it.
+pub type Profiler<S> = (FlameLayer<S, FoldedWriter>, FlushGuard<FoldedWriter>);
+
+/// Creates a flame layer writing folded stacks to `path`.
+///
+/// The returned guard flushes the file when dropped.
+pub fn flame_layer<S>(path: &Path) -> Result<Profiler<S>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let (layer, guard) =
+        FlameLayer::with_file(path).map_err(|e| CliError::Profile(e.to_string()))?;
+    Ok((
+        layer.with_threads_collapsed(true).with_file_and_line(false),
+        guard,
+    ))
+}
+
+/// Sums folded-stack samples by leaf span, in microseconds.
+pub fn summarize_folded(folded: &str) -> BTreeMap<String, u64> {
+    let mut totals = BTreeMap::new();
+    for line in folded.lines() {
+        let Some((stack, micros)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        let Ok(micros) = micros.parse::<u64>() else {
+            continue;
+        };
+        // Leaf frames look like "musetalk_cli::assembler::write_frame"
+        let leaf = stack.rsplit(';').next().unwrap_or(stack).trim();
+        let name = leaf.rsplit("::").next().unwrap_or(leaf);
+        *totals.entry(name.to_string()).or_insert(0) += micros;
+    }
+    totals
+}
+
+/// Prints a per-span timing report for a folded file.
+pub fn print_report(path: &Path) -> Result<()> {
+    let folded = std::fs::read_to_string(path)?;
+    println!("Profile ({}):", path.display());
+    for (span, micros) in summarize_folded(&folded) {
+        println!("  {span:<16} {:>10.3} ms", micros as f64 / 1000.0);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::VideoAssembler;
+    use crate::test_support::tiny_png_base64;
+    use tempfile::tempdir;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn test_folded_file_non_empty_after_staging() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("profile.folded");
+
+        let (layer, guard) = flame_layer(&path).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let assembler = VideoAssembler::new(30).unwrap();
+            let frames = vec![tiny_png_base64(); 3];
+            assembler.stage_frames(&frames).unwrap();
+        });
+        guard.flush().unwrap();
+
+        let folded = std::fs::read_to_string(&path).unwrap();
+        assert!(!folded.is_empty());
+        let summary = summarize_folded(&folded);
+        assert!(summary.contains_key("write_frame"));
+    }
+
+    #[test]
+    fn test_summarize_folded() {
+        let folded = "all-threads; musetalk_cli::inference 1500\n\
+                      all-threads; assembly; musetalk_cli::assembler::ffmpeg 700\n\
+                      all-threads; assembly; musetalk_cli::assembler::ffmpeg 300\n";
+        let summary = summarize_folded(folded);
+        assert_eq!(summary["inference"], 1500);
+        assert_eq!(summary["ffmpeg"], 1000);
+    }
+}
@@ -0,0 +1,217 @@
+//! Progress events from the inference and assembly stages.
+//!
+//! Front ends such as `--tui` consume these events; the plain console
+//! output does not need them, apart from a frame bar while assembling.
+
+use crate::assembler::FrameSink;
+use crate::error::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+/// Something that happened while rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// A named stage started.
+    StageStarted(String),
+    /// A stage finished after `elapsed`.
+    StageFinished { stage: String, elapsed: Duration },
+    /// The number of frames the server returned.
+    FramesExpected(usize),
+    /// Frame `index` was handed to the sink as PNG bytes.
+    Frame { index: usize, png: Vec<u8> },
+}
+
+/// Accumulated view of the progress events seen so far.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressState {
+    /// Stage currently running.
+    pub stage: Option<String>,
+    /// Finished stages and how long each took.
+    pub timings: Vec<(String, Duration)>,
+    /// Total frames, once the server has answered.
+    pub frames_expected: Option<usize>,
+    /// Frames handed to the sink.
+    pub frames_done: usize,
+    /// Index and PNG bytes of the most recent frame.
+    pub latest_frame: Option<(usize, Vec<u8>)>,
+}
+
+impl ProgressState {
+    /// Updates the state from one event.
+    pub fn apply(&mut self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::StageStarted(stage) => self.stage = Some(stage),
+            ProgressEvent::StageFinished { stage, elapsed } => {
+                if self.stage.as_deref() == Some(stage.as_str()) {
+                    self.stage = None;
+                }
+                self.timings.push((stage, elapsed));
+            }
+            ProgressEvent::FramesExpected(total) => self.frames_expected = Some(total),
+            ProgressEvent::Frame { index, png } => {
+                self.frames_done = self.frames_done.max(index + 1);
+                self.latest_frame = Some((index, png));
+            }
+        }
+    }
+
+    /// Fraction of the expected frames done, or 0 before the count is known.
+    pub fn fraction(&self) -> f64 {
+        match self.frames_expected {
+            Some(total) if total > 0 => (self.frames_done as f64 / total as f64).min(1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Forwards frames to an inner sink, reporting each one to `observer`.
+pub struct ProgressSink<S, F> {
+    inner: S,
+    observer: F,
+}
+
+impl<S: FrameSink, F: FnMut(ProgressEvent)> ProgressSink<S, F> {
+    /// Wraps `inner`, calling `observer` for every frame written.
+    pub fn new(inner: S, observer: F) -> Self {
+        Self { inner, observer }
+    }
+}
+
+impl<S: FrameSink, F: FnMut(ProgressEvent)> FrameSink for ProgressSink<S, F> {
+    fn write_frame(&mut self, index: usize, png: &[u8]) -> Result<()> {
+        self.inner.write_frame(index, png)?;
+        (self.observer)(ProgressEvent::Frame {
+            index,
+            png: png.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        self.inner.finish()
+    }
+}
+
+/// A console bar counting `total` frames written, with an ETA.
+///
+/// Hidden when not `enabled` (e.g. under `--quiet`); indicatif also hides it
+/// when stderr is not a terminal.
+pub fn frame_bar(total: usize, enabled: bool) -> ProgressBar {
+    if !enabled {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(total as u64);
+    if let Ok(style) =
+        ProgressStyle::with_template("{bar:40} {pos}/{len} frames written (ETA {eta})")
+    {
+        bar.set_style(style);
+    }
+    bar
+}
+
+/// Where progress events are shown: the `--tui` display, or nowhere.
+#[derive(Default)]
+pub struct ProgressDisplay {
+    #[cfg(feature = "tui")]
+    tui: Option<crate::tui::Tui>,
+}
+
+impl ProgressDisplay {
+    /// Starts the TUI when `enabled` and the terminal supports it.
+    ///
+    /// Falls back to the normal console output otherwise.
+    pub fn start(enabled: bool) -> Self {
+        if !enabled {
+            return Self::default();
+        }
+        #[cfg(feature = "tui")]
+        {
+            let tui = crate::tui::Tui::start();
+            if tui.is_none() {
+                tracing::warn!("Terminal does not support the TUI; using normal output");
+            }
+            Self { tui }
+        }
+        #[cfg(not(feature = "tui"))]
+        {
+            tracing::warn!("Built without the `tui` feature; using normal output");
+            Self::default()
+        }
+    }
+
+    /// Whether the TUI is running, so console output would garble it.
+    pub fn is_active(&self) -> bool {
+        #[cfg(feature = "tui")]
+        return self.tui.is_some();
+        #[cfg(not(feature = "tui"))]
+        false
+    }
+
+    /// Shows an event.
+    pub fn send(&mut self, event: ProgressEvent) {
+        #[cfg(feature = "tui")]
+        if let Some(tui) = &mut self.tui {
+            tui.update(event);
+        }
+        #[cfg(not(feature = "tui"))]
+        let _ = event;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::write_frames;
+    use crate::test_support::tiny_png_base64;
+
+    /// Discards frames.
+    struct NullSink;
+
+    impl FrameSink for NullSink {
+        fn write_frame(&mut self, _index: usize, _png: &[u8]) -> Result<()> {
+            Ok(())
+        }
+
+        fn finish(self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_state_updates_from_frame_events() {
+        let mut state = ProgressState::default();
+        state.apply(ProgressEvent::StageStarted("assembly".to_string()));
+        state.apply(ProgressEvent::FramesExpected(4));
+        assert_eq!(state.fraction(), 0.0);
+
+        let frames = vec![tiny_png_base64(); 3];
+        let sink = ProgressSink::new(NullSink, |event| state.apply(event));
+        write_frames(&frames, sink).unwrap();
+
+        assert_eq!(state.frames_done, 3);
+        assert_eq!(state.fraction(), 0.75);
+        let (index, png) = state.latest_frame.as_ref().unwrap();
+        assert_eq!(*index, 2);
+        assert!(png.starts_with(b"\x89PNG"));
+
+        state.apply(ProgressEvent::StageFinished {
+            stage: "assembly".to_string(),
+            elapsed: Duration::from_secs(2),
+        });
+        assert_eq!(state.stage, None);
+        assert_eq!(
+            state.timings,
+            [("assembly".to_string(), Duration::from_secs(2))]
+        );
+    }
+
+    #[test]
+    fn test_frame_bar_counts_frames() {
+        let bar = frame_bar(3, true);
+        let frames = vec![tiny_png_base64(); 3];
+        write_frames(&frames, ProgressSink::new(NullSink, |_| bar.inc(1))).unwrap();
+        assert_eq!((bar.position(), bar.length()), (3, Some(3)));
+
+        assert!(frame_bar(3, false).is_hidden());
+    }
+}
@@ -0,0 +1,85 @@
+//! Human-readable summaries printed after a dry run or render.
+
+use anyhow::{Context, Result};
+use musetalk_cli::assembler::OutputTarget;
+use musetalk_cli::compat::format_server_warnings;
+use musetalk_cli::{Args, ReferenceType, compare};
+use std::path::Path;
+
+/// Outcome of a finished render.
+pub struct RenderSummary<'a> {
+    pub outputs: &'a [&'a Path],
+    pub duration_secs: f32,
+    pub fps: u32,
+    pub server_warnings: &'a [String],
+    /// False when the static fallback was rendered instead.
+    pub lip_sync: bool,
+}
+
+/// Prints the inputs a dry run validated.
+pub fn dry_run(args: &Args, reference: &Path, ref_type: ReferenceType, audio: &Path) {
+    println!("Dry run: inputs validated successfully");
+    println!(
+        "  Reference: {} ({})",
+        reference.display(),
+        match ref_type {
+            ReferenceType::Image => "image",
+            ReferenceType::Video => "video",
+        }
+    );
+    println!("  Audio: {}", audio.display());
+    for output in args.output.iter().chain(&args.also_output) {
+        println!("  Output: {}", output.display());
+    }
+    println!("  Server: {}", args.server);
+    println!("  Resolution: {}", args.resolution);
+    println!("  FPS: {}", args.fps);
+    println!("  FFmpeg: available");
+}
+
+/// Prints the produced files, then runs the `--compare-to` regression check.
+pub fn success(args: &Args, summary: &RenderSummary<'_>) -> Result<()> {
+    println!();
+    println!("Output video created successfully!");
+    for file in summary.outputs {
+        println!("  File: {}", file.display());
+        if OutputTarget::detect(file).has_size() {
+            let output_size = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+            println!("  Size: {:.2} MB", output_size as f64 / 1_000_000.0);
+        }
+    }
+    println!("  Duration: {:.2}s", summary.duration_secs);
+    println!("  FPS: {}", summary.fps);
+    print!("{}", format_server_warnings(summary.server_warnings));
+
+    if let Some(golden) = &args.compare_to {
+        compare_to_golden(args, summary.outputs[0], golden)?;
+    }
+
+    if !summary.lip_sync {
+        println!();
+        println!("Note: This is a static video (no lip-sync).");
+        println!(
+            "Start a MuseTalk server at {} for lip-sync generation.",
+            args.server
+        );
+    }
+    Ok(())
+}
+
+/// Compares the rendered output against a golden video.
+fn compare_to_golden(args: &Args, output: &Path, golden: &Path) -> Result<()> {
+    let threshold = args.compare_threshold;
+    if !OutputTarget::detect(output).has_size() {
+        tracing::warn!("Cannot compare streamed output; skipping --compare-to");
+        return Ok(());
+    }
+    let difference =
+        compare::compare_videos(&args.ffmpeg, output, golden).context("Comparison failed")?;
+    println!(
+        "  Difference from {}: {difference:.4} (threshold {threshold:.4})",
+        golden.display()
+    );
+    compare::check_difference(difference, threshold).context("Regression check failed")?;
+    Ok(())
+}
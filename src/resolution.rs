@@ -0,0 +1,97 @@
+//! Output resolution (`--resolution WxH`).
+//!
+//! Parsed once at the command line (and in batch manifests), so a typo like
+//! `512` or `512xx512` is rejected up front instead of surfacing mid-render.
+
+use crate::error::{CliError, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// A `WxH` size in pixels; both dimensions are positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resolution {
+    /// A resolution of `width` x `height`; both must be positive.
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(CliError::InvalidResolution(format!(
+                "{width}x{height}, dimensions must be positive"
+            )));
+        }
+        Ok(Self { width, height })
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            CliError::InvalidResolution(format!(
+                "'{s}', expected WxH with positive dimensions (e.g. 512x512)"
+            ))
+        };
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+        let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+        let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+        Self::new(width, height).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for Resolution {
+    type Error = CliError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_resolutions() {
+        let hd: Resolution = "1280x720".parse().unwrap();
+        assert_eq!((hd.width, hd.height), (1280, 720));
+        assert_eq!(hd.to_string(), "1280x720");
+        assert_eq!(
+            "512x512".parse::<Resolution>().unwrap(),
+            Resolution::new(512, 512).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_resolutions_rejected() {
+        for malformed in ["abc", "0x0", "512", "axb", "0x512", "512x", ""] {
+            let err = malformed.parse::<Resolution>().unwrap_err();
+            assert!(matches!(err, CliError::InvalidResolution(_)), "{malformed}");
+        }
+        assert!(
+            "512"
+                .parse::<Resolution>()
+                .unwrap_err()
+                .to_string()
+                .contains("expected WxH")
+        );
+    }
+
+    #[test]
+    fn test_deserialized_from_string() {
+        let resolution: Resolution = serde_json::from_str(r#""640x360""#).unwrap();
+        assert_eq!(resolution, Resolution::new(640, 360).unwrap());
+        assert!(serde_json::from_str::<Resolution>(r#""640""#).is_err());
+    }
+}
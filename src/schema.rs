@@ -0,0 +1,82 @@
+//! Friendlier errors for config and manifest files.
+//!
+//! Config and manifest types reject unknown keys. Serde reports those as
+//! "unknown field `x`, expected one of ..."; this adds a suggestion for the
+//! closest valid key, since a typo is the usual cause.
+
+/// Appends a "did you mean" hint to a serde error message, if one applies.
+///
+/// The original message, including any line and column, is kept.
+pub fn explain(message: &str) -> String {
+    match suggestion(message) {
+        Some((unknown, suggested)) => {
+            format!("{message}\n(`{unknown}` is not a valid key; did you mean `{suggested}`?)")
+        }
+        None => message.to_string(),
+    }
+}
+
+/// Finds the unknown field in a serde message and the closest expected field.
+fn suggestion(message: &str) -> Option<(&str, &str)> {
+    let rest = &message[message.find("unknown field `")? + "unknown field `".len()..];
+    let (unknown, rest) = rest.split_once('`')?;
+    let expected = rest.split_once("expected")?.1.lines().next()?;
+    let candidates = expected.split('`').skip(1).step_by(2);
+    let suggested = closest(unknown, candidates)?;
+    Some((unknown, suggested))
+}
+
+/// Returns the candidate nearest to `name`, if it is close enough to be a typo.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(2);
+    candidates
+        .into_iter()
+        .map(|c| (levenshtein(name, c), c))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, c)| c)
+}
+
+/// Edit distance between two strings, counted in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("server", "server"), 0);
+        assert_eq!(levenshtein("srever", "server"), 2);
+        assert_eq!(levenshtein("fps", "fpss"), 1);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_closest_ignores_distant_names() {
+        let keys = ["server", "model", "fps"];
+        assert_eq!(closest("servr", keys), Some("server"));
+        assert_eq!(closest("resolution", keys), None);
+    }
+
+    #[test]
+    fn test_explain_suggests_expected_field() {
+        let message = "unknown field `outptu`, expected one of `reference`, `audio`, `output` \
+                       at line 1 column 9";
+        assert!(explain(message).contains("did you mean `output`?"));
+        assert_eq!(explain("missing field `jobs`"), "missing field `jobs`");
+    }
+}
@@ -0,0 +1,175 @@
+//! End-to-end connectivity smoke test and model warmup.
+//!
+//! Sits between `--dry-run` (no network) and a full render: it checks
+//! health and capabilities, then sends a tiny synthetic inference and
+//! discards the result. The same tiny request warms up a cold model.
+
+use crate::benchmark::synthetic_inputs;
+use crate::client::{InferenceOptions, MuseTalkClient, ReferenceInput, ServerCapabilities};
+use crate::error::{CliError, Result};
+use std::fmt;
+use std::time::Instant;
+
+/// Seconds of synthetic audio sent by the smoke test.
+const SMOKE_AUDIO_SECS: f32 = 0.5;
+
+/// Outcome of a successful smoke test.
+#[derive(Debug, Clone)]
+pub struct SmokeReport {
+    /// Server status from `/health`.
+    pub status: String,
+    /// Server version from `/health`, if reported.
+    pub version: Option<String>,
+    /// Advertised capabilities, if the server exposes them.
+    pub capabilities: Option<ServerCapabilities>,
+    /// Frames returned for the synthetic request.
+    pub frames: usize,
+    /// Round-trip latency of the synthetic request in seconds.
+    pub latency_secs: f64,
+}
+
+impl fmt::Display for SmokeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Smoke test passed")?;
+        writeln!(
+            f,
+            "  Server: {} (version {})",
+            self.status,
+            self.version.as_deref().unwrap_or("unknown")
+        )?;
+        let caps = if self.capabilities.is_some() {
+            "advertised"
+        } else {
+            "not advertised"
+        };
+        writeln!(f, "  Capabilities: {caps}")?;
+        write!(
+            f,
+            "  Inference: {} frames in {:.2}s",
+            self.frames, self.latency_secs
+        )
+    }
+}
+
+/// Runs health, capabilities, and a tiny inference against the server.
+pub async fn smoke_test(
+    client: &MuseTalkClient,
+    options: &InferenceOptions,
+) -> Result<SmokeReport> {
+    let health = client.health_check().await?;
+    let capabilities = client.capabilities().await.unwrap_or_else(|e| {
+        tracing::debug!("Capabilities unavailable: {e}");
+        None
+    });
+
+    let (image, audio) = synthetic_inputs(SMOKE_AUDIO_SECS)?;
+    let start = Instant::now();
+    let response = client
+        .infer(ReferenceInput::Image(&image), &audio, options)
+        .await?;
+    if response.frames.is_empty() {
+        return Err(CliError::ServerConnection(
+            "Smoke test inference returned no frames".to_string(),
+        ));
+    }
+
+    Ok(SmokeReport {
+        status: health.status,
+        version: health.version,
+        capabilities,
+        frames: response.frames.len(),
+        latency_secs: start.elapsed().as_secs_f64(),
+    })
+}
+
+/// Sends a tiny synthetic inference so the model is loaded before real work.
+///
+/// Skipped when the capabilities say the model is already loaded. Failures
+/// are logged and ignored. Returns true if a warmup request was sent.
+pub async fn warm_up(
+    client: &MuseTalkClient,
+    caps: Option<&ServerCapabilities>,
+    options: &InferenceOptions,
+) -> Result<bool> {
+    if caps.is_some_and(|c| c.model_loaded) {
+        tracing::debug!("Model already loaded, skipping warmup");
+        return Ok(false);
+    }
+    let (image, audio) = synthetic_inputs(SMOKE_AUDIO_SECS)?;
+    let start = Instant::now();
+    match client
+        .infer(ReferenceInput::Image(&image), &audio, options)
+        .await
+    {
+        Ok(_) => tracing::info!("Warmup took {:.2}s", start.elapsed().as_secs_f64()),
+        Err(e) => tracing::warn!("Warmup request failed: {e}"),
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer, frames_response};
+
+    #[tokio::test]
+    async fn test_smoke_test_checks_health_and_infers() {
+        let server = MockServer::with_infer(|_| frames_response(2)).await;
+        let client = MuseTalkClient::new(server.url());
+
+        let report = smoke_test(&client, &InferenceOptions::new(25))
+            .await
+            .unwrap();
+
+        assert_eq!(report.status, "ok");
+        assert_eq!(report.frames, 2);
+        assert!(report.capabilities.is_none());
+        let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/health", "/capabilities", "/infer"]);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_precedes_main_request() {
+        let server = MockServer::with_infer(|_| frames_response(1)).await;
+        let client = MuseTalkClient::new(server.url());
+        let options = InferenceOptions::new(25);
+
+        assert!(warm_up(&client, None, &options).await.unwrap());
+        let (image, audio) = synthetic_inputs(2.0).unwrap();
+        client
+            .infer(ReferenceInput::Image(&image), &audio, &options)
+            .await
+            .unwrap();
+
+        let requests = server.requests_to("/infer");
+        assert_eq!(requests.len(), 2);
+        let audio_len = |i: usize| requests[i].json()["audio"].as_str().unwrap().len();
+        assert!(audio_len(0) < audio_len(1));
+
+        let loaded = ServerCapabilities {
+            model_loaded: true,
+            ..Default::default()
+        };
+        assert!(!warm_up(&client, Some(&loaded), &options).await.unwrap());
+        assert_eq!(server.requests_to("/infer").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_smoke_test_fails_on_empty_response() {
+        let server = MockServer::with_infer(|_| frames_response(0)).await;
+        let client = MuseTalkClient::new(server.url());
+        assert!(
+            smoke_test(&client, &InferenceOptions::new(25))
+                .await
+                .is_err()
+        );
+
+        let server = MockServer::start(|_| MockResponse::status(503)).await;
+        let client = MuseTalkClient::new(server.url());
+        assert!(
+            smoke_test(&client, &InferenceOptions::new(25))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Loading and editing the input audio before inference.
+
+use super::warn_clipping;
+use anyhow::{Context, Result};
+use musetalk_cli::Args;
+use musetalk_cli::events::{Event, EventStream};
+use musetalk_cli::loader::levels::TARGET_RMS_DBFS;
+use musetalk_cli::loader::{
+    AudioData, AudioLoadOptions, TimeWindow, load_audio_with, probe_remote_audio,
+};
+use musetalk_cli::validation::{check_sample_rate, validate_audio_duration};
+use std::path::{Path, PathBuf};
+use tempfile::TempPath;
+
+/// The audio sent to the server, and the file muxed with its frames.
+pub struct PreparedAudio {
+    /// Samples sent to the server.
+    pub data: AudioData,
+    /// Length of the input before `--start-time`/`--end-time`.
+    pub full_secs: f32,
+    /// The rendered part of the input, in seconds.
+    pub window: Option<(f64, f64)>,
+    source: PathBuf,
+    edited: Option<TempPath>,
+}
+
+impl PreparedAudio {
+    /// The file FFmpeg muxes: the input, or a WAV of the edited samples.
+    pub fn muxed_path(&self) -> &Path {
+        self.edited.as_deref().unwrap_or(&self.source)
+    }
+
+    /// The input file, before any edits.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+}
+
+/// Loads `--audio` (or probes `--audio-url`) and applies every audio option.
+pub fn prepare_audio(args: &Args, audio: &Path, events: &EventStream) -> Result<PreparedAudio> {
+    let mut data = load(args, audio, events)?;
+    let full_secs = data.duration_secs;
+    let window = TimeWindow::new(args.start_time, args.end_time)
+        .map(|w| w.resolve(f64::from(full_secs)))
+        .transpose()
+        .context("Audio validation failed")?;
+    if let Some((start, end)) = window {
+        println!("Rendering {start:.2}s-{end:.2}s of the audio");
+        data = data.slice(start, end)?;
+    }
+    validate_audio_duration(&data, args.min_audio_duration).context("Audio validation failed")?;
+    data = edit(args, data)?;
+    // The muxed audio must match what the server renders against, and a
+    // repaired file can't be muxed from its broken original
+    let edited = (window.is_some()
+        || args.lead_in.is_some()
+        || args.audio_gain.is_some()
+        || args.two_pass_audio_analysis
+        || args.repair_wav)
+        .then(|| data.to_temp_wav())
+        .transpose()?;
+    let data = condition(args, data)?;
+    warn_clipping(&data, events);
+    if let Some(path) = &args.dump_samples {
+        data.write_npy(path)?;
+        println!("Audio samples written to {}", path.display());
+    }
+    Ok(PreparedAudio {
+        data,
+        full_secs,
+        window,
+        source: audio.to_path_buf(),
+        edited,
+    })
+}
+
+fn load(args: &Args, audio: &Path, events: &EventStream) -> Result<AudioData> {
+    let data = match &args.audio_url {
+        Some(url) => probe_remote_audio(&args.ffmpeg, url),
+        None => load_audio_with(audio, &audio_options(args)),
+    }
+    .context("Failed to load audio")?;
+    println!(
+        "Loaded audio: {:.2}s, {} Hz from {}",
+        data.duration_secs,
+        data.sample_rate,
+        audio.display()
+    );
+    events.emit(Event::AudioLoaded {
+        duration_secs: data.duration_secs,
+        sample_rate: data.sample_rate,
+        channels: data.channels,
+    });
+    Ok(data)
+}
+
+/// Applies `--lead-in`, `--audio-gain`, and `--two-pass-audio-analysis`,
+/// which change what is heard.
+fn edit(args: &Args, mut data: AudioData) -> Result<AudioData> {
+    if let Some(millis) = args.lead_in {
+        data = data.with_lead_in(millis)?;
+    }
+    if let Some(db) = args.audio_gain {
+        data = data.with_gain(db).context("Failed to apply audio gain")?;
+    }
+    if args.two_pass_audio_analysis {
+        data = data
+            .normalize_rms(TARGET_RMS_DBFS)
+            .context("Failed to normalize audio")?;
+    }
+    Ok(data)
+}
+
+/// Converts the samples into the form the server expects.
+fn condition(args: &Args, mut data: AudioData) -> Result<AudioData> {
+    if args.preprocess_audio {
+        data = data
+            .preprocess_for_musetalk()
+            .context("Failed to preprocess audio")?;
+    } else {
+        // Judged on the source: resampling can't restore what an odd rate lost.
+        // Only `--audio-url` audio goes out unconverted, so only it is refused.
+        match check_sample_rate(data.sample_rate) {
+            Err(e) if args.strict && args.audio_url.is_some() => {
+                return Err(e).context("Audio validation failed");
+            }
+            Err(e) => tracing::warn!("{e}"),
+            Ok(()) => {}
+        }
+        if args.audio_url.is_none() {
+            data = data
+                .resample_to(args.target_sample_rate)
+                .context("Failed to resample audio")?;
+        }
+    }
+    if args.mono && data.channels > 1 {
+        data = data.to_mono().context("Failed to downmix audio")?;
+    }
+    Ok(data)
+}
+
+/// Audio loading options selected by `--repair-wav` and `--assume-format`.
+pub fn audio_options(args: &Args) -> AudioLoadOptions {
+    AudioLoadOptions {
+        repair: args.repair_wav.then_some(args.assume_format),
+    }
+}
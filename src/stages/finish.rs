@@ -0,0 +1,57 @@
+//! Checks and reporting once the outputs are written.
+
+use super::{Render, assert_durations, remember_last_good};
+use crate::report::{self, RenderSummary};
+use anyhow::Result;
+use musetalk_cli::events::Event;
+use musetalk_cli::hook::HookFailed;
+use std::path::Path;
+
+/// Verifies and reports the finished outputs, then runs `--post-hook`.
+///
+/// `lip_sync` is false when the static fallback was rendered instead. A
+/// failing hook still lets every output be reported as done, then returns
+/// [`HookFailed`].
+pub fn finish(
+    render: &Render<'_>,
+    reference: &Path,
+    lip_sync: bool,
+    server_warnings: &[String],
+) -> Result<()> {
+    let args = render.args;
+    assert_durations(args, &render.outputs)?;
+    report::success(
+        args,
+        &RenderSummary {
+            outputs: &render.outputs,
+            duration_secs: render.audio.data.duration_secs,
+            fps: render.fps,
+            server_warnings,
+            lip_sync,
+        },
+    )?;
+    if args.stale_ok && lip_sync {
+        remember_last_good(render, reference);
+    }
+    let mut failures = 0;
+    if let Some(hook) = &args.post_hook {
+        for output in &render.outputs {
+            if let Err(e) = hook.run(output, render.audio.data.duration_secs) {
+                // On stderr whatever the log level, apart from render errors
+                eprintln!("Post-hook failed for {}: {e}", output.display());
+                render.events.emit(Event::PostHookFailed {
+                    output: output.to_path_buf(),
+                    message: e.to_string(),
+                });
+                failures += 1;
+            }
+        }
+    }
+    render.events.emit(Event::Done {
+        outputs: render.outputs.iter().map(|p| p.to_path_buf()).collect(),
+    });
+    if failures > 0 {
+        return Err(HookFailed { failures }.into());
+    }
+    Ok(())
+}
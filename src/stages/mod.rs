@@ -0,0 +1,435 @@
+//! Pipeline helpers shared by the render and the standalone commands.
+
+mod audio;
+mod finish;
+mod pipeline;
+mod render;
+
+pub use audio::{PreparedAudio, audio_options, prepare_audio};
+pub use finish::finish;
+pub use render::{Inference, Render, render_offline, render_with_server};
+
+use anyhow::{Context, Result};
+use musetalk_cli::assembler::{OutputTarget, VideoCodec, check_ffmpeg, resolve_codec};
+use musetalk_cli::client::{
+    Frame, HealthCache, InferenceOptions, MuseTalkClient, ReferenceInput, ServerCapabilities,
+    UpscaleClient, payload,
+};
+use musetalk_cli::compat::{check_server_compatibility, server_assembly_format};
+use musetalk_cli::debug_bundle::SharedBundle;
+use musetalk_cli::events::{Event, EventStream};
+use musetalk_cli::landmarks::LandmarkSidecar;
+use musetalk_cli::last_good::{KeySettings, LastGoodCache};
+use musetalk_cli::loader::{
+    AudioData, ImageData, ImageLoadOptions, ReferenceSpec, VideoData, load_image_with,
+    load_video_reference,
+};
+use musetalk_cli::resolution::Resolution;
+use musetalk_cli::validation::{
+    fps_for_frame_budget, snap_fps, validate_audio_path, validate_output_path,
+    validate_reference_path,
+};
+use musetalk_cli::{Args, ReferenceType};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use std::time::Instant;
+use tracing::Instrument;
+
+/// The validated inputs of a single render.
+pub struct Inputs<'a> {
+    pub reference: &'a Path,
+    /// `--audio`, or `--audio-url` as a path.
+    pub audio: &'a Path,
+    pub ref_type: ReferenceType,
+    /// `--codec` after any `--codec-fallback`.
+    pub codec: Option<VideoCodec>,
+}
+
+/// Validates the reference, audio, and outputs, and checks FFmpeg and the
+/// requested codec.
+pub fn validate_inputs<'a>(args: &'a Args, events: &EventStream) -> Result<Inputs<'a>> {
+    let reference = required_path(&args.reference, "--reference")?;
+    let audio = match &args.audio_url {
+        Some(url) => url.as_path(),
+        None => required_path(&args.audio, "--audio")?,
+    };
+
+    // Determine the reference type (--queue has no output)
+    let ref_type = validate_reference_path(reference)
+        .and_then(|ref_type| {
+            if args.audio_url.is_none() {
+                validate_audio_path(audio)?;
+            }
+            for output in args.output.iter().chain(&args.also_output) {
+                validate_output_path(output)?;
+            }
+            Ok(ref_type)
+        })
+        .context("Input validation failed")?;
+    if let Some(golden) = args.compare_to.as_ref().filter(|p| !p.exists()) {
+        anyhow::bail!("Golden video not found: {}", golden.display());
+    }
+
+    check_ffmpeg(&args.ffmpeg).context("FFmpeg check failed")?;
+    let codec_outputs: Vec<&Path> = args
+        .output
+        .iter()
+        .chain(&args.also_output)
+        .map(PathBuf::as_path)
+        .collect();
+    let codec = args
+        .codec
+        .map(|codec| resolve_codec(&args.ffmpeg, codec, &codec_outputs, args.codec_fallback))
+        .transpose()
+        .context("Codec check failed")?;
+
+    events.emit(Event::Validated {
+        reference: reference.to_path_buf(),
+        audio: audio.to_path_buf(),
+    });
+    Ok(Inputs {
+        reference,
+        audio,
+        ref_type,
+        codec,
+    })
+}
+
+/// Whether the server answered its health check, and what it advertised.
+pub struct ServerStatus {
+    pub available: bool,
+    pub caps: Option<ServerCapabilities>,
+}
+
+/// Checks the server's health, then the inputs against its capabilities.
+///
+/// An unreachable server isn't an error: the render falls back to a static
+/// video.
+pub async fn connect(
+    args: &Args,
+    client: &MuseTalkClient,
+    inputs: &Inputs<'_>,
+    audio: &mut AudioData,
+    events: &EventStream,
+) -> Result<ServerStatus> {
+    let health = match client.health_check().await {
+        Ok(health) => health,
+        Err(e) => {
+            tracing::warn!("Server not available: {e}");
+            println!("MuseTalk server not available at {}", args.server);
+            println!("Falling back to static video mode (no lip-sync)");
+            return Ok(ServerStatus {
+                available: false,
+                caps: None,
+            });
+        }
+    };
+    println!(
+        "Connected to MuseTalk server: {} (version: {})",
+        health.status,
+        health.version.as_deref().unwrap_or("unknown")
+    );
+    events.emit(Event::ServerConnected {
+        server: args.server.clone(),
+        version: health.version,
+    });
+    let caps = check_server_compatibility(args, client, inputs.ref_type, inputs.reference, audio)
+        .await
+        .context("Server compatibility check failed")?;
+    Ok(ServerStatus {
+        available: true,
+        caps,
+    })
+}
+
+/// The inference options for a render at `fps`.
+pub fn inference_options(
+    args: &Args,
+    caps: Option<&ServerCapabilities>,
+    fps: u32,
+    reference: &LoadedReference,
+) -> Result<InferenceOptions> {
+    Ok(InferenceOptions {
+        model: args.model.clone(),
+        audio_url: args.audio_url.as_ref().map(|url| url.to_string()),
+        output: args
+            .output
+            .as_deref()
+            .filter(|_| args.server_assemble)
+            .and_then(|output| server_assembly_format(caps, output)),
+        resume: caps.is_some_and(|c| c.supports_resume),
+        retry_timeouts: caps.is_some_and(|c| c.idempotent),
+        face_center: face_center(args, reference)?,
+        ..InferenceOptions::new(fps)
+    })
+}
+
+/// An encoded reference, reusable by later renders in the same invocation.
+pub enum LoadedReference {
+    Image(ImageData),
+    Video(VideoData),
+}
+
+impl LoadedReference {
+    pub fn input(&self) -> ReferenceInput<'_> {
+        match self {
+            Self::Image(image) => ReferenceInput::Image(image),
+            Self::Video(video) => ReferenceInput::Video(video),
+        }
+    }
+}
+
+/// Everything a loaded reference depends on besides the shared arguments.
+#[derive(PartialEq)]
+struct ReferenceKey {
+    path: PathBuf,
+    fps: u32,
+    resolution: Resolution,
+    loop_to: Option<f32>,
+    window: Option<(f64, f64)>,
+}
+
+/// The last reference loaded, so `--audio-track` and batch renders of the
+/// same reference encode it once.
+static LAST_REFERENCE: Mutex<Option<(ReferenceKey, Arc<LoadedReference>)>> = Mutex::new(None);
+
+/// Loads and encodes the reference, or reuses the previous render's.
+pub fn load_reference(
+    args: &Args,
+    inputs: &Inputs<'_>,
+    fps: u32,
+    audio_secs: f32,
+    window: Option<(f64, f64)>,
+) -> Result<Arc<LoadedReference>> {
+    let (reference, ref_type) = (inputs.reference, inputs.ref_type);
+    let loop_to = args.reference_loop.then_some(audio_secs);
+    let key = ReferenceKey {
+        path: reference.to_path_buf(),
+        fps,
+        resolution: args.resolution,
+        loop_to: loop_to.filter(|_| ref_type == ReferenceType::Video),
+        window: window.filter(|_| ref_type == ReferenceType::Video),
+    };
+    let mut last = LAST_REFERENCE.lock().unwrap();
+    if let Some((_, loaded)) = last.as_ref().filter(|(k, _)| *k == key) {
+        println!("Reusing encoded reference {}", reference.display());
+        return Ok(loaded.clone());
+    }
+
+    let loaded = match ref_type {
+        ReferenceType::Image => {
+            let options = ImageLoadOptions {
+                png_compression: args.png_compression,
+                color_manage: args.color_manage,
+                tonemap: args.tonemap,
+                max_encoded_bytes: args
+                    .downscale_reference_if_over
+                    .map(|mb| payload::megabytes(mb) as usize),
+                resize_to: Some((args.resolution.width, args.resolution.height)),
+            };
+            let image = load_image_with(reference, &options).context("Failed to load image")?;
+            println!(
+                "Loaded image: {}x{} from {}",
+                image.width,
+                image.height,
+                reference.display()
+            );
+            LoadedReference::Image(image)
+        }
+        ReferenceType::Video => {
+            let spec = args
+                .normalize_reference
+                .then(|| ReferenceSpec::new(args.resolution, fps))
+                .transpose()?;
+            let video =
+                load_video_reference(&args.ffmpeg, reference, spec.as_ref(), loop_to, window)
+                    .context("Failed to load video")?;
+            println!(
+                "Loaded video: {} bytes from {}",
+                video.file_size,
+                reference.display()
+            );
+            LoadedReference::Video(video)
+        }
+    };
+    let loaded = Arc::new(loaded);
+    *last = Some((key, loaded.clone()));
+    Ok(loaded)
+}
+
+/// The `--face-center` to send, checked against an image reference's source
+/// size and moved to where that point landed after resizing.
+///
+/// A video reference's frame size isn't known locally, so it is sent as-is.
+pub fn face_center(args: &Args, reference: &LoadedReference) -> Result<Option<[u32; 2]>> {
+    let Some(center) = args.face_center else {
+        return Ok(None);
+    };
+    match reference {
+        LoadedReference::Image(image) => Ok(Some(center.in_image(image)?)),
+        LoadedReference::Video(_) => Ok(Some(center.to_array())),
+    }
+}
+
+/// Passes frames through `--upscale-server` when one is configured.
+pub async fn upscale_frames(
+    args: &Args,
+    frames: Vec<String>,
+    bundle: Option<&SharedBundle>,
+) -> Result<Vec<String>> {
+    let Some(url) = &args.upscale_server else {
+        return Ok(frames);
+    };
+    let client = UpscaleClient::from_args(url, args).context("Invalid --upscale-server setup")?;
+    println!("Upscaling {} frames via {url}...", frames.len());
+    let start = Instant::now();
+    let frames = client
+        .upscale_frames(frames)
+        .instrument(tracing::info_span!("upscale"))
+        .await;
+    record_timing(bundle, "upscale", start);
+    Ok(frames)
+}
+
+/// Health cache shared by every render in this invocation.
+pub fn health_cache(args: &Args) -> HealthCache {
+    static CACHE: OnceLock<HealthCache> = OnceLock::new();
+    CACHE
+        .get_or_init(|| HealthCache::new(Duration::from_secs(args.health_cache_ttl)))
+        .clone()
+}
+
+pub fn record_timing(bundle: Option<&SharedBundle>, stage: &str, start: Instant) {
+    if let Some(bundle) = bundle {
+        bundle.lock().unwrap().record_timing(stage, start.elapsed());
+    }
+}
+
+pub fn required_path<'a>(path: &'a Option<PathBuf>, flag: &str) -> Result<&'a Path> {
+    path.as_deref()
+        .with_context(|| format!("{flag} is required"))
+}
+
+/// Writes the `--landmarks-out` sidecar, warning when the server sent none.
+pub fn write_landmarks(frames: &[Frame], path: &Path) -> Result<()> {
+    let sidecar = LandmarkSidecar::from_frames(frames);
+    if sidecar.is_empty() {
+        tracing::warn!("Server sent no face landmarks; writing an empty sidecar");
+    }
+    sidecar
+        .write(path)
+        .context("Failed to write landmarks sidecar")?;
+    Ok(())
+}
+
+/// Checks each rendered output against `--assert-duration`.
+///
+/// Named pipes are skipped since they can't be probed after the fact.
+pub fn assert_durations(args: &Args, outputs: &[&Path]) -> Result<()> {
+    let Some(assertion) = &args.assert_duration else {
+        return Ok(());
+    };
+    for output in outputs {
+        if OutputTarget::detect(output) == OutputTarget::Fifo {
+            continue;
+        }
+        let actual = assertion.verify(&args.ffmpeg, output)?;
+        println!(
+            "Duration check passed: {} is {actual:.2}s",
+            output.display()
+        );
+    }
+    Ok(())
+}
+
+/// The frame rate to render at: `--fps`, snapped by `--snap-fps`, then
+/// lowered to fit `--max-frames` if one was given.
+pub fn render_fps(args: &Args, duration_secs: f32) -> u32 {
+    let mut fps = args.fps;
+    if args.snap_fps {
+        match snap_fps(fps) {
+            Some(snapped) if snapped != fps => {
+                tracing::warn!("Snapping {fps} fps to the common rate {snapped} fps (--snap-fps)");
+                fps = snapped;
+            }
+            Some(_) => {}
+            None => tracing::warn!(
+                "{fps} fps is too far from any common rate to snap; keeping it (--snap-fps)"
+            ),
+        }
+    }
+    let Some(max_frames) = args.max_frames else {
+        return fps;
+    };
+    let budget_fps = fps_for_frame_budget(fps, duration_secs, max_frames);
+    if budget_fps < fps {
+        tracing::warn!(
+            "Lowering fps from {fps} to {budget_fps} to stay within {max_frames} frames"
+        );
+    }
+    budget_fps
+}
+
+/// The `--stale-ok` cache key for `render` against `reference`.
+fn last_good_key(render: &Render<'_>, reference: &Path) -> Result<String> {
+    let settings = KeySettings::from_args(render.args, render.fps, render.audio.window);
+    Ok(LastGoodCache::input_key(
+        reference,
+        render.audio.source(),
+        &settings,
+    )?)
+}
+
+/// Serves the last good render of these inputs when the server is down
+/// (`--stale-ok`), failing if there is none.
+pub fn serve_last_good(render: &Render<'_>, reference: &Path) -> Result<()> {
+    let cache = LastGoodCache::new(LastGoodCache::default_dir());
+    let key = last_good_key(render, reference).context("Failed to hash inputs for --stale-ok")?;
+    for output in &render.outputs {
+        let served = cache
+            .restore(&key, output)
+            .context("Failed to copy the cached render")?;
+        anyhow::ensure!(
+            served,
+            "Server unavailable and no previous render of these inputs is cached for --stale-ok"
+        );
+        println!(
+            "WARNING: server unavailable; {} is a STALE copy of the last successful render of these inputs",
+            output.display()
+        );
+    }
+    Ok(())
+}
+
+/// Remembers fresh renders for later `--stale-ok` runs; failures only warn.
+///
+/// Named pipe outputs are skipped: their video went to the reader.
+pub fn remember_last_good(render: &Render<'_>, reference: &Path) {
+    let cache = LastGoodCache::new(LastGoodCache::default_dir());
+    let stored = last_good_key(render, reference).and_then(|key| {
+        for output in render
+            .outputs
+            .iter()
+            .filter(|o| OutputTarget::detect(o).has_size())
+        {
+            cache.store(&key, output)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = stored {
+        tracing::warn!("Failed to cache render for --stale-ok: {e}");
+    }
+}
+
+/// Warns when gain or normalization pushed samples to full scale.
+pub fn warn_clipping(audio: &AudioData, events: &EventStream) {
+    let samples = audio.clip_count();
+    if samples > 0 {
+        tracing::warn!(
+            "{samples} audio samples clip at full scale, which can hurt lip-sync; \
+             try a lower --audio-gain"
+        );
+        events.emit(Event::AudioClipped { samples });
+    }
+}
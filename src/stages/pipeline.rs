@@ -0,0 +1,142 @@
+//! Encoding frames while the server streams them (`--pipeline`).
+//!
+//! FFmpeg runs on a blocking thread fed through a channel, so a slow encoder
+//! never stalls the async stream it is reading from.
+
+use super::{Inference, Render, record_timing};
+use anyhow::{Context, Result};
+use musetalk_cli::Args;
+use musetalk_cli::assembler::{FrameSink, PipeSink, VideoAssembler};
+use musetalk_cli::client::{ServerCapabilities, StreamSummary};
+use musetalk_cli::compat::check_server_warnings;
+use musetalk_cli::error::{CliError, decode_base64};
+use musetalk_cli::events::Event;
+use musetalk_cli::progress::{ProgressDisplay, ProgressEvent};
+use musetalk_cli::throttle::ThrottledLog;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Instant;
+use tracing::Instrument;
+
+/// Whether `--pipeline` applies to this server; warns when it can't.
+pub fn pipelining(args: &Args, caps: Option<&ServerCapabilities>) -> bool {
+    if !args.pipeline {
+        return false;
+    }
+    let supported = caps.is_some_and(|c| c.supports_stream);
+    if !supported {
+        println!("Warning: server does not advertise streaming; ignoring --pipeline");
+    }
+    supported
+}
+
+/// Streams frames from the server straight into FFmpeg, so encoding
+/// overlaps with inference.
+///
+/// Returns the server's warnings.
+pub async fn render_pipelined(
+    render: &Render<'_>,
+    inference: &Inference<'_>,
+    assembler: &VideoAssembler,
+) -> Result<Vec<String>> {
+    println!("Streaming lip-sync inference into the encoder...");
+    let mut display = ProgressDisplay::start(render.args.tui);
+    display.send(ProgressEvent::StageStarted("inference".to_string()));
+    let infer_start = Instant::now();
+    render.events.emit(Event::InferenceStarted);
+    let sink = assembler.pipe_sink(render.audio.muxed_path(), render.output())?;
+    let (sink, summary) = stream_frames(render, inference, sink, &mut display).await?;
+    record_timing(render.bundle, "inference", infer_start);
+    display.send(ProgressEvent::StageFinished {
+        stage: "inference".to_string(),
+        elapsed: infer_start.elapsed(),
+    });
+    render.events.emit(Event::FrameReceived {
+        count: summary.frames,
+    });
+
+    // Frames are already encoded; what is left is FFmpeg finishing the file
+    display.send(ProgressEvent::StageStarted("assembly".to_string()));
+    render.events.emit(Event::Encoding {
+        frames: summary.frames,
+    });
+    let assemble_start = Instant::now();
+    tokio::task::spawn_blocking(move || sink.finish())
+        .await
+        .context("Encoder thread panicked")?
+        .context("Failed to assemble video")?;
+    record_timing(render.bundle, "assembly", assemble_start);
+    display.send(ProgressEvent::StageFinished {
+        stage: "assembly".to_string(),
+        elapsed: assemble_start.elapsed(),
+    });
+    println!("Encoded {} frames as they arrived", summary.frames);
+    Ok(summary.warnings)
+}
+
+/// Streams the render into `sink` on a blocking thread.
+///
+/// Hands the sink back only once the whole stream has arrived and passed
+/// the same checks as a regular response; otherwise FFmpeg is stopped.
+async fn stream_frames(
+    render: &Render<'_>,
+    inference: &Inference<'_>,
+    sink: PipeSink,
+    display: &mut ProgressDisplay,
+) -> Result<(PipeSink, StreamSummary)> {
+    let audio = &render.audio.data;
+    let expected = (audio.duration_secs * inference.options.fps as f32).round() as usize;
+    display.send(ProgressEvent::FramesExpected(expected));
+    let (frames, received) = mpsc::channel();
+    let encoder = tokio::task::spawn_blocking(move || feed_encoder(sink, received));
+    let mut log = ThrottledLog::new("Encoded", expected);
+    let streamed = inference
+        .client
+        .infer_stream(inference.reference, audio, inference.options, |frame| {
+            let png = decode_base64(&frame.data, || format!("frame {}", frame.index))?;
+            display.send(ProgressEvent::Frame {
+                index: frame.index,
+                png: png.clone(),
+            });
+            log.tick();
+            frames
+                .send((frame.index, png))
+                .map_err(|_| CliError::Video("FFmpeg stopped accepting frames".to_string()))
+        })
+        .instrument(tracing::info_span!("inference"))
+        .await;
+    drop(frames);
+    log.finish();
+    // An encoder failure explains a failed send better than the stream does
+    let sink = encoder
+        .await
+        .context("Encoder thread panicked")?
+        .context("Failed to assemble video")?;
+    let checked = streamed.and_then(|summary| {
+        check_server_warnings(&summary.warnings, render.args.strict)?;
+        Ok(summary)
+    });
+    match checked {
+        Ok(summary) => Ok((sink, summary)),
+        Err(e) => {
+            sink.abort();
+            Err(e).context("Streaming inference failed")
+        }
+    }
+}
+
+/// Writes each received frame to FFmpeg until the stream closes the channel.
+///
+/// Returns the sink unfinished, so the caller decides whether to finish or
+/// abort it; on a write error FFmpeg is stopped here.
+fn feed_encoder(
+    mut sink: PipeSink,
+    frames: Receiver<(usize, Vec<u8>)>,
+) -> musetalk_cli::error::Result<PipeSink> {
+    for (index, png) in frames {
+        if let Err(e) = sink.write_frame(index, &png) {
+            sink.abort();
+            return Err(e);
+        }
+    }
+    Ok(sink)
+}
@@ -0,0 +1,271 @@
+//! Producing the video: through the server, or the static fallback without it.
+
+use super::pipeline::{pipelining, render_pipelined};
+use super::{PreparedAudio, record_timing, serve_last_good, upscale_frames, write_landmarks};
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use musetalk_cli::assembler::{VideoAssembler, VideoCodec, write_frames, write_server_video};
+use musetalk_cli::client::{
+    Frame, InferenceOptions, MuseTalkClient, ReferenceInput, ServerCapabilities,
+};
+use musetalk_cli::compat::check_server_warnings;
+use musetalk_cli::debug_bundle::SharedBundle;
+use musetalk_cli::events::{Event, EventStream};
+use musetalk_cli::frame_map::FrameManifest;
+use musetalk_cli::loader::load_image;
+use musetalk_cli::preview::{PreviewRequest, open_in_player, render_preview};
+use musetalk_cli::progress::{ProgressDisplay, ProgressEvent, ProgressSink};
+use musetalk_cli::smoke::warm_up;
+use musetalk_cli::{Args, ReferenceType};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::Instrument;
+
+/// What every stage of one render shares.
+pub struct Render<'a> {
+    pub args: &'a Args,
+    pub bundle: Option<&'a SharedBundle>,
+    pub events: &'a EventStream,
+    pub audio: &'a PreparedAudio,
+    /// `--output` first, then each `--also-output`.
+    pub outputs: Vec<&'a Path>,
+    pub fps: u32,
+}
+
+impl<'a> Render<'a> {
+    /// A render of `audio` at `fps` into `--output` and each `--also-output`.
+    pub fn new(
+        args: &'a Args,
+        bundle: Option<&'a SharedBundle>,
+        events: &'a EventStream,
+        audio: &'a PreparedAudio,
+        fps: u32,
+    ) -> Result<Self> {
+        let output = super::required_path(&args.output, "--output")?;
+        Ok(Self {
+            args,
+            bundle,
+            events,
+            audio,
+            outputs: std::iter::once(output)
+                .chain(args.also_output.iter().map(PathBuf::as_path))
+                .collect(),
+            fps,
+        })
+    }
+
+    /// The primary `--output`.
+    pub fn output(&self) -> &Path {
+        self.outputs[0]
+    }
+
+    /// An assembler for the outputs, muxing the prepared audio.
+    pub fn assembler(&self, codec: Option<VideoCodec>) -> Result<VideoAssembler> {
+        let mut assembler = VideoAssembler::from_args(
+            self.args,
+            self.fps,
+            self.audio.data.duration_secs,
+            self.bundle,
+        )?;
+        if let Some(codec) = codec {
+            assembler = assembler.with_video_codec(codec);
+        }
+        if self.args.auto_audio {
+            assembler = assembler.with_audio_passthrough(self.audio.muxed_path());
+        }
+        Ok(assembler)
+    }
+}
+
+/// How to ask a reachable server for the render.
+pub struct Inference<'a> {
+    pub client: &'a MuseTalkClient,
+    pub caps: Option<&'a ServerCapabilities>,
+    pub reference: ReferenceInput<'a>,
+    pub options: &'a InferenceOptions,
+}
+
+/// Renders through the server, after the `--warmup` and `--preview-stream`
+/// requests when asked for.
+///
+/// Returns the server's warnings.
+pub async fn render_with_server(
+    render: &Render<'_>,
+    inference: &Inference<'_>,
+    assembler: &VideoAssembler,
+) -> Result<Vec<String>> {
+    if render.args.warmup {
+        println!("Warming up the model...");
+        warm_up(inference.client, inference.caps, inference.options).await?;
+    }
+    if render.args.preview_stream {
+        preview(render, inference).await?;
+    }
+    if pipelining(render.args, inference.caps) {
+        return render_pipelined(render, inference, assembler).await;
+    }
+    render_frames(render, inference, assembler).await
+}
+
+/// Renders and opens a low-res preview, when the server supports one.
+async fn preview(render: &Render<'_>, inference: &Inference<'_>) -> Result<()> {
+    let request = PreviewRequest {
+        reference: inference.reference,
+        audio: &render.audio.data,
+        audio_path: render.audio.muxed_path(),
+        options: inference.options,
+        ffmpeg: &render.args.ffmpeg,
+    };
+    println!("Requesting preview...");
+    match render_preview(inference.client, inference.caps, request, render.output())
+        .await
+        .context("Preview failed")?
+    {
+        Some(path) => {
+            println!("Preview ready: {}", path.display());
+            if let Err(e) = open_in_player(&path) {
+                tracing::warn!("Could not open preview: {e}");
+            }
+        }
+        None => tracing::warn!("Server does not support previews; skipping"),
+    }
+    Ok(())
+}
+
+/// Requests every frame at once, then assembles them (or writes the
+/// server-assembled video).
+async fn render_frames(
+    render: &Render<'_>,
+    inference: &Inference<'_>,
+    assembler: &VideoAssembler,
+) -> Result<Vec<String>> {
+    println!("Requesting lip-sync inference...");
+    let mut display = ProgressDisplay::start(render.args.tui);
+    display.send(ProgressEvent::StageStarted("inference".to_string()));
+    let infer_start = Instant::now();
+    render.events.emit(Event::InferenceStarted);
+    let response = inference
+        .client
+        .infer(inference.reference, &render.audio.data, inference.options)
+        .instrument(tracing::info_span!("inference"))
+        .await
+        .context("Inference request failed")?;
+    record_timing(render.bundle, "inference", infer_start);
+    check_server_warnings(&response.warnings, render.args.strict)?;
+    display.send(ProgressEvent::StageFinished {
+        stage: "inference".to_string(),
+        elapsed: infer_start.elapsed(),
+    });
+
+    if let Some(video) = &response.video_out {
+        let size = write_server_video(video, &render.outputs)
+            .context("Failed to write server-assembled video")?;
+        println!("Received server-assembled video ({size} bytes)");
+    } else {
+        println!(
+            "Received {} frames, assembling video...",
+            response.total_frames
+        );
+        let frames = receive_frames(render, response.frames, &mut display).await?;
+        assemble(render, assembler, &frames, &mut display)?;
+    }
+    Ok(response.warnings)
+}
+
+/// Announces the returned frames, writes their sidecars, and upscales them.
+async fn receive_frames(
+    render: &Render<'_>,
+    frames: Vec<Frame>,
+    display: &mut ProgressDisplay,
+) -> Result<Vec<String>> {
+    display.send(ProgressEvent::FramesExpected(frames.len()));
+    render.events.emit(Event::FrameReceived {
+        count: frames.len(),
+    });
+    if let Some(path) = &render.args.landmarks_out {
+        write_landmarks(&frames, path)?;
+    }
+    let frames: Vec<String> = frames.into_iter().map(|f| f.data).collect();
+    if let Some(path) = &render.args.frame_manifest {
+        FrameManifest::from_chunks(
+            render.fps,
+            &[(f64::from(render.audio.data.duration_secs), frames.len())],
+        )
+        .write(path)
+        .context("Failed to write frame manifest")?;
+    }
+    upscale_frames(render.args, frames, render.bundle).await
+}
+
+/// Encodes `frames` into every output.
+fn assemble(
+    render: &Render<'_>,
+    assembler: &VideoAssembler,
+    frames: &[String],
+    display: &mut ProgressDisplay,
+) -> Result<()> {
+    display.send(ProgressEvent::StageStarted("assembly".to_string()));
+    render.events.emit(Event::Encoding {
+        frames: frames.len(),
+    });
+    let assemble_start = Instant::now();
+    let sink = render.outputs[1..].iter().fold(
+        assembler.sink(render.audio.muxed_path(), render.output()),
+        |sink, extra| sink.with_output(extra),
+    );
+    let bar = if display.is_active() {
+        ProgressBar::hidden()
+    } else {
+        assembler.frame_bar(frames.len())
+    };
+    let sink = ProgressSink::new(sink, |e| {
+        bar.inc(1);
+        display.send(e);
+    });
+    let result = tracing::info_span!("assembly").in_scope(|| write_frames(frames, sink));
+    bar.finish_and_clear();
+    result.context("Failed to assemble video")?;
+    record_timing(render.bundle, "assembly", assemble_start);
+    display.send(ProgressEvent::StageFinished {
+        stage: "assembly".to_string(),
+        elapsed: assemble_start.elapsed(),
+    });
+    Ok(())
+}
+
+/// Renders without a server: a static video of an image reference, or the
+/// `--stale-ok` copy of a video reference's last render.
+pub fn render_offline(
+    render: &Render<'_>,
+    reference: &Path,
+    ref_type: ReferenceType,
+    assembler: &VideoAssembler,
+) -> Result<()> {
+    match ref_type {
+        ReferenceType::Image => {
+            let image_data = load_image(reference).context("Failed to load image")?;
+            println!("Creating static video...");
+            render.events.emit(Event::Encoding { frames: 0 });
+            for output in &render.outputs {
+                assembler
+                    .assemble_static(
+                        &image_data,
+                        &render.audio.data,
+                        reference,
+                        render.audio.muxed_path(),
+                        output,
+                    )
+                    .context("Failed to create static video")?;
+            }
+            Ok(())
+        }
+        ReferenceType::Video if render.args.stale_ok => serve_last_good(render, reference),
+        ReferenceType::Video => {
+            println!("Warning: Video reference requires server connection.");
+            println!("Cannot create fallback video from video reference.");
+            Err(anyhow::anyhow!(
+                "Server unavailable and video reference cannot be used for static fallback"
+            ))
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Test helpers shared across modules.
+//!
+//! Provides a minimal HTTP/1.1 mock server so client behavior can be tested
+//! without a real MuseTalk backend.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A request captured by the mock server.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Parses the request body as JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+/// A canned response returned by the mock server.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub delay: Option<Duration>,
+    /// Pause between body lines, to mimic a server streaming as it renders.
+    pub line_delay: Option<Duration>,
+    /// Body bytes sent before the connection is dropped.
+    pub drop_after: Option<usize>,
+}
+
+impl MockResponse {
+    /// Creates a 200 response with a JSON body.
+    pub fn json(body: serde_json::Value) -> Self {
+        Self::status(200).with_body(body.to_string())
+    }
+
+    /// Creates an empty response with the given status code.
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: String::new(),
+            delay: None,
+            line_delay: None,
+            drop_after: None,
+        }
+    }
+
+    /// Sets the response body.
+    pub fn with_body(mut self, body: String) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Adds a response header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Delays the response by the given duration.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sends the body one line at a time, pausing `delay` between lines.
+    pub fn with_line_delay(mut self, delay: Duration) -> Self {
+        self.line_delay = Some(delay);
+        self
+    }
+
+    /// Drops the connection after `bytes` of the body, still announcing the
+    /// full `Content-Length`.
+    pub fn with_drop_after(mut self, bytes: usize) -> Self {
+        self.drop_after = Some(bytes);
+        self
+    }
+}
+
+type Handler = dyn Fn(&RecordedRequest) -> MockResponse + Send + Sync;
+
+/// A mock HTTP server bound to a random local port.
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Starts a server that answers every request with `handler`.
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let recorded = Arc::clone(&recorded);
+                let handler = Arc::clone(&handler);
+                tokio::spawn(async move {
+                    serve_connection(stream, recorded, handler).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Starts a server with a healthy `/health` and the given `/infer` handler.
+    pub async fn with_infer<F>(infer: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        Self::start(move |req| match req.path.as_str() {
+            "/health" => health_response(),
+            "/infer" => infer(req),
+            _ => MockResponse::status(404),
+        })
+        .await
+    }
+
+    /// Base URL of the server (no trailing slash).
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Requests received for the given path.
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.path == path)
+            .collect()
+    }
+}
+
+/// A standard healthy `/health` response.
+pub fn health_response() -> MockResponse {
+    MockResponse::json(serde_json::json!({"status": "ok", "version": "1.5"}))
+}
+
+/// A successful `/infer` response with `count` tiny frames.
+pub fn frames_response(count: usize) -> MockResponse {
+    frames_range_response(0..count, count)
+}
+
+/// A successful `/infer` response carrying frames `range` of a
+/// `total`-frame render, as a resuming server sends them.
+pub fn frames_range_response(range: Range<usize>, total: usize) -> MockResponse {
+    png_frames_response(range, total, &tiny_png_base64())
+}
+
+/// A successful `/infer` response with `count` 1x1 frames of color `rgb`.
+pub fn solid_frames_response(count: usize, rgb: [u8; 3]) -> MockResponse {
+    png_frames_response(0..count, count, &solid_png_base64(rgb))
+}
+
+fn png_frames_response(range: Range<usize>, total: usize, data: &str) -> MockResponse {
+    let frames: Vec<_> = range
+        .map(|i| serde_json::json!({"index": i, "data": data}))
+        .collect();
+    MockResponse::json(serde_json::json!({
+        "status": "success",
+        "total_frames": total,
+        "frames": frames,
+    }))
+}
+
+/// Frames `range` as newline-delimited JSON, as `/infer/stream` sends them.
+pub fn ndjson_frames(range: Range<usize>) -> String {
+    let data = tiny_png_base64();
+    range
+        .map(|i| format!("{}\n", serde_json::json!({"index": i, "data": data})))
+        .collect()
+}
+
+/// A one-second silent mono audio fixture.
+pub fn test_audio() -> crate::loader::AudioData {
+    crate::loader::AudioData {
+        sample_rate: 16000,
+        channels: 1,
+        duration_secs: 1.0,
+        samples: vec![0.0; 16000],
+        base64_wav: "UklGRg==".to_string(),
+    }
+}
+
+/// A 1x1 black image fixture.
+pub fn test_image() -> crate::loader::ImageData {
+    crate::loader::ImageData {
+        width: 1,
+        height: 1,
+        rgb_data: vec![0, 0, 0],
+        base64_png: "iVBORw0KGgo=".to_string(),
+        source_size: (1, 1),
+    }
+}
+
+/// Base64 of a 1x1 black PNG.
+pub fn tiny_png_base64() -> String {
+    solid_png_base64([0, 0, 0])
+}
+
+/// Base64 of a 1x1 PNG of color `rgb`.
+pub fn solid_png_base64(rgb: [u8; 3]) -> String {
+    use base64::Engine;
+    let img = image::RgbImage::from_pixel(1, 1, image::Rgb(rgb));
+    let mut bytes = Vec::new();
+    img.write_to(
+        &mut std::io::Cursor::new(&mut bytes),
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+async fn serve_connection(
+    mut stream: tokio::net::TcpStream,
+    recorded: Arc<Mutex<Vec<RecordedRequest>>>,
+    handler: Arc<Handler>,
+) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    recorded.lock().unwrap().push(request.clone());
+
+    let response = handler(&request);
+    if let Some(delay) = response.delay {
+        tokio::time::sleep(delay).await;
+    }
+
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    if !response
+        .headers
+        .iter()
+        .any(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+    {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let body = &response.body[..response.drop_after.unwrap_or(response.body.len())];
+    match response.line_delay {
+        Some(delay) => {
+            for line in body.split_inclusive('\n') {
+                let _ = stream.write_all(line.as_bytes()).await;
+                let _ = stream.flush().await;
+                tokio::time::sleep(delay).await;
+            }
+        }
+        None => {
+            let _ = stream.write_all(body.as_bytes()).await;
+        }
+    }
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<RecordedRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let body = String::from_utf8_lossy(&buf[header_end..]).to_string();
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
@@ -0,0 +1,92 @@
+//! Rate-limited progress logging for per-frame loops.
+//!
+//! One log line per frame floods the terminal under `--verbose`. A
+//! [`ThrottledLog`] counts events instead and logs a summary such as
+//! "Wrote 500/1000 frames" at most once per interval, plus once at the end.
+
+use std::time::{Duration, Instant};
+
+/// Default minimum time between summaries.
+pub const DEFAULT_LOG_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Coalesces per-frame events into periodic debug summaries.
+#[derive(Debug)]
+pub struct ThrottledLog {
+    action: &'static str,
+    total: usize,
+    interval: Duration,
+    count: usize,
+    logged: usize,
+    last: Instant,
+}
+
+impl ThrottledLog {
+    /// Counts `total` frames, summarized as "`action` N/total frames".
+    pub fn new(action: &'static str, total: usize) -> Self {
+        Self {
+            action,
+            total,
+            interval: DEFAULT_LOG_INTERVAL,
+            count: 0,
+            logged: 0,
+            last: Instant::now(),
+        }
+    }
+
+    /// Logs at most once per `interval` instead of [`DEFAULT_LOG_INTERVAL`].
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Counts one frame, logging a summary if the interval has passed.
+    pub fn tick(&mut self) {
+        if let Some(line) = self.tick_at(Instant::now()) {
+            tracing::debug!("{line}");
+        }
+    }
+
+    /// Logs the final count, unless the last summary already showed it.
+    pub fn finish(self) {
+        if self.count > self.logged {
+            tracing::debug!("{}", self.summary());
+        }
+    }
+
+    /// Counts one frame at `now`, returning the summary to log, if any.
+    fn tick_at(&mut self, now: Instant) -> Option<String> {
+        self.count += 1;
+        if now.saturating_duration_since(self.last) < self.interval {
+            return None;
+        }
+        self.last = now;
+        self.logged = self.count;
+        Some(self.summary())
+    }
+
+    fn summary(&self) -> String {
+        format!("{} {}/{} frames", self.action, self.count, self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_events_coalesced() {
+        let start = Instant::now();
+        let mut log = ThrottledLog::new("Received", 1000).with_interval(Duration::from_millis(100));
+        log.last = start;
+
+        // 1000 frames arriving 1 ms apart
+        let lines: Vec<_> = (1..=1000)
+            .filter_map(|i| log.tick_at(start + Duration::from_millis(i)))
+            .collect();
+
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "Received 100/1000 frames");
+        assert_eq!(lines[9], "Received 1000/1000 frames");
+        assert_eq!(log.logged, 1000);
+    }
+}
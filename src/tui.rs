@@ -0,0 +1,150 @@
+//! Full-screen progress display for `--tui`.
+//!
+//! Shows the current stage, a frame progress bar, an ASCII thumbnail of the
+//! latest frame, and finished stage timings.
+
+use crate::progress::{ProgressEvent, ProgressState};
+use ratatui::DefaultTerminal;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::widgets::{Block, Gauge, Paragraph};
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+/// Minimum time between redraws triggered by frame events.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Brightness ramp for thumbnails, from dark to light.
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// The running TUI; the terminal is restored when it is dropped.
+pub struct Tui {
+    terminal: DefaultTerminal,
+    state: ProgressState,
+    last_draw: Option<Instant>,
+}
+
+impl Tui {
+    /// Takes over the terminal, or returns `None` if it is not interactive.
+    pub fn start() -> Option<Self> {
+        let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+        if dumb || !std::io::stdout().is_terminal() {
+            return None;
+        }
+        let terminal = ratatui::try_init().ok()?;
+        Some(Self {
+            terminal,
+            state: ProgressState::default(),
+            last_draw: None,
+        })
+    }
+
+    /// Applies an event and redraws, throttling per-frame redraws.
+    pub fn update(&mut self, event: ProgressEvent) {
+        let is_frame = matches!(event, ProgressEvent::Frame { .. });
+        self.state.apply(event);
+        let due = self
+            .last_draw
+            .is_none_or(|t| t.elapsed() >= REDRAW_INTERVAL);
+        if !is_frame || due {
+            self.draw();
+        }
+    }
+
+    fn draw(&mut self) {
+        // Clear first so stray log lines are painted over
+        let _ = self.terminal.clear();
+        let state = &self.state;
+        let _ = self.terminal.draw(|frame| render(frame, state));
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+fn render(frame: &mut ratatui::Frame, state: &ProgressState) {
+    let [header, body] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(frame.area());
+    let [thumbnail, timings] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    let stage = state.stage.as_deref().unwrap_or("done");
+    let label = match state.frames_expected {
+        Some(total) => format!("{} / {total} frames", state.frames_done),
+        None => "waiting for frames".to_string(),
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(format!(" musetalk-cli: {stage} ")))
+            .ratio(state.fraction())
+            .label(label),
+        header,
+    );
+
+    let block = Block::bordered().title(" Latest frame ");
+    let inner = block.inner(thumbnail);
+    let lines = state
+        .latest_frame
+        .as_ref()
+        .and_then(|(_, png)| ascii_thumbnail(png, inner.width, inner.height))
+        .unwrap_or_default();
+    frame.render_widget(Paragraph::new(lines.join("\n")).block(block), thumbnail);
+
+    let text: Vec<_> = state
+        .timings
+        .iter()
+        .map(|(stage, elapsed)| format!("{stage:<12} {:>8.2}s", elapsed.as_secs_f64()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(text.join("\n")).block(Block::bordered().title(" Stages ")),
+        timings,
+    );
+}
+
+/// Renders PNG bytes as `rows` lines of `cols` ASCII characters.
+pub fn ascii_thumbnail(png: &[u8], cols: u16, rows: u16) -> Option<Vec<String>> {
+    if cols == 0 || rows == 0 {
+        return None;
+    }
+    let img = image::load_from_memory(png).ok()?.to_luma8();
+    let small = image::imageops::resize(
+        &img,
+        u32::from(cols),
+        u32::from(rows),
+        image::imageops::FilterType::Triangle,
+    );
+    let lines = small
+        .rows()
+        .map(|row| {
+            row.map(|pixel| {
+                let level = usize::from(pixel.0[0]) * (ASCII_RAMP.len() - 1) / 255;
+                ASCII_RAMP[level] as char
+            })
+            .collect()
+        })
+        .collect();
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_thumbnail_dimensions_and_ramp() {
+        let img =
+            image::GrayImage::from_fn(8, 4, |x, _| image::Luma([if x < 4 { 0 } else { 255 }]));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let lines = ascii_thumbnail(&png, 8, 2).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() == 8));
+        assert!(lines[0].starts_with(' ') && lines[0].ends_with('@'));
+        assert_eq!(ascii_thumbnail(b"not a png", 8, 2), None);
+    }
+}
@@ -0,0 +1,270 @@
+//! Input validation for CLI arguments.
+
+use crate::assembler::{Container, OutputTarget};
+use crate::error::{CliError, Result};
+use crate::loader::AudioData;
+use std::path::Path;
+
+/// Supported image extensions, including common JPEG aliases.
+const SUPPORTED_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "jfif", "jpe", "webp", "bmp"];
+
+/// Supported video extensions.
+const SUPPORTED_VIDEO_EXTENSIONS: &[&str] = &["mp4"];
+
+/// Supported audio extensions.
+const SUPPORTED_AUDIO_EXTENSIONS: &[&str] = &["wav", "mp3", "flac"];
+
+/// Reference input type (image or video).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceType {
+    /// Static image (PNG/JPEG/WebP/BMP).
+    Image,
+    /// Video file (MP4).
+    Video,
+}
+
+/// Validates the reference file path.
+///
+/// Checks that:
+/// - The file exists
+/// - The extension is a supported reference format (PNG, JPEG, WebP, BMP, MP4)
+///
+/// Returns the detected reference type.
+pub fn validate_reference_path(path: &Path) -> Result<ReferenceType> {
+    // Check file exists
+    if !path.exists() {
+        return Err(CliError::ReferenceNotFound(path.to_path_buf()));
+    }
+
+    // Check extension
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    if SUPPORTED_IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        return Ok(ReferenceType::Image);
+    }
+
+    if SUPPORTED_VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+        return Ok(ReferenceType::Video);
+    }
+
+    Err(CliError::UnsupportedReferenceFormat(ext))
+}
+
+/// Returns true if the path has an image extension.
+pub fn is_image_reference(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SUPPORTED_IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Returns true if the path has a video extension.
+pub fn is_video_reference(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SUPPORTED_VIDEO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Returns true if the path has a supported audio extension.
+pub fn is_audio_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SUPPORTED_AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Validates the audio file path.
+///
+/// Checks that:
+/// - The file exists
+/// - The extension is a supported audio format
+pub fn validate_audio_path(path: &Path) -> Result<()> {
+    // Check file exists
+    if !path.exists() {
+        return Err(CliError::AudioNotFound(path.to_path_buf()));
+    }
+
+    // Check extension
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    if !SUPPORTED_AUDIO_EXTENSIONS.contains(&ext.as_str()) {
+        return Err(CliError::UnsupportedAudioFormat(ext));
+    }
+
+    Ok(())
+}
+
+/// Validates the output path.
+///
+/// Checks that the parent directory exists and, unless the output is a
+/// named pipe, that the extension is a supported video container.
+pub fn validate_output_path(path: &Path) -> Result<()> {
+    // Get parent directory (or current dir if no parent or empty parent)
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let parent = parent.unwrap_or(Path::new("."));
+
+    // Check parent directory exists
+    if !parent.exists() {
+        return Err(CliError::InvalidOutputPath(format!(
+            "{} (directory does not exist)",
+            path.display()
+        )));
+    }
+
+    if OutputTarget::detect(path) == OutputTarget::File {
+        Container::from_path(path)?;
+    }
+    Ok(())
+}
+
+/// Validates all input arguments.
+///
+/// Returns the detected reference type (image or video).
+pub fn validate_inputs(reference: &Path, audio: &Path, output: &Path) -> Result<ReferenceType> {
+    let ref_type = validate_reference_path(reference)?;
+    validate_audio_path(audio)?;
+    validate_output_path(output)?;
+    Ok(ref_type)
+}
+
+/// Checks a reference video's codec against the server's supported formats.
+///
+/// An empty `supported` list means the server did not advertise formats,
+/// so every codec is accepted.
+pub fn check_reference_codec(codec: &str, supported: &[String]) -> Result<()> {
+    if supported.is_empty() || supported.iter().any(|f| f.eq_ignore_ascii_case(codec)) {
+        return Ok(());
+    }
+    Err(CliError::UnsupportedCodec(format!(
+        "reference uses {codec}, server supports {}. Transcode first, e.g. \
+         ffmpeg -i input -c:v libx264 -pix_fmt yuv420p reference.mp4",
+        supported.join(", ")
+    )))
+}
+
+/// Validates a requested model against the server's advertised models.
+///
+/// An empty `available` list means the server did not advertise models,
+/// so the name is passed through unchecked.
+pub fn validate_model(model: &str, available: &[String]) -> Result<()> {
+    if available.is_empty() || available.iter().any(|m| m == model) {
+        return Ok(());
+    }
+    Err(CliError::UnknownModel(format!(
+        "{model} (available: {})",
+        available.join(", ")
+    )))
+}
+
+/// Checks a requested frame rate against the server's `max_fps`, if any.
+pub fn check_fps(fps: u32, max_fps: Option<u32>) -> Result<()> {
+    match max_fps {
+        Some(max) if fps > max => Err(CliError::UnsupportedFps(format!(
+            "{fps} fps exceeds the server maximum of {max} fps"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Outcome of checking the audio channel count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelCheck {
+    /// Audio is mono; nothing to do.
+    Mono,
+    /// Multi-channel audio the server accepts; warn and suggest `--mono`.
+    Warn(String),
+    /// The server requires mono; downmix automatically.
+    Downmix(String),
+}
+
+/// Checks the channel count against the server's expectations.
+pub fn check_audio_channels(channels: u16, requires_mono: bool) -> ChannelCheck {
+    if channels <= 1 {
+        ChannelCheck::Mono
+    } else if requires_mono {
+        ChannelCheck::Downmix(format!(
+            "Server requires mono audio, downmixing {channels} channels"
+        ))
+    } else {
+        ChannelCheck::Warn(format!(
+            "Audio has {channels} channels; MuseTalk works best with mono (try --mono)"
+        ))
+    }
+}
+
+/// Sample rates MuseTalk's audio encoder handles well.
+pub const COMMON_SAMPLE_RATES: &[u32] = &[16000, 22050, 24000, 44100, 48000];
+
+/// Checks that the audio sample rate is one of [`COMMON_SAMPLE_RATES`].
+///
+/// Oddball rates such as 8000 or 11025 Hz load fine but degrade lip-sync.
+pub fn check_sample_rate(sample_rate: u32) -> Result<()> {
+    if COMMON_SAMPLE_RATES.contains(&sample_rate) {
+        return Ok(());
+    }
+    Err(CliError::UnusualSampleRate(format!(
+        "{sample_rate} Hz; results may be poor. Convert it with --preprocess-audio \
+         or --target-sample-rate 16000, or use a 16000 Hz or higher recording"
+    )))
+}
+
+/// Default minimum audio duration in seconds.
+pub const DEFAULT_MIN_AUDIO_DURATION: f64 = 0.1;
+
+/// Validates that loaded audio is long enough to produce a video.
+///
+/// Zero-length or truncated audio otherwise surfaces as a confusing
+/// FFmpeg failure after inference.
+pub fn validate_audio_duration(audio: &AudioData, min_secs: f64) -> Result<()> {
+    if f64::from(audio.duration_secs) < min_secs {
+        return Err(CliError::AudioLoad(format!(
+            "Audio is too short ({:.3}s, minimum {min_secs:.3}s)",
+            audio.duration_secs
+        )));
+    }
+    Ok(())
+}
+
+/// Computes an fps that keeps the total frame count within `max_frames`.
+///
+/// Returns the requested fps unchanged when it already fits the budget,
+/// otherwise the largest integer fps (at least 1) that does.
+pub fn fps_for_frame_budget(requested_fps: u32, duration_secs: f32, max_frames: u32) -> u32 {
+    if duration_secs <= 0.0 {
+        return requested_fps;
+    }
+    let budget_fps = (max_frames as f32 / duration_secs).floor() as u32;
+    requested_fps.min(budget_fps).max(1)
+}
+
+/// Frame rates players and muxers handle without timing quirks.
+pub const COMMON_FRAME_RATES: &[u32] = &[24, 25, 30, 50, 60];
+
+/// Largest change, as a fraction of the requested rate, `--snap-fps` makes.
+pub const MAX_SNAP_FRACTION: f64 = 0.1;
+
+/// Snaps `fps` to the nearest of [`COMMON_FRAME_RATES`] (`--snap-fps`).
+///
+/// Common rates are returned unchanged; a rate halfway between two is
+/// snapped down. Returns `None` when the nearest common rate is more than
+/// [`MAX_SNAP_FRACTION`] away, since that would change the video's pacing
+/// rather than just round it.
+pub fn snap_fps(fps: u32) -> Option<u32> {
+    COMMON_FRAME_RATES
+        .iter()
+        .copied()
+        .min_by_key(|rate| rate.abs_diff(fps))
+        .filter(|rate| f64::from(rate.abs_diff(fps)) <= f64::from(fps) * MAX_SNAP_FRACTION)
+}
+
+#[cfg(test)]
+mod tests;
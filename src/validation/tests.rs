@@ -0,0 +1,337 @@
+//! Input validation tests.
+
+use super::*;
+use std::fs::File;
+use tempfile::tempdir;
+
+#[test]
+fn test_validate_reference_not_found() {
+    let result = validate_reference_path(Path::new("nonexistent.png"));
+    assert!(matches!(result, Err(CliError::ReferenceNotFound(_))));
+}
+
+#[test]
+fn test_validate_reference_unsupported_format() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("image.gif");
+    File::create(&path).unwrap();
+
+    let result = validate_reference_path(&path);
+    assert!(matches!(
+        result,
+        Err(CliError::UnsupportedReferenceFormat(_))
+    ));
+}
+
+#[test]
+fn test_validate_reference_png_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("image.png");
+    File::create(&path).unwrap();
+
+    let result = validate_reference_path(&path);
+    assert_eq!(result.unwrap(), ReferenceType::Image);
+}
+
+#[test]
+fn test_validate_reference_jpeg_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("image.jpeg");
+    File::create(&path).unwrap();
+
+    let result = validate_reference_path(&path);
+    assert_eq!(result.unwrap(), ReferenceType::Image);
+}
+
+#[test]
+fn test_validate_reference_jpg_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("image.jpg");
+    File::create(&path).unwrap();
+
+    let result = validate_reference_path(&path);
+    assert_eq!(result.unwrap(), ReferenceType::Image);
+}
+
+#[test]
+fn test_validate_reference_jpeg_alias_success() {
+    let dir = tempdir().unwrap();
+    for name in ["image.jfif", "image.jpe"] {
+        let path = dir.path().join(name);
+        File::create(&path).unwrap();
+        assert_eq!(
+            validate_reference_path(&path).unwrap(),
+            ReferenceType::Image
+        );
+    }
+}
+
+#[test]
+fn test_validate_reference_webp_and_bmp_success() {
+    let dir = tempdir().unwrap();
+    for name in ["image.webp", "image.BMP"] {
+        let path = dir.path().join(name);
+        File::create(&path).unwrap();
+        assert_eq!(
+            validate_reference_path(&path).unwrap(),
+            ReferenceType::Image
+        );
+        assert!(is_image_reference(&path));
+    }
+}
+
+#[test]
+fn test_validate_reference_mp4_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("video.mp4");
+    File::create(&path).unwrap();
+
+    let result = validate_reference_path(&path);
+    assert_eq!(result.unwrap(), ReferenceType::Video);
+}
+
+#[test]
+fn test_is_image_reference() {
+    assert!(is_image_reference(Path::new("test.png")));
+    assert!(is_image_reference(Path::new("test.jpg")));
+    assert!(is_image_reference(Path::new("test.jpeg")));
+    assert!(is_image_reference(Path::new("test.jfif")));
+    assert!(is_image_reference(Path::new("test.JPE")));
+    assert!(!is_image_reference(Path::new("test.mp4")));
+    assert!(!is_image_reference(Path::new("test.wav")));
+}
+
+#[test]
+fn test_is_video_reference() {
+    assert!(is_video_reference(Path::new("test.mp4")));
+    assert!(!is_video_reference(Path::new("test.png")));
+    assert!(!is_video_reference(Path::new("test.jpg")));
+    assert!(!is_video_reference(Path::new("test.wav")));
+}
+
+#[test]
+fn test_validate_audio_not_found() {
+    let result = validate_audio_path(Path::new("nonexistent.wav"));
+    assert!(matches!(result, Err(CliError::AudioNotFound(_))));
+}
+
+#[test]
+fn test_validate_audio_unsupported_format() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("audio.ogg");
+    File::create(&path).unwrap();
+
+    let result = validate_audio_path(&path);
+    assert!(matches!(result, Err(CliError::UnsupportedAudioFormat(_))));
+}
+
+#[test]
+fn test_validate_audio_wav_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("audio.wav");
+    File::create(&path).unwrap();
+
+    let result = validate_audio_path(&path);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_validate_audio_mp3_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("audio.mp3");
+    File::create(&path).unwrap();
+
+    let result = validate_audio_path(&path);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_validate_audio_flac_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("audio.flac");
+    File::create(&path).unwrap();
+
+    let result = validate_audio_path(&path);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_validate_output_invalid_parent() {
+    let result = validate_output_path(Path::new("/nonexistent/dir/output.mp4"));
+    assert!(matches!(result, Err(CliError::InvalidOutputPath(_))));
+}
+
+#[test]
+fn test_validate_output_success() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("output.mp4");
+
+    let result = validate_output_path(&path);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_validate_output_relative_path() {
+    // Relative path like "output.mp4" should be valid (parent is ".")
+    let path = Path::new("output.mp4");
+    let result = validate_output_path(path);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_validate_audio_duration_rejects_near_empty_wav() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("short.wav");
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    for _ in 0..10 {
+        writer.write_sample(0i16).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    let audio = crate::loader::load_audio(&path).unwrap();
+    let result = validate_audio_duration(&audio, DEFAULT_MIN_AUDIO_DURATION);
+    assert!(matches!(result, Err(CliError::AudioLoad(_))));
+    assert!(validate_audio_duration(&audio, 0.0).is_ok());
+}
+
+#[test]
+fn test_check_reference_codec() {
+    let supported = vec!["h264".to_string(), "mpeg4".to_string()];
+    assert!(check_reference_codec("h264", &supported).is_ok());
+    assert!(check_reference_codec("H264", &supported).is_ok());
+    assert!(matches!(
+        check_reference_codec("hevc", &supported),
+        Err(CliError::UnsupportedCodec(_))
+    ));
+    assert!(matches!(
+        check_reference_codec("av1", &supported),
+        Err(CliError::UnsupportedCodec(_))
+    ));
+    // No advertised list accepts anything
+    assert!(check_reference_codec("av1", &[]).is_ok());
+}
+
+#[test]
+fn test_validate_model() {
+    let models = vec!["musetalk-v1".to_string(), "musetalk-v15".to_string()];
+    assert!(validate_model("musetalk-v15", &models).is_ok());
+    assert!(matches!(
+        validate_model("musetalk-v9", &models),
+        Err(CliError::UnknownModel(_))
+    ));
+    // Without a capability list any name passes through
+    assert!(validate_model("musetalk-v9", &[]).is_ok());
+}
+
+#[test]
+fn test_check_fps() {
+    assert!(check_fps(30, Some(30)).is_ok());
+    assert!(matches!(
+        check_fps(60, Some(30)),
+        Err(CliError::UnsupportedFps(_))
+    ));
+    assert!(check_fps(60, None).is_ok());
+}
+
+#[test]
+fn test_check_audio_channels() {
+    assert_eq!(check_audio_channels(1, true), ChannelCheck::Mono);
+    assert!(matches!(
+        check_audio_channels(2, false),
+        ChannelCheck::Warn(_)
+    ));
+    assert!(matches!(
+        check_audio_channels(2, true),
+        ChannelCheck::Downmix(_)
+    ));
+}
+
+#[test]
+fn test_check_sample_rate() {
+    for rate in COMMON_SAMPLE_RATES {
+        assert!(check_sample_rate(*rate).is_ok());
+    }
+    assert!(matches!(
+        check_sample_rate(8000),
+        Err(CliError::UnusualSampleRate(msg))
+            if msg.contains("--preprocess-audio") && msg.contains("--target-sample-rate 16000")
+    ));
+    assert!(check_sample_rate(11025).is_err());
+}
+
+#[test]
+fn test_fps_for_frame_budget_lowers_fps() {
+    // 10s of audio with a 150 frame budget allows at most 15 fps
+    assert_eq!(fps_for_frame_budget(30, 10.0, 150), 15);
+    // Budget not evenly divisible rounds down
+    assert_eq!(fps_for_frame_budget(30, 7.0, 100), 14);
+}
+
+#[test]
+fn test_fps_for_frame_budget_keeps_fps_within_budget() {
+    assert_eq!(fps_for_frame_budget(25, 2.0, 1000), 25);
+    assert_eq!(fps_for_frame_budget(30, 0.0, 10), 30);
+    // Never drops below 1 fps
+    assert_eq!(fps_for_frame_budget(30, 100.0, 10), 1);
+}
+
+#[test]
+fn test_snap_fps_to_common_rate() {
+    for common in COMMON_FRAME_RATES {
+        assert_eq!(snap_fps(*common), Some(*common));
+    }
+    assert_eq!(snap_fps(23), Some(24));
+    assert_eq!(snap_fps(29), Some(30));
+    assert_eq!(snap_fps(47), Some(50));
+    // Too far from any common rate to snap without changing the pacing
+    assert_eq!(snap_fps(1), None);
+    assert_eq!(snap_fps(40), None);
+    assert_eq!(snap_fps(144), None);
+}
+
+#[test]
+fn test_validate_inputs_image_valid() {
+    let dir = tempdir().unwrap();
+    let reference = dir.path().join("avatar.png");
+    let audio = dir.path().join("speech.wav");
+    let output = dir.path().join("output.mp4");
+
+    File::create(&reference).unwrap();
+    File::create(&audio).unwrap();
+
+    let result = validate_inputs(&reference, &audio, &output);
+    assert_eq!(result.unwrap(), ReferenceType::Image);
+}
+
+#[test]
+fn test_validate_inputs_video_valid() {
+    let dir = tempdir().unwrap();
+    let reference = dir.path().join("avatar.mp4");
+    let audio = dir.path().join("speech.wav");
+    let output = dir.path().join("output.mp4");
+
+    File::create(&reference).unwrap();
+    File::create(&audio).unwrap();
+
+    let result = validate_inputs(&reference, &audio, &output);
+    assert_eq!(result.unwrap(), ReferenceType::Video);
+}
+
+#[test]
+fn test_validate_inputs_reference_not_found() {
+    let dir = tempdir().unwrap();
+    let reference = dir.path().join("nonexistent.png");
+    let audio = dir.path().join("speech.wav");
+    let output = dir.path().join("output.mp4");
+
+    File::create(&audio).unwrap();
+
+    let result = validate_inputs(&reference, &audio, &output);
+    assert!(matches!(result, Err(CliError::ReferenceNotFound(_))));
+}
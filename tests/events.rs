@@ -0,0 +1,355 @@
+//! End-to-end runs of the binary against a mock server: the `--events`
+//! stream and the render paths around it.
+#![cfg(unix)]
+
+use base64::Engine;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+/// Serves `/health` and `/infer` (three frames) until the test exits.
+fn start_server() -> String {
+    serve(false)
+}
+
+/// Like [`start_server`], but advertises streaming and streams `/infer` as
+/// NDJSON that breaks off after the first frame.
+fn start_truncating_stream_server() -> String {
+    serve(true)
+}
+
+fn serve(truncated_stream: bool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            respond(stream, truncated_stream);
+        }
+    });
+    url
+}
+
+fn respond(mut stream: std::net::TcpStream, truncated_stream: bool) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        let Ok(n) = stream.read(&mut chunk) else {
+            return;
+        };
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+    let length: usize = head
+        .lines()
+        .find_map(|l| l.strip_prefix("content-length:"))
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + length {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+
+    let path = head.split_whitespace().nth(1).unwrap_or("");
+    if truncated_stream && path == "/infer" {
+        let line = serde_json::json!({"index": 0, "data": tiny_png_base64()});
+        let body = format!("{line}\n");
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 Mock\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        return;
+    }
+    let (status, body) = match path {
+        "/health" => (200, serde_json::json!({"status": "ok", "version": "1.5"})),
+        "/capabilities" if truncated_stream => (200, serde_json::json!({"supports_stream": true})),
+        "/infer" => {
+            let frames: Vec<_> = (0..3)
+                .map(|i| serde_json::json!({"index": i, "data": tiny_png_base64()}))
+                .collect();
+            let body =
+                serde_json::json!({"status": "success", "total_frames": 3, "frames": frames});
+            (200, body)
+        }
+        _ => (404, serde_json::json!({})),
+    };
+    let body = body.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+fn tiny_png_base64() -> String {
+    let mut bytes = Vec::new();
+    image::RgbImage::new(1, 1)
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// An FFmpeg stand-in that reports a version, drains piped frames, and
+/// writes its output argument.
+fn write_ffmpeg_stub(dir: &Path) -> std::path::PathBuf {
+    let stub = dir.join("ffmpeg");
+    std::fs::write(
+        &stub,
+        "#!/bin/sh\nfor last; do :; done\n\
+         if [ \"$last\" = -version ]; then echo 'ffmpeg version stub'; else cat > /dev/null; echo ok > \"$last\"; fi\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+    stub
+}
+
+fn write_inputs(dir: &Path) {
+    image::RgbImage::new(8, 8)
+        .save(dir.join("avatar.png"))
+        .unwrap();
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(dir.join("speech.wav"), spec).unwrap();
+    for _ in 0..16000 {
+        writer.write_sample(0i16).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn test_full_run_emits_lifecycle_events_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    write_inputs(dir.path());
+    let ffmpeg = write_ffmpeg_stub(dir.path());
+    let server = start_server();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_musetalk-cli"))
+        .current_dir(dir.path())
+        .args([
+            "-r",
+            "avatar.png",
+            "-a",
+            "speech.wav",
+            "-o",
+            "out.mp4",
+            "--events",
+            "-q",
+        ])
+        .args(["--server", &server, "--fps", "3"])
+        .arg("--ffmpeg-path")
+        .arg(&ffmpeg)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let names: Vec<_> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "validated",
+            "audio_loaded",
+            "server_connected",
+            "inference_started",
+            "frame_received",
+            "encoding",
+            "done"
+        ]
+    );
+    assert_eq!(events[4]["count"], 3);
+    assert_eq!(events[6]["outputs"][0], "out.mp4");
+    assert!(dir.path().join("out.mp4").exists());
+}
+
+#[test]
+fn test_audio_tracks_render_one_output_per_language() {
+    let dir = tempfile::tempdir().unwrap();
+    write_inputs(dir.path());
+    std::fs::copy(dir.path().join("speech.wav"), dir.path().join("rede.wav")).unwrap();
+    let ffmpeg = write_ffmpeg_stub(dir.path());
+    let server = start_server();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_musetalk-cli"))
+        .current_dir(dir.path())
+        .args(["-r", "avatar.png", "-o", "talk.mp4", "--events", "-q"])
+        .args([
+            "--audio-track",
+            "en=speech.wav",
+            "--audio-track",
+            "de=rede.wav",
+        ])
+        .args(["--tag-language", "--server", &server, "--fps", "3"])
+        .arg("--ffmpeg-path")
+        .arg(&ffmpeg)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let outputs: Vec<String> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|e| e["event"] == "done")
+        .map(|e| e["outputs"][0].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(outputs, ["talk.en.mp4", "talk.de.mp4"]);
+    assert!(dir.path().join("talk.de.mp4").exists());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Reusing encoded reference"), "{stdout}");
+}
+
+#[test]
+fn test_pipeline_fails_on_truncated_stream() {
+    let dir = tempfile::tempdir().unwrap();
+    write_inputs(dir.path());
+    let ffmpeg = write_ffmpeg_stub(dir.path());
+    let server = start_truncating_stream_server();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_musetalk-cli"))
+        .current_dir(dir.path())
+        .args(["-r", "avatar.png", "-a", "speech.wav", "-o", "out.mp4"])
+        .args(["--pipeline", "--events", "-q"])
+        .args(["--server", &server, "--fps", "3"])
+        .arg("--ffmpeg-path")
+        .arg(&ffmpeg)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{output:?}");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Response ended early"), "{stderr}");
+    assert!(!stderr.contains("\"event\":\"done\""), "{stderr}");
+    assert!(!dir.path().join("out.mp4").exists());
+}
+
+#[test]
+fn test_repaired_wav_is_muxed() {
+    let dir = tempfile::tempdir().unwrap();
+    write_inputs(dir.path());
+    // Raw 16 kHz mono PCM with no header at all
+    std::fs::write(dir.path().join("raw.wav"), vec![0u8; 32000]).unwrap();
+    let ffmpeg = dir.path().join("ffmpeg");
+    // Keeps a copy of the audio it was asked to mux
+    std::fs::write(
+        &ffmpeg,
+        "#!/bin/sh\nprev=\nfor last; do\n\
+         if [ \"$prev\" = -i ]; then case \"$last\" in *.wav) cp \"$last\" muxed.wav;; esac; fi\n\
+         prev=$last\ndone\n\
+         if [ \"$last\" = -version ]; then echo 'ffmpeg version stub'; else echo ok > \"$last\"; fi\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let server = start_server();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_musetalk-cli"))
+        .current_dir(dir.path())
+        .args(["-r", "avatar.png", "-a", "raw.wav", "-o", "out.mp4"])
+        .args(["--repair-wav", "-q", "--server", &server, "--fps", "3"])
+        .arg("--ffmpeg-path")
+        .arg(&ffmpeg)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let muxed = hound::WavReader::open(dir.path().join("muxed.wav")).unwrap();
+    assert_eq!(muxed.spec().sample_rate, 16000);
+    assert_eq!(muxed.duration(), 16000);
+}
+
+#[test]
+fn test_stale_ok_serves_every_output_with_server_down() {
+    let dir = tempfile::tempdir().unwrap();
+    write_inputs(dir.path());
+    std::fs::write(dir.path().join("talk.mp4"), b"reference video").unwrap();
+    let ffmpeg = write_ffmpeg_stub(dir.path());
+    let down = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let render = |server: &str, extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_musetalk-cli"))
+            .current_dir(dir.path())
+            .env("XDG_CACHE_HOME", dir.path().join("cache"))
+            .args(["-r", "talk.mp4", "-a", "speech.wav", "-o", "out.mp4"])
+            .args(["--also-output", "out.webm", "--stale-ok", "-q"])
+            .args(["--server", server, "--fps", "3"])
+            .args(extra)
+            .arg("--ffmpeg-path")
+            .arg(&ffmpeg)
+            .output()
+            .unwrap()
+    };
+
+    let fresh = render(&start_server(), &[]);
+    assert!(fresh.status.success(), "{fresh:?}");
+    for output in ["out.mp4", "out.webm"] {
+        std::fs::remove_file(dir.path().join(output)).unwrap();
+    }
+
+    let stale = render(&down, &[]);
+    assert!(stale.status.success(), "{stale:?}");
+    for output in ["out.mp4", "out.webm"] {
+        assert!(dir.path().join(output).exists(), "{output} not restored");
+    }
+    // A different lead-in renders different audio, so nothing is cached for it
+    let changed = render(&down, &["--lead-in", "100"]);
+    assert!(!changed.status.success(), "{changed:?}");
+}
+
+#[test]
+fn test_failing_post_hook_runs_on_every_output_and_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    write_inputs(dir.path());
+    let ffmpeg = write_ffmpeg_stub(dir.path());
+    let hook = dir.path().join("post hook.sh");
+    // Logs each output it is run on, then fails
+    std::fs::write(&hook, "#!/bin/sh\necho \"$1\" >> hooks.log\nexit 1\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let server = start_server();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_musetalk-cli"))
+        .current_dir(dir.path())
+        .args(["-r", "avatar.png", "-a", "speech.wav", "-o", "out.mp4"])
+        .args(["--also-output", "out.webm"])
+        .args(["--server", &server, "--fps", "3"])
+        .arg("--post-hook")
+        .arg(format!("'{}'", hook.display()))
+        .arg("--ffmpeg-path")
+        .arg(&ffmpeg)
+        .args(["-q", "--events"])
+        .output()
+        .unwrap();
+    // Not the 1 of a failed render
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+
+    let log = std::fs::read_to_string(dir.path().join("hooks.log")).unwrap();
+    assert_eq!(log.lines().collect::<Vec<_>>(), ["out.mp4", "out.webm"]);
+    assert!(dir.path().join("out.mp4").exists());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Post-hook failed for out.webm"), "{stderr}");
+    assert_eq!(stderr.matches("\"event\":\"post_hook_failed\"").count(), 2);
+    assert!(stderr.contains("\"event\":\"done\""), "{stderr}");
+    assert!(!stderr.contains("\"event\":\"error\""), "{stderr}");
+}